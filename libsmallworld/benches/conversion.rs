@@ -0,0 +1,98 @@
+//! Benchmarks for converting openingTitle.arc files that already
+//! contain all six regions (the most common real-world input).
+//!
+//! Run with `cargo bench -p libsmallworld`.
+
+use std::io::Cursor;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use libsmallworld::u8_fnt::{self, U8FileNode, U8FolderNode, U8Node};
+use libsmallworld::{
+    convert_openingtitle_between_regions, ConvertOpeningTitleBetweenRegionsConflictStrategies,
+    Region, RegionBitFlags,
+};
+
+/// Size of each regional file. Large enough that comparing file data
+/// dominates the conversion time.
+const FILE_SIZE: u32 = 0x100000;
+
+/// Build an openingTitle.arc containing all six regions. Each region
+/// is assigned to a group (by index into `Region::ALL`), and every
+/// region in a group shares the same copy of each file. All copies
+/// have identical data, so there are no conflicts.
+fn make_all_region_arc(groups: [usize; 6]) -> Vec<u8> {
+    let num_groups = groups.iter().max().unwrap() + 1;
+
+    let mut anim = U8FolderNode::new();
+    let mut blyt = U8FolderNode::new();
+    for ((_, filenames), group) in Region::all_filenames().zip(groups) {
+        let file = |slot: u32| {
+            let offset = (slot * num_groups as u32 + group as u32) * FILE_SIZE;
+            U8Node::File(U8FileNode {
+                offset,
+                size: FILE_SIZE,
+            })
+        };
+        anim.extend([
+            (filenames.in_press_brlan.to_owned(), file(0)),
+            (filenames.in_title_brlan.to_owned(), file(1)),
+            (filenames.loop_press_brlan.to_owned(), file(2)),
+            (filenames.out_press_brlan.to_owned(), file(3)),
+        ]);
+        blyt.insert(filenames.brlyt.to_owned(), file(4));
+    }
+    let root = U8Node::Folder(U8FolderNode::from([(
+        "arc".to_owned(),
+        U8Node::Folder(U8FolderNode::from([
+            ("anim".to_owned(), U8Node::Folder(anim)),
+            ("blyt".to_owned(), U8Node::Folder(blyt)),
+        ])),
+    )]));
+
+    let mut arc = Cursor::new(Vec::new());
+    u8_fnt::write(&mut arc, &root).unwrap();
+    let mut arc = arc.into_inner();
+
+    // Every copy of a given slot has the same data
+    for slot in 0..5 {
+        for _ in 0..num_groups {
+            arc.extend((0..FILE_SIZE).map(|i| (i as u8) ^ slot));
+        }
+    }
+    arc
+}
+
+fn convert(arc: &[u8]) {
+    convert_openingtitle_between_regions(
+        Cursor::new(arc),
+        Cursor::new(Vec::new()),
+        None,
+        RegionBitFlags::ALL,
+        &ConvertOpeningTitleBetweenRegionsConflictStrategies::default(),
+    )
+    .unwrap();
+}
+
+fn bench_all_regions(c: &mut Criterion) {
+    let mut group = c.benchmark_group("all regions present");
+    group.sample_size(20);
+
+    // Typical smallworld output: everything is already shared
+    let arc = make_all_region_arc([0, 0, 0, 0, 0, 0]);
+    group.bench_function("fully shared", |b| b.iter(|| convert(&arc)));
+
+    // Two groups of regions (e.g. a region-free arc that was partially
+    // re-saved in another tool). Only two digests per file are needed.
+    let arc = make_all_region_arc([0, 0, 0, 1, 1, 1]);
+    group.bench_function("two groups", |b| b.iter(|| convert(&arc)));
+
+    // Every region has its own copy, so every copy must be compared
+    let arc = make_all_region_arc([0, 1, 2, 3, 4, 5]);
+    group.bench_function("no sharing", |b| b.iter(|| convert(&arc)));
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_all_regions);
+criterion_main!(benches);
@@ -0,0 +1,61 @@
+//! Minimal WASM bindings for libsmallworld, as an example of using the
+//! in-memory conversion API from JavaScript.
+//!
+//! Build with `cargo build --example wasm --features wasm --target
+//! wasm32-unknown-unknown`, and then run `wasm-bindgen --target web
+//! --out-dir pkg` on the resulting .wasm file. From JavaScript:
+//!
+//! ```js
+//! import init, { convert_to_region_free } from "./pkg/wasm.js";
+//! await init();
+//! const output = convert_to_region_free(new Uint8Array(arcBytes));
+//! ```
+
+use std::error::Error;
+
+use libsmallworld as lib;
+use wasm_bindgen::prelude::*;
+
+/// Turn an error and all of its sources into a single message, since JS
+/// only gets to see the string.
+fn js_error(error: &dyn Error) -> JsError {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(error) = source {
+        message.push_str(&format!(": {error}"));
+        source = error.source();
+    }
+    JsError::new(&message)
+}
+
+/// Convert an openingTitle.arc to region-free. Conflicting regional
+/// files are reported as errors.
+#[wasm_bindgen]
+pub fn convert_to_region_free(input: &[u8]) -> Result<Vec<u8>, JsError> {
+    convert(input, "all", "all")
+}
+
+/// Convert an openingTitle.arc between regions, given as comma-separated
+/// region letters (e.g. "P,E") or "all".
+#[wasm_bindgen]
+pub fn convert(input: &[u8], from: &str, to: &str) -> Result<Vec<u8>, JsError> {
+    let parse_regions = |arg: &str| -> Result<Vec<lib::Region>, JsError> {
+        if arg.eq_ignore_ascii_case("all") {
+            return Ok(lib::Region::DEFAULT_ORDER.to_vec());
+        }
+        arg.split(',')
+            .map(|region| region.trim().parse().map_err(|e| js_error(&e)))
+            .collect()
+    };
+    let from_regions = parse_regions(from)?;
+    let to_regions = lib::RegionBitFlags::from_iter(parse_regions(to)?);
+
+    let (output, _) = lib::convert_openingtitle_between_regions_in_memory(
+        input,
+        Some(&from_regions),
+        to_regions,
+        &lib::ConvertOpeningTitleBetweenRegionsConflictStrategies::default(),
+    )
+    .map_err(|e| js_error(&e))?;
+    Ok(output)
+}
@@ -0,0 +1,426 @@
+//! A map with string keys that are compared ASCII-case-insensitively,
+//! the same way U8 archive lookups work.
+
+use std::collections::{hash_map, HashMap};
+use std::fmt;
+
+/// A `HashMap`-like map from names to values, where names are looked
+/// up ASCII-case-insensitively. Each entry keeps the name it was
+/// inserted with, so the original spelling is preserved.
+///
+/// This is what `U8FolderNode` is. A U8 archive can still contain two
+/// names that only differ in case (the game will only ever find one of
+/// them), so the map can hold both if they're added with `.insert()`;
+/// `.try_insert()` refuses to, and `U8Node::validate()` reports them.
+/// When several entries match a lookup, the one spelled exactly like
+/// the name wins, and otherwise the one inserted first does.
+///
+/// The order entries were inserted in is also tracked, for
+/// `.iter_insertion_order()`. It isn't taken into account when
+/// comparing maps.
+#[derive(Clone)]
+pub struct CaseInsensitiveMap<V> {
+    /// Maps each case-folded name to the entries with that name, in
+    /// insertion order. The `Vec`s are never empty, and almost always
+    /// have exactly one entry.
+    entries: HashMap<String, Vec<Entry<V>>>,
+    /// The `seq` to give the next new entry.
+    next_seq: u64,
+}
+
+/// A single entry in a `CaseInsensitiveMap`.
+#[derive(Clone)]
+struct Entry<V> {
+    name: String,
+    value: V,
+    /// Increases with each new entry, so sorting by it gives the
+    /// insertion order.
+    seq: u64,
+}
+
+/// Fold a name into the key used for lookups.
+fn fold(name: &str) -> String {
+    name.to_ascii_lowercase()
+}
+
+/// The index of the entry a lookup for `name` should find, out of
+/// entries whose names all match it case-insensitively.
+fn lookup_index<V>(entries: &[Entry<V>], name: &str) -> usize {
+    entries
+        .iter()
+        .position(|entry| entry.name == name)
+        .unwrap_or(0)
+}
+
+/// The error returned by `CaseInsensitiveMap::try_insert()` if the
+/// name only differs in case from one already in the map.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CaseCollision<V> {
+    /// The name that's already in the map.
+    pub existing: String,
+    /// The name that was being inserted.
+    pub name: String,
+    /// The value that was being inserted.
+    pub value: V,
+}
+
+impl<V> CaseInsensitiveMap<V> {
+    /// Create an empty map.
+    pub fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+            next_seq: 0,
+        }
+    }
+
+    /// The number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.values().map(Vec::len).sum()
+    }
+
+    /// Whether there are no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Insert a value. If there's already an entry with exactly the same
+    /// name, it's replaced, and its old value is returned. A name that
+    /// only differs in case from an existing one is added alongside it.
+    /// A replaced entry keeps its place in the insertion order.
+    pub fn insert(&mut self, name: String, value: V) -> Option<V> {
+        let entries = self.entries.entry(fold(&name)).or_default();
+        match entries.iter_mut().find(|entry| entry.name == name) {
+            Some(entry) => Some(std::mem::replace(&mut entry.value, value)),
+            None => {
+                entries.push(Entry {
+                    name,
+                    value,
+                    seq: self.next_seq,
+                });
+                self.next_seq += 1;
+                None
+            }
+        }
+    }
+
+    /// Like `.insert()`, but fails instead of adding a name that only
+    /// differs in case from an existing one.
+    pub fn try_insert(&mut self, name: String, value: V) -> Result<Option<V>, CaseCollision<V>> {
+        if let Some((existing, _)) = self.get_key_value(&name) {
+            if *existing != name {
+                return Err(CaseCollision {
+                    existing: existing.clone(),
+                    name,
+                    value,
+                });
+            }
+        }
+        Ok(self.insert(name, value))
+    }
+
+    /// Get the value for a name, case-insensitively.
+    pub fn get(&self, name: &str) -> Option<&V> {
+        self.get_key_value(name).map(|(_, value)| value)
+    }
+
+    /// Mutable version of `.get()`.
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut V> {
+        let entries = self.entries.get_mut(&fold(name))?;
+        let i = lookup_index(entries, name);
+        Some(&mut entries[i].value)
+    }
+
+    /// Like `.get()`, but also returns the name as it's actually
+    /// spelled in the map.
+    pub fn get_key_value(&self, name: &str) -> Option<(&String, &V)> {
+        let entries = self.entries.get(&fold(name))?;
+        let entry = &entries[lookup_index(entries, name)];
+        Some((&entry.name, &entry.value))
+    }
+
+    /// Whether there's an entry for a name, case-insensitively.
+    pub fn contains_key(&self, name: &str) -> bool {
+        self.entries.contains_key(&fold(name))
+    }
+
+    /// Remove the entry for a name (case-insensitively), returning its
+    /// value.
+    pub fn remove(&mut self, name: &str) -> Option<V> {
+        self.remove_entry(name).map(|(_, value)| value)
+    }
+
+    /// Like `.remove()`, but also returns the name as it was spelled
+    /// in the map.
+    pub fn remove_entry(&mut self, name: &str) -> Option<(String, V)> {
+        let key = fold(name);
+        let entries = self.entries.get_mut(&key)?;
+        let entry = entries.remove(lookup_index(entries, name));
+        if entries.is_empty() {
+            self.entries.remove(&key);
+        }
+        Some((entry.name, entry.value))
+    }
+
+    /// Keep only the entries for which `f` returns `true`.
+    pub fn retain<F: FnMut(&String, &mut V) -> bool>(&mut self, mut f: F) {
+        self.entries.retain(|_, entries| {
+            entries.retain_mut(|entry| f(&entry.name, &mut entry.value));
+            !entries.is_empty()
+        });
+    }
+
+    /// Iterate over groups of two or more names that only differ in
+    /// case, in arbitrary order.
+    pub fn case_collisions(&self) -> impl Iterator<Item = Vec<&String>> {
+        self.entries
+            .values()
+            .filter(|entries| entries.len() > 1)
+            .map(|entries| entries.iter().map(|entry| &entry.name).collect())
+    }
+
+    /// Iterate over the names, in arbitrary order.
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        self.iter().map(|(name, _)| name)
+    }
+
+    /// Iterate over the values, in arbitrary order.
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, value)| value)
+    }
+
+    /// Mutable version of `.values()`.
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut V> {
+        self.iter_mut().map(|(_, value)| value)
+    }
+
+    /// Iterate over the entries, in arbitrary order.
+    pub fn iter(&self) -> Iter<'_, V> {
+        Iter {
+            inner: self.entries.values().flatten(),
+        }
+    }
+
+    /// Mutable version of `.iter()`. Only the values can be changed.
+    pub fn iter_mut(&mut self) -> IterMut<'_, V> {
+        IterMut {
+            inner: self.entries.values_mut().flatten(),
+        }
+    }
+
+    /// Iterate over the entries in the order they were first inserted.
+    pub fn iter_insertion_order(&self) -> std::vec::IntoIter<(&String, &V)> {
+        let mut entries: Vec<&Entry<V>> = self.entries.values().flatten().collect();
+        entries.sort_unstable_by_key(|entry| entry.seq);
+        entries
+            .into_iter()
+            .map(|entry| (&entry.name, &entry.value))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
+
+/// Compares names (exactly) and values, but not insertion order.
+impl<V: PartialEq> PartialEq for CaseInsensitiveMap<V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len()
+            && self.iter().all(|(name, value)| {
+                other.entries.get(&fold(name)).is_some_and(|entries| {
+                    entries
+                        .iter()
+                        .any(|entry| entry.name == *name && entry.value == *value)
+                })
+            })
+    }
+}
+
+impl<V: Eq> Eq for CaseInsensitiveMap<V> {}
+
+/// The iterator returned by `CaseInsensitiveMap::iter()`.
+pub struct Iter<'a, V> {
+    inner: std::iter::Flatten<hash_map::Values<'a, String, Vec<Entry<V>>>>,
+}
+
+impl<'a, V> Iterator for Iter<'a, V> {
+    type Item = (&'a String, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|entry| (&entry.name, &entry.value))
+    }
+}
+
+/// The iterator returned by `CaseInsensitiveMap::iter_mut()`.
+pub struct IterMut<'a, V> {
+    inner: std::iter::Flatten<hash_map::ValuesMut<'a, String, Vec<Entry<V>>>>,
+}
+
+impl<'a, V> Iterator for IterMut<'a, V> {
+    type Item = (&'a String, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next()
+            .map(|entry| (&entry.name, &mut entry.value))
+    }
+}
+
+/// The iterator returned by `CaseInsensitiveMap::into_iter()`.
+pub struct IntoIter<V> {
+    inner: std::iter::Flatten<hash_map::IntoValues<String, Vec<Entry<V>>>>,
+}
+
+impl<V> Iterator for IntoIter<V> {
+    type Item = (String, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|entry| (entry.name, entry.value))
+    }
+}
+
+impl<V> Default for CaseInsensitiveMap<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V: fmt::Debug> fmt::Debug for CaseInsensitiveMap<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<V> Extend<(String, V)> for CaseInsensitiveMap<V> {
+    fn extend<I: IntoIterator<Item = (String, V)>>(&mut self, iter: I) {
+        for (name, value) in iter {
+            self.insert(name, value);
+        }
+    }
+}
+
+impl<V> FromIterator<(String, V)> for CaseInsensitiveMap<V> {
+    fn from_iter<I: IntoIterator<Item = (String, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<V, const N: usize> From<[(String, V); N]> for CaseInsensitiveMap<V> {
+    fn from(entries: [(String, V); N]) -> Self {
+        entries.into_iter().collect()
+    }
+}
+
+impl<V> IntoIterator for CaseInsensitiveMap<V> {
+    type Item = (String, V);
+    type IntoIter = IntoIter<V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            inner: self.entries.into_values().flatten(),
+        }
+    }
+}
+
+impl<'a, V> IntoIterator for &'a CaseInsensitiveMap<V> {
+    type Item = (&'a String, &'a V);
+    type IntoIter = Iter<'a, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, V> IntoIterator for &'a mut CaseInsensitiveMap<V> {
+    type Item = (&'a String, &'a mut V);
+    type IntoIter = IterMut<'a, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unnecessary_wraps)]
+mod tests {
+    use super::*;
+
+    type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+    #[test]
+    fn test_lookups() -> TestResult {
+        let mut map = CaseInsensitiveMap::from([("Title.BRLYT".to_owned(), 1)]);
+        assert_eq!(map.get("title.brlyt"), Some(&1));
+        assert!(map.contains_key("TITLE.brlyt"));
+        assert_eq!(
+            map.get_key_value("title.brlyt"),
+            Some((&"Title.BRLYT".to_owned(), &1))
+        );
+
+        // Only ASCII letters are case-folded
+        map.insert("É".to_owned(), 2);
+        assert_eq!(map.get("é"), None);
+        assert_eq!(map.get("É"), Some(&2));
+
+        assert_eq!(map.remove("TITLE.BRLYT"), Some(1));
+        assert_eq!(map.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_insertion_order() -> TestResult {
+        let mut map = CaseInsensitiveMap::new();
+        for (i, name) in ["c", "a", "B", "b", "d"].into_iter().enumerate() {
+            map.insert(name.to_owned(), i);
+        }
+        map.remove("d");
+        map.insert("c".to_owned(), 5);
+        map.insert("d".to_owned(), 6);
+
+        assert_eq!(
+            map.iter_insertion_order()
+                .map(|(name, &value)| (name.as_str(), value))
+                .collect::<Vec<_>>(),
+            [("c", 5), ("a", 1), ("B", 2), ("b", 3), ("d", 6)]
+        );
+
+        // ...but it doesn't affect comparisons
+        let reversed: CaseInsensitiveMap<_> = map
+            .iter_insertion_order()
+            .rev()
+            .map(|(name, &value)| (name.clone(), value))
+            .collect();
+        assert_eq!(map, reversed);
+        Ok(())
+    }
+
+    #[test]
+    fn test_case_collision() -> TestResult {
+        let mut map = CaseInsensitiveMap::new();
+        assert_eq!(map.try_insert("a.brlan".to_owned(), 1), Ok(None));
+        assert_eq!(
+            map.try_insert("A.brlan".to_owned(), 2),
+            Err(CaseCollision {
+                existing: "a.brlan".to_owned(),
+                name: "A.brlan".to_owned(),
+                value: 2,
+            })
+        );
+        assert_eq!(map.try_insert("a.brlan".to_owned(), 3), Ok(Some(1)));
+        assert_eq!(map.case_collisions().count(), 0);
+
+        // Plain `.insert()` keeps both, and exact spellings win lookups
+        assert_eq!(map.insert("A.brlan".to_owned(), 4), None);
+        assert_eq!(map.len(), 2);
+        assert_eq!(
+            map.case_collisions().collect::<Vec<_>>(),
+            [["a.brlan", "A.brlan"]]
+        );
+        assert_eq!(map.get("A.brlan"), Some(&4));
+        assert_eq!(map.get("a.brlan"), Some(&3));
+        assert_eq!(map.get("A.BRLAN"), Some(&3));
+
+        assert_eq!(map.remove_entry("A.BRLAN"), Some(("a.brlan".to_owned(), 3)));
+        assert_eq!(map.keys().collect::<Vec<_>>(), ["A.brlan"]);
+        Ok(())
+    }
+}
@@ -0,0 +1,114 @@
+//! Support for comparing the contents of two U8 archives, ignoring
+//! differences in byte layout (file order, data offsets, padding, and
+//! so on).
+
+use std::collections::BTreeMap;
+use std::io::{Read, Seek};
+
+use crate::u8_fnt::{self, U8Node};
+use crate::util;
+use crate::{ConvertOpeningTitleBetweenRegionsError, IoContext};
+
+/// Hash the contents of every file in an arc, by path.
+fn hash_all_files<SR: Seek + Read>(
+    mut file: SR,
+) -> Result<BTreeMap<String, u64>, ConvertOpeningTitleBetweenRegionsError> {
+    /// Helper function to hash the files in a node, recursively
+    fn visit_node<SR: Seek + Read>(
+        node: &U8Node,
+        path: &str,
+        data_table_offs: u32,
+        file: &mut SR,
+        hashes: &mut BTreeMap<String, u64>,
+    ) -> Result<(), ConvertOpeningTitleBetweenRegionsError> {
+        for (name, child) in node.iter() {
+            let child_path = format!("{path}/{name}");
+            match child {
+                U8Node::File(node) => {
+                    let hash = util::calc_hash_from_file_slice(
+                        file,
+                        u64::from(data_table_offs) + u64::from(node.offset),
+                        node.size.try_into().unwrap(),
+                        false,
+                    )
+                    .io_context("hashing file for comparison")?;
+                    hashes.insert(child_path, hash);
+                }
+                U8Node::Folder(_) => {
+                    visit_node(child, &child_path, data_table_offs, file, hashes)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    let (root, data_table_offs) = u8_fnt::read(&mut file)?;
+    let mut hashes = BTreeMap::new();
+    visit_node(&root, "", data_table_offs, &mut file, &mut hashes)?;
+    Ok(hashes)
+}
+
+/// Compare the contents of two U8 archives: the paths of all of their
+/// files, and the data in each one. Differences in byte layout are
+/// ignored.
+///
+/// Returns the first path (alphabetically) that's missing from one of
+/// the arcs or has different data in each, or `None` if the contents
+/// are identical. Empty folders aren't compared.
+pub fn first_arc_content_difference<A: Seek + Read, B: Seek + Read>(
+    a: A,
+    b: B,
+) -> Result<Option<String>, ConvertOpeningTitleBetweenRegionsError> {
+    let a = hash_all_files(a)?;
+    let b = hash_all_files(b)?;
+
+    let mut paths: Vec<&String> = a.keys().chain(b.keys()).collect();
+    paths.sort();
+    Ok(paths
+        .into_iter()
+        .find(|path| a.get(*path) != b.get(*path))
+        .cloned())
+}
+
+/// Check whether two U8 archives have the same contents. See
+/// `first_arc_content_difference()`, which also reports where the
+/// first difference is.
+pub fn arcs_content_equal<A: Seek + Read, B: Seek + Read>(
+    a: A,
+    b: B,
+) -> Result<bool, ConvertOpeningTitleBetweenRegionsError> {
+    Ok(first_arc_content_difference(a, b)?.is_none())
+}
+
+#[cfg(test)]
+#[allow(clippy::unnecessary_wraps)]
+mod tests {
+    use super::*;
+
+    use crate::test_util::make_arc;
+
+    type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+    #[test]
+    fn test_equal() -> TestResult {
+        // Same files, with their data laid out in a different order
+        assert!(arcs_content_equal(
+            make_arc(&[("a", b"aaa"), ("b", b"bbbb")]),
+            make_arc(&[("b", b"bbbb"), ("a", b"aaa")])
+        )?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_different() -> TestResult {
+        let a = make_arc(&[("a", b"aaa"), ("b", b"bbbb"), ("c", b"cc")]);
+        let b = make_arc(&[("a", b"aaa"), ("b", b"BBBB"), ("c", b"CC")]);
+        assert_eq!(first_arc_content_difference(a, b)?.as_deref(), Some("/b"));
+
+        let a = make_arc(&[("a", b"aaa"), ("c", b"cc")]);
+        let b = make_arc(&[("a", b"aaa"), ("b", b"bbbb"), ("c", b"cc")]);
+        assert!(!arcs_content_equal(a.clone(), b.clone())?);
+        assert_eq!(first_arc_content_difference(b, a)?.as_deref(), Some("/b"));
+        Ok(())
+    }
+}
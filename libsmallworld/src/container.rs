@@ -0,0 +1,187 @@
+//! Support for openingTitle.arc files stored inside larger container
+//! files (WADs, disc images, etc.). smallworld doesn't parse any
+//! container formats itself; instead, the caller provides access to the
+//! container's contents by implementing `ArcContainer`.
+
+use std::io::{self, Cursor, Read, Seek};
+
+use log::info;
+
+use crate::{
+    convert_openingtitle_between_regions, ConversionReport,
+    ConvertOpeningTitleBetweenRegionsConflictStrategies, ConvertOpeningTitleBetweenRegionsError,
+    IoContext, Region, RegionBitFlags,
+};
+
+/// The filename that `find_openingtitle_arcs()` searches for.
+const OPENINGTITLE_ARC_FILENAME: &str = "openingTitle.arc";
+
+/// Access to the files inside a container file. Paths are
+/// forward-slash-separated, and their exact format is up to the
+/// implementation.
+pub trait ArcContainer {
+    /// The type returned by `open_file()`.
+    type Reader: Seek + Read;
+
+    /// List the paths of all files in the container.
+    fn list_files(&mut self) -> Result<Vec<String>, io::Error>;
+
+    /// Open a file in the container for reading.
+    fn open_file(&mut self, path: &str) -> Result<Self::Reader, io::Error>;
+
+    /// Replace the data of a file in the container.
+    fn write_file(&mut self, path: &str, data: Vec<u8>) -> Result<(), io::Error>;
+}
+
+/// Split a path of the form `"container@inner/path"` (e.g.
+/// `"disc.iso@files/EU/Layout/openingTitle/openingTitle.arc"`) into the
+/// container path and the path within the container. Returns `None` if
+/// there's no `@`, or if either half is empty.
+pub fn split_container_path(path: &str) -> Option<(&str, &str)> {
+    match path.rsplit_once('@') {
+        Some((container_path, inner_path))
+            if !container_path.is_empty() && !inner_path.is_empty() =>
+        {
+            Some((container_path, inner_path))
+        }
+        _ => None,
+    }
+}
+
+/// List the paths of all files in the container named openingTitle.arc
+/// (case-insensitively).
+pub fn find_openingtitle_arcs<C: ArcContainer>(
+    container: &mut C,
+) -> Result<Vec<String>, io::Error> {
+    Ok(container
+        .list_files()?
+        .into_iter()
+        .filter(|path| {
+            let filename = path.rsplit('/').next().unwrap_or_default();
+            filename.eq_ignore_ascii_case(OPENINGTITLE_ARC_FILENAME)
+        })
+        .collect())
+}
+
+/// Convert an openingTitle.arc inside a container in-place, with the
+/// same options as `convert_openingtitle_between_regions()`. The
+/// converted arc is buffered in memory, and only written back to the
+/// container if the conversion succeeds.
+pub fn convert_openingtitle_in_container<C: ArcContainer>(
+    container: &mut C,
+    path: &str,
+    from_regions: Option<&[Region]>,
+    to_regions: RegionBitFlags,
+    conflict_strategies: &ConvertOpeningTitleBetweenRegionsConflictStrategies,
+) -> Result<ConversionReport, ConvertOpeningTitleBetweenRegionsError> {
+    info!("Converting {path:?} inside a container");
+
+    let in_file = container
+        .open_file(path)
+        .io_context("opening arc in container")?;
+    let (out_buf, report) = convert_openingtitle_between_regions(
+        in_file,
+        Cursor::new(Vec::new()),
+        from_regions,
+        to_regions,
+        conflict_strategies,
+    )?;
+
+    container
+        .write_file(path, out_buf.into_inner())
+        .io_context("writing arc back to container")?;
+    Ok(report)
+}
+
+#[cfg(test)]
+#[allow(clippy::unnecessary_wraps)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+
+    use crate::test_util::make_arc;
+    use crate::{find_missing_regional_filenames, ANIM_FOLDER_PATH, BLYT_FOLDER_PATH};
+
+    type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+    /// Trivial in-memory container, mapping paths to file data
+    struct TestContainer(HashMap<String, Vec<u8>>);
+
+    impl ArcContainer for TestContainer {
+        type Reader = Cursor<Vec<u8>>;
+
+        fn list_files(&mut self) -> Result<Vec<String>, io::Error> {
+            Ok(self.0.keys().cloned().collect())
+        }
+
+        fn open_file(&mut self, path: &str) -> Result<Self::Reader, io::Error> {
+            self.0
+                .get(path)
+                .map(|data| Cursor::new(data.clone()))
+                .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))
+        }
+
+        fn write_file(&mut self, path: &str, data: Vec<u8>) -> Result<(), io::Error> {
+            self.0.insert(path.to_owned(), data);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_split_container_path() -> TestResult {
+        assert_eq!(
+            split_container_path("disc.iso@files/openingTitle.arc"),
+            Some(("disc.iso", "files/openingTitle.arc"))
+        );
+        assert_eq!(split_container_path("openingTitle.arc"), None);
+        assert_eq!(split_container_path("disc.iso@"), None);
+        assert_eq!(split_container_path("@files/openingTitle.arc"), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_and_convert() -> TestResult {
+        // Single-region (P) openingTitle.arc, with all files empty
+        let filenames = Region::P.filenames();
+        let arc = make_arc(&[
+            (
+                format!("{ANIM_FOLDER_PATH}/{}", filenames.in_press_brlan),
+                b"",
+            ),
+            (
+                format!("{ANIM_FOLDER_PATH}/{}", filenames.in_title_brlan),
+                b"",
+            ),
+            (
+                format!("{ANIM_FOLDER_PATH}/{}", filenames.loop_press_brlan),
+                b"",
+            ),
+            (
+                format!("{ANIM_FOLDER_PATH}/{}", filenames.out_press_brlan),
+                b"",
+            ),
+            (format!("{BLYT_FOLDER_PATH}/{}", filenames.brlyt), b""),
+        ]);
+
+        let mut container = TestContainer(HashMap::from([
+            ("files/EU/openingTitle.arc".to_owned(), arc.into_inner()),
+            ("files/EU/somethingElse.arc".to_owned(), Vec::new()),
+        ]));
+
+        let found = find_openingtitle_arcs(&mut container)?;
+        assert_eq!(found, vec!["files/EU/openingTitle.arc".to_owned()]);
+
+        convert_openingtitle_in_container(
+            &mut container,
+            &found[0],
+            None,
+            RegionBitFlags::ALL,
+            &ConvertOpeningTitleBetweenRegionsConflictStrategies::default(),
+        )?;
+
+        let converted = container.open_file(&found[0])?;
+        assert!(find_missing_regional_filenames(converted, RegionBitFlags::ALL)?.is_empty());
+        Ok(())
+    }
+}
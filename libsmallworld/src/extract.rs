@@ -0,0 +1,171 @@
+//! Support for extracting a single region's files from
+//! openingTitle.arc, under names that don't depend on the region, so
+//! they can be edited without keeping track of where they came from.
+
+use std::io::{Read, Seek};
+
+use log::warn;
+
+use crate::u8_fnt::{self, U8Node};
+use crate::util;
+use crate::{
+    ConvertOpeningTitleBetweenRegionsError, IoContext, OpeningTitleRegionFilenames, Region,
+    ANIM_FOLDER_PATH, BLYT_FOLDER_PATH, TIMG_FOLDER_PATH,
+};
+
+/// The names `extract_region_files()` gives to each regional file: the
+/// usual names, minus the region tag.
+pub const GENERIC_FILENAMES: OpeningTitleRegionFilenames<'static> = OpeningTitleRegionFilenames {
+    in_press_brlan: "openingTitle_inPress.brlan",
+    in_title_brlan: "openingTitle_inTitle.brlan",
+    loop_press_brlan: "openingTitle_loopPress.brlan",
+    out_press_brlan: "openingTitle_outPress.brlan",
+    brlyt: "openingTitle.brlyt",
+};
+
+/// The name `extract_region_files()` gives to the title logo TPL.
+pub const GENERIC_LOGO_TPL_FILENAME: &str = "wiiMario_Title_logo.tpl";
+
+/// Read openingTitle.arc from a `Seek+Read`, and return the data of
+/// `region`'s five regional files and its title logo TPL, each paired
+/// with its name from `GENERIC_FILENAMES` (or
+/// `GENERIC_LOGO_TPL_FILENAME`).
+///
+/// Missing regional files are reported together in a single
+/// `MissingFiles` error. A missing TPL is only a warning, since the
+/// logo isn't always edited along with the layout (and arcs converted
+/// from another region may not have the right one).
+pub fn extract_region_files<SR: Seek + Read>(
+    reader: &mut SR,
+    region: Region,
+) -> Result<Vec<(&'static str, Vec<u8>)>, ConvertOpeningTitleBetweenRegionsError> {
+    let (fnt, data_table_offs) = u8_fnt::read(reader)?;
+
+    let filenames = region.filenames();
+    let regional_files = [
+        (
+            ANIM_FOLDER_PATH,
+            filenames.in_press_brlan,
+            GENERIC_FILENAMES.in_press_brlan,
+        ),
+        (
+            ANIM_FOLDER_PATH,
+            filenames.in_title_brlan,
+            GENERIC_FILENAMES.in_title_brlan,
+        ),
+        (
+            ANIM_FOLDER_PATH,
+            filenames.loop_press_brlan,
+            GENERIC_FILENAMES.loop_press_brlan,
+        ),
+        (
+            ANIM_FOLDER_PATH,
+            filenames.out_press_brlan,
+            GENERIC_FILENAMES.out_press_brlan,
+        ),
+        (BLYT_FOLDER_PATH, filenames.brlyt, GENERIC_FILENAMES.brlyt),
+    ];
+    let find = |folder: &str, filename: &str| {
+        fnt.get(&format!("{folder}/{filename}"))
+            .and_then(U8Node::as_file)
+    };
+
+    let mut nodes = Vec::new();
+    let mut missing = Vec::new();
+    for (folder, filename, generic_filename) in regional_files {
+        match find(folder, filename) {
+            Some(node) => nodes.push((generic_filename, node)),
+            None => missing.push(filename),
+        }
+    }
+    if !missing.is_empty() {
+        return Err(ConvertOpeningTitleBetweenRegionsError::MissingFiles(
+            missing.join(", "),
+        ));
+    }
+    match find(TIMG_FOLDER_PATH, region.logo_tpl_filename()) {
+        Some(node) => nodes.push((GENERIC_LOGO_TPL_FILENAME, node)),
+        None => warn!(
+            "{:?} not found, so the title logo won't be extracted",
+            region.logo_tpl_filename()
+        ),
+    }
+
+    nodes
+        .into_iter()
+        .map(|(generic_filename, node)| {
+            let data = util::read_file_slice(
+                reader,
+                u64::from(data_table_offs) + u64::from(node.offset),
+                node.size as usize,
+            )
+            .io_context("reading regional file")?;
+            Ok((generic_filename, data))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+#[allow(clippy::unnecessary_wraps)]
+mod tests {
+    use super::*;
+
+    use crate::test_util::make_arc;
+
+    type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+    #[test]
+    fn test_extract() -> TestResult {
+        // K's files, plus one of W's that shouldn't be picked up
+        let k = Region::K.filenames();
+        let files: [(String, &[u8]); 7] = [
+            (
+                format!("{ANIM_FOLDER_PATH}/{}", k.in_press_brlan),
+                b"in_press",
+            ),
+            (
+                format!("{ANIM_FOLDER_PATH}/{}", k.in_title_brlan),
+                b"in_title",
+            ),
+            (
+                format!("{ANIM_FOLDER_PATH}/{}", k.loop_press_brlan),
+                b"loop_press",
+            ),
+            (
+                format!("{ANIM_FOLDER_PATH}/{}", k.out_press_brlan),
+                b"out_press",
+            ),
+            (format!("{BLYT_FOLDER_PATH}/{}", k.brlyt), b"brlyt"),
+            (
+                format!("{BLYT_FOLDER_PATH}/{}", Region::W.filenames().brlyt),
+                b"other brlyt",
+            ),
+            (
+                format!("{TIMG_FOLDER_PATH}/{}", Region::K.logo_tpl_filename()),
+                b"tpl",
+            ),
+        ];
+        let mut arc = make_arc(&files);
+
+        let extracted = extract_region_files(&mut arc, Region::K)?;
+        assert_eq!(
+            extracted,
+            [
+                ("openingTitle_inPress.brlan", b"in_press".to_vec()),
+                ("openingTitle_inTitle.brlan", b"in_title".to_vec()),
+                ("openingTitle_loopPress.brlan", b"loop_press".to_vec()),
+                ("openingTitle_outPress.brlan", b"out_press".to_vec()),
+                ("openingTitle.brlyt", b"brlyt".to_vec()),
+                ("wiiMario_Title_logo.tpl", b"tpl".to_vec()),
+            ]
+        );
+
+        // W only has its brlyt, and no logo
+        assert!(matches!(
+            extract_region_files(&mut arc, Region::W),
+            Err(ConvertOpeningTitleBetweenRegionsError::MissingFiles(files))
+                if files.split(", ").count() == 4
+        ));
+        Ok(())
+    }
+}
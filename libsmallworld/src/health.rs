@@ -0,0 +1,328 @@
+//! Heuristic "health checks" for openingTitle.arc files. Unlike
+//! `U8Node::validate()`, these don't look for definite problems, just
+//! things that are unusual enough to be worth a second look. None of
+//! them are ever treated as errors.
+
+use std::fmt;
+use std::io::{Read, Seek};
+
+use crate::u8_fnt::{self, U8FolderNode, U8Node};
+use crate::{
+    get_anim_folder, get_blyt_folder, region_coverage_mismatches,
+    ConvertOpeningTitleBetweenRegionsError, Region, RegionCoverageMismatch, ANIM_FOLDER_PATH,
+    BLYT_FOLDER_PATH,
+};
+
+/// A region's brlyt is considered suspiciously small if it's less than
+/// this fraction of the average size of its brlans. Real brlyts are
+/// usually a few times larger than each brlan.
+const MIN_BRLYT_TO_BRLAN_SIZE_RATIO: f64 = 0.25;
+
+/// A region's brlyt is considered suspiciously large if it's more than
+/// this many times the average size of its brlans.
+const MAX_BRLYT_TO_BRLAN_SIZE_RATIO: f64 = 64.0;
+
+/// The magic that every BRLAN file starts with.
+#[cfg(feature = "brlyt")]
+const BRLAN_MAGIC: &[u8; 4] = b"RLAN";
+
+/// The magic that every BRLYT file starts with.
+#[cfg(feature = "brlyt")]
+const BRLYT_MAGIC: &[u8; 4] = b"RLYT";
+
+/// Something unusual about a file in an openingTitle.arc.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HealthWarning {
+    /// Path of the file that looks suspicious.
+    pub path: String,
+    /// Human-readable description of what looks wrong.
+    pub message: String,
+}
+
+impl fmt::Display for HealthWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// Get the size of a file in a folder, if it exists.
+fn file_size(folder: &U8FolderNode, filename: &str) -> Option<u32> {
+    match folder.get(filename) {
+        Some(U8Node::File(node)) => Some(node.size),
+        _ => None,
+    }
+}
+
+/// Compare each region's brlyt size against the average size of its
+/// brlans, and warn about extreme outliers. Regions missing any of
+/// their regional files are skipped.
+fn check_brlyt_sizes(
+    fnt: &U8Node,
+) -> Result<Vec<HealthWarning>, ConvertOpeningTitleBetweenRegionsError> {
+    let anim_folder = get_anim_folder(fnt)?;
+    let blyt_folder = get_blyt_folder(fnt)?;
+
+    let mut warnings = Vec::new();
+
+    for (region, filenames) in Region::all_filenames() {
+        let brlan_sizes: Option<Vec<u32>> = [
+            filenames.in_press_brlan,
+            filenames.in_title_brlan,
+            filenames.loop_press_brlan,
+            filenames.out_press_brlan,
+        ]
+        .into_iter()
+        .map(|filename| file_size(anim_folder, filename))
+        .collect();
+
+        let (Some(brlan_sizes), Some(brlyt_size)) =
+            (brlan_sizes, file_size(blyt_folder, filenames.brlyt))
+        else {
+            continue;
+        };
+
+        let average_brlan_size =
+            brlan_sizes.iter().map(|&size| f64::from(size)).sum::<f64>() / brlan_sizes.len() as f64;
+        if average_brlan_size == 0.0 {
+            // Placeholder files; nothing sensible to compare against
+            continue;
+        }
+
+        let ratio = f64::from(brlyt_size) / average_brlan_size;
+        let problem = if ratio < MIN_BRLYT_TO_BRLAN_SIZE_RATIO {
+            "much smaller"
+        } else if ratio > MAX_BRLYT_TO_BRLAN_SIZE_RATIO {
+            "much larger"
+        } else {
+            continue;
+        };
+
+        warnings.push(HealthWarning {
+            path: format!("{BLYT_FOLDER_PATH}/{}", filenames.brlyt),
+            message: format!(
+                "{region} brlyt ({brlyt_size:#x} bytes) is {problem} than expected, compared \
+                 to the brlans in {ANIM_FOLDER_PATH} (average {:#x} bytes)",
+                average_brlan_size as u64
+            ),
+        });
+    }
+
+    Ok(warnings)
+}
+
+/// Warn about regions with files in only one of the anim and blyt
+/// folders. Conversion can still work if another source region has the
+/// missing files, but it usually means something was deleted or
+/// renamed by mistake.
+fn check_region_coverage(
+    fnt: &U8Node,
+) -> Result<Vec<HealthWarning>, ConvertOpeningTitleBetweenRegionsError> {
+    Ok(region_coverage_mismatches(fnt)?
+        .into_iter()
+        .map(|mismatch| {
+            let filenames = mismatch.region().filenames();
+            let path = match mismatch {
+                RegionCoverageMismatch::AnimWithoutBlyt(_) => {
+                    format!("{BLYT_FOLDER_PATH}/{}", filenames.brlyt)
+                }
+                RegionCoverageMismatch::BlytWithoutAnim(_) => ANIM_FOLDER_PATH.to_owned(),
+            };
+            HealthWarning {
+                path,
+                message: mismatch.to_string(),
+            }
+        })
+        .collect())
+}
+
+/// Check that every file in the anim and blyt folders with a .brlan or
+/// .brlyt extension really starts with the matching magic. This catches
+/// unrelated files that were renamed to take a layout file's place.
+/// Only the magic is checked, not the rest of the file.
+#[cfg(feature = "brlyt")]
+fn check_layout_magics<SR: Seek + Read>(
+    fnt: &U8Node,
+    data_table_offs: u32,
+    in_file: &mut SR,
+) -> Result<Vec<HealthWarning>, ConvertOpeningTitleBetweenRegionsError> {
+    use itertools::Itertools;
+
+    use crate::{util, IoContext};
+
+    let mut warnings = Vec::new();
+
+    for (folder_path, folder, extension, kind, magic) in [
+        (
+            ANIM_FOLDER_PATH,
+            get_anim_folder(fnt)?,
+            ".brlan",
+            "BRLAN",
+            BRLAN_MAGIC,
+        ),
+        (
+            BLYT_FOLDER_PATH,
+            get_blyt_folder(fnt)?,
+            ".brlyt",
+            "BRLYT",
+            BRLYT_MAGIC,
+        ),
+    ] {
+        for (name, node) in folder
+            .iter()
+            .sorted_by_key(|(name, _)| (u8_fnt::fnt_sort_key(name), *name))
+        {
+            let U8Node::File(node) = node else {
+                continue;
+            };
+            if !name.to_lowercase().ends_with(extension) {
+                continue;
+            }
+
+            let data = util::read_file_slice(
+                in_file,
+                u64::from(data_table_offs) + u64::from(node.offset),
+                node.size.min(4).try_into().unwrap(),
+            )
+            .io_context("reading file magic")?;
+
+            if data != magic {
+                warnings.push(HealthWarning {
+                    path: format!("{folder_path}/{name}"),
+                    message: format!(
+                        "named like a {kind}, but doesn't start with the {kind} magic (found \
+                         {data:02x?})"
+                    ),
+                });
+            }
+        }
+    }
+
+    Ok(warnings)
+}
+
+/// Read openingTitle.arc from a `Seek+Read`, and run heuristic checks
+/// on it that might indicate corruption. Since these are only
+/// heuristics, a non-empty result doesn't necessarily mean the arc is
+/// broken.
+pub fn check_openingtitle_health<SR: Seek + Read>(
+    mut in_file: SR,
+) -> Result<Vec<HealthWarning>, ConvertOpeningTitleBetweenRegionsError> {
+    let (fnt, _data_table_offs) = u8_fnt::read(&mut in_file)?;
+    let mut warnings = check_brlyt_sizes(&fnt)?;
+    warnings.extend(check_region_coverage(&fnt)?);
+    #[cfg(feature = "brlyt")]
+    warnings.extend(check_layout_magics(&fnt, _data_table_offs, &mut in_file)?);
+    Ok(warnings)
+}
+
+#[cfg(test)]
+#[allow(clippy::unnecessary_wraps)]
+mod tests {
+    use super::*;
+
+    use crate::empty_openingtitle_tree;
+    use crate::u8_fnt::U8FileNode;
+
+    type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+    /// Helper function to make an openingTitle FNT with P and E files,
+    /// with the given brlan and brlyt sizes
+    fn make_fnt(brlan_size: u32, p_brlyt_size: u32, e_brlyt_size: u32) -> U8Node {
+        let file = |size| U8Node::File(U8FileNode { offset: 0, size });
+
+        let mut fnt = empty_openingtitle_tree();
+        for (region, brlyt_size) in [(Region::P, p_brlyt_size), (Region::E, e_brlyt_size)] {
+            let filenames = region.filenames();
+            for filename in [
+                filenames.in_press_brlan,
+                filenames.in_title_brlan,
+                filenames.loop_press_brlan,
+                filenames.out_press_brlan,
+            ] {
+                fnt.insert_path(&format!("{ANIM_FOLDER_PATH}/{filename}"), file(brlan_size))
+                    .unwrap();
+            }
+            fnt.insert_path(
+                &format!("{BLYT_FOLDER_PATH}/{}", filenames.brlyt),
+                file(brlyt_size),
+            )
+            .unwrap();
+        }
+        fnt
+    }
+
+    #[test]
+    fn test_typical_sizes() -> TestResult {
+        assert!(check_brlyt_sizes(&make_fnt(0x400, 0x1800, 0x1800))?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_zero_size_brlans() -> TestResult {
+        assert!(check_brlyt_sizes(&make_fnt(0, 0x1800, 0))?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_region_coverage() -> TestResult {
+        let mut fnt = make_fnt(0x400, 0x1800, 0x1800);
+        assert!(check_region_coverage(&fnt)?.is_empty());
+
+        fnt.get_mut(BLYT_FOLDER_PATH)
+            .unwrap()
+            .as_mut_folder()
+            .unwrap()
+            .remove(Region::E.filenames().brlyt);
+        let warnings = check_region_coverage(&fnt)?;
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].path, "/arc/blyt/openingTitle_US_00.brlyt");
+        assert_eq!(warnings[0].message, "region E has anim files but no brlyt");
+        Ok(())
+    }
+
+    #[cfg(feature = "brlyt")]
+    #[test]
+    fn test_layout_magics() -> TestResult {
+        use std::io::{Cursor, Write};
+
+        // All of the brlans point to the same (valid) data, but the
+        // brlyts don't
+        let file = |offset| U8Node::File(U8FileNode { offset, size: 8 });
+        let mut fnt = make_fnt(0, 0, 0);
+        for (_, node) in fnt.get_mut(ANIM_FOLDER_PATH).unwrap().iter_mut() {
+            *node = file(0);
+        }
+        let blyt = fnt
+            .get_mut(BLYT_FOLDER_PATH)
+            .unwrap()
+            .as_mut_folder()
+            .unwrap();
+        blyt.insert(Region::P.filenames().brlyt.to_owned(), file(0x20));
+        blyt.insert(Region::E.filenames().brlyt.to_owned(), file(0));
+        blyt.insert("not_a_layout.bin".to_owned(), file(0));
+
+        let mut arc = Cursor::new(Vec::new());
+        let data_table_offs = u8_fnt::write(&mut arc, &fnt)?;
+        arc.write_all(b"RLAN\xfe\xff\0\0")?;
+        arc.write_all(&[0; 0x18])?;
+        arc.write_all(b"RLYT\xfe\xff\0\0")?;
+
+        let warnings = check_layout_magics(&fnt, data_table_offs, &mut arc)?;
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].path, "/arc/blyt/openingTitle_US_00.brlyt");
+        assert!(warnings[0].message.contains("named like a BRLYT"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_outliers() -> TestResult {
+        let warnings = check_brlyt_sizes(&make_fnt(0x400, 0x20, 0x100000))?;
+
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0].path, "/arc/blyt/openingTitle_EU_00.brlyt");
+        assert!(warnings[0].message.contains("much smaller"));
+        assert_eq!(warnings[1].path, "/arc/blyt/openingTitle_US_00.brlyt");
+        assert!(warnings[1].message.contains("much larger"));
+        Ok(())
+    }
+}
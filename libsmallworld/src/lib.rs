@@ -1,37 +1,83 @@
 //! The library that powers smallworld. Handles everything other than
 //! reading CLI arguments.
 
-#![feature(concat_bytes)]
-#![feature(int_roundings)]
-
+#![cfg_attr(test, feature(concat_bytes))]
+
+mod case_insensitive_map;
+mod compare;
+mod container;
+mod extract;
+mod health;
+mod merge;
+mod normalize;
 mod openingtitle_filename_constants;
-mod u8_fnt;
+#[cfg(feature = "bsdiff")]
+mod patch;
+mod template;
+#[cfg(test)]
+mod test_util;
+mod u8_archive;
+pub mod u8_fnt;
 mod util;
+pub mod yaz0;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::io::{self, Cursor, Read, Seek, SeekFrom, Write};
 use std::str::FromStr;
+use std::time::{Duration, Instant};
 
 use enumflags2::{bitflags, BitFlags};
-use log::{debug, info, trace};
+use log::{debug, info, trace, warn};
 use thiserror::Error;
 
-use crate::openingtitle_filename_constants::ALL_FILENAMES;
+use crate::openingtitle_filename_constants::{
+    ALL_FILENAMES, CHINA_LOGO_TPL_FILENAME, SHARED_LOGO_TPL_FILENAME,
+};
 use crate::u8_fnt::{U8FileNode, U8FolderNode, U8Node};
 
+pub use crate::case_insensitive_map::{CaseCollision, CaseInsensitiveMap};
+pub use crate::compare::{arcs_content_equal, first_arc_content_difference};
+pub use crate::container::{
+    convert_openingtitle_in_container, find_openingtitle_arcs, split_container_path, ArcContainer,
+};
+pub use crate::extract::{extract_region_files, GENERIC_FILENAMES, GENERIC_LOGO_TPL_FILENAME};
+pub use crate::health::{check_openingtitle_health, HealthWarning};
+pub use crate::merge::merge_arcs_to_region_free;
+pub use crate::normalize::{find_nonstandard_regional_filenames, FilenameNormalization};
+pub use crate::openingtitle_filename_constants::OpeningTitleRegionFilenames;
+#[cfg(feature = "bsdiff")]
+pub use crate::patch::{apply_patch, make_patch, ApplyPatchError};
+pub use crate::template::build_openingtitle_template;
+pub use crate::u8_archive::U8Archive;
 pub use crate::u8_fnt::ParseU8Error;
+pub use crate::util::{copy_n, crc32, sha256};
+
+/// The path to openingTitle.arc's top-level "arc" folder.
+pub const ARC_FOLDER_PATH: &str = "/arc";
 
 /// The path to openingTitle.arc's "anim" folder.
-const ANIM_FOLDER_PATH: &str = "/arc/anim";
+pub const ANIM_FOLDER_PATH: &str = "/arc/anim";
 
 /// The path to openingTitle.arc's "blyt" folder.
-const BLYT_FOLDER_PATH: &str = "/arc/blyt";
+pub const BLYT_FOLDER_PATH: &str = "/arc/blyt";
+
+/// The path to openingTitle.arc's "timg" folder.
+pub const TIMG_FOLDER_PATH: &str = "/arc/timg";
+
+/// Size of the buffer used to copy file data into the new FAT.
+const FAT_COPY_BUFFER_SIZE: usize = 0x10000;
+
+/// If the new FNT is more than this many times larger than the
+/// original one, a warning is logged.
+const FNT_GROWTH_WARNING_RATIO: f64 = 2.0;
 
 /// All unique regions of *New Super Mario Bros. Wii*, using the letter
 /// names from their game codes (SMN**P**01, SMN**E**01, etc.).
 #[bitflags]
 #[repr(u32)]
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Region {
     /// International
     P,
@@ -70,6 +116,199 @@ impl Region {
     /// prioritized is a documented guarantee. The array may change in
     /// the future, though, if new versions of NSMBW are ever released.
     pub const DEFAULT_ORDER: [Self; 6] = [Self::P, Self::E, Self::J, Self::K, Self::W, Self::C];
+
+    /// All regions. This is just an alias for `DEFAULT_ORDER`, and is
+    /// ordered the same way, so it's safe to use wherever "all regions,
+    /// in priority order" is needed. `RegionBitFlags::ALL` is the
+    /// equivalent set, for when order doesn't matter.
+    pub const ALL: [Self; 6] = Self::DEFAULT_ORDER;
+
+    /// The human-readable name of the market this region was sold in
+    /// (e.g. "North America" for E), for display in user interfaces.
+    pub fn market_name(self) -> &'static str {
+        match self {
+            Self::P => "International",
+            Self::E => "North America",
+            Self::J => "Japan",
+            Self::K => "Korea",
+            Self::W => "Taiwan",
+            Self::C => "China",
+        }
+    }
+
+    /// The regional filenames that this region's openingTitle.arc uses.
+    pub fn filenames(self) -> &'static OpeningTitleRegionFilenames<'static> {
+        &ALL_FILENAMES[self.into()]
+    }
+
+    /// The filename of the title logo TPL this region's game looks for
+    /// in the "timg" folder. P and E share `SHARED_LOGO_TPL_FILENAME`.
+    pub fn logo_tpl_filename(self) -> &'static str {
+        match self {
+            Self::P | Self::E => SHARED_LOGO_TPL_FILENAME,
+            Self::J => "wiiMario_Title_logo_00.tpl",
+            Self::K => "wiiMario_Title_logo_KOR.tpl",
+            Self::W => "wiiMario_Title_logo_TW.tpl",
+            Self::C => CHINA_LOGO_TPL_FILENAME,
+        }
+    }
+
+    /// Figure out which region a regional filename belongs to, from the
+    /// region tag after "openingTitle_" (case-insensitively): "EU" is
+    /// P, "US" is E, "13" is J, "KR" is K, "TW" is W, and "CN" is C.
+    /// The rest of the filename isn't checked, so slightly nonstandard
+    /// names (e.g. "openingTitle_KR_01_inPress.brlan") are recognized
+    /// too. Returns `None` if there's no recognizable region tag.
+    ///
+    /// ```
+    /// use libsmallworld::Region;
+    ///
+    /// assert_eq!(Region::from_filename("openingTitle_US_00_inPress.brlan"), Some(Region::E));
+    /// assert_eq!(Region::from_filename("openingTitle_13.brlyt"), Some(Region::J));
+    /// assert_eq!(Region::from_filename("wiiMario_Title_logo_CN.tpl"), None);
+    /// ```
+    pub fn from_filename(filename: &str) -> Option<Self> {
+        let filename = filename.to_lowercase();
+        Self::DEFAULT_ORDER.into_iter().find(|region| {
+            filename
+                .strip_prefix(&normalize::region_prefix(*region))
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with(['_', '.']))
+        })
+    }
+
+    /// Iterate over every region and its regional filenames, in
+    /// `DEFAULT_ORDER`.
+    ///
+    /// ```
+    /// use libsmallworld::Region;
+    ///
+    /// for (region, filenames) in Region::all_filenames() {
+    ///     println!("{region}: {}", filenames.brlyt);
+    /// }
+    ///
+    /// let (first_region, first_filenames) = Region::all_filenames().next().unwrap();
+    /// assert_eq!(first_region, Region::P);
+    /// assert_eq!(first_filenames.brlyt, "openingTitle_EU_00.brlyt");
+    /// assert_eq!(Region::all_filenames().count(), 6);
+    /// ```
+    pub fn all_filenames(
+    ) -> impl Iterator<Item = (Self, &'static OpeningTitleRegionFilenames<'static>)> {
+        Self::DEFAULT_ORDER
+            .into_iter()
+            .map(|region| (region, region.filenames()))
+    }
+}
+
+/// Check smallworld's built-in table of regional filenames for internal
+/// consistency, and return a description of each problem found. An
+/// empty `Vec` means the table is OK. This doesn't read any files; it's
+/// meant to catch mistakes when the table is edited (e.g. when adding a
+/// region).
+///
+/// Checked: every region has an entry (and there are no extras), each
+/// region's five filenames are distinct and recognized as belonging to
+/// it by `Region::from_filename()`, no two regions share a filename,
+/// and every region's letter parses back to the same region. The title
+/// logo TPLs from `Region::logo_tpl_filename()` are checked too: only P
+/// and E may share one, and China's has to differ from that shared one
+/// (`add_china_logo_fallback()` relies on this).
+pub fn check_filename_table() -> Vec<String> {
+    let mut problems = Vec::new();
+
+    if RegionBitFlags::from_iter(Region::DEFAULT_ORDER) != RegionBitFlags::ALL {
+        problems.push("Region::DEFAULT_ORDER doesn't list every region exactly once".to_owned());
+    }
+    for name in ALL_FILENAMES.keys() {
+        if name.parse::<Region>().is_err() {
+            problems.push(format!(
+                "filename table has an entry for unknown region {name:?}"
+            ));
+        }
+    }
+
+    let mut owners: HashMap<String, Region> = HashMap::new();
+    for region in Region::DEFAULT_ORDER {
+        let letter: &str = region.into();
+        for name in [letter.to_owned(), letter.to_lowercase()] {
+            if name.parse::<Region>().ok() != Some(region) {
+                problems.push(format!("{name:?} doesn't parse as region {letter}"));
+            }
+        }
+
+        let Some(filenames) = ALL_FILENAMES.get(letter) else {
+            problems.push(format!("region {letter} has no filenames"));
+            continue;
+        };
+        for filename in [
+            filenames.in_press_brlan,
+            filenames.in_title_brlan,
+            filenames.loop_press_brlan,
+            filenames.out_press_brlan,
+            filenames.brlyt,
+        ] {
+            if Region::from_filename(filename) != Some(region) {
+                problems.push(format!(
+                    "{filename:?} (region {letter}) isn't recognized as a region-{letter} filename"
+                ));
+            }
+            // (case-insensitively, since that's how U8 lookups work)
+            match owners.insert(filename.to_lowercase(), region) {
+                Some(other) if other == region => {
+                    problems.push(format!("region {letter} uses {filename:?} more than once"));
+                }
+                Some(other) => {
+                    let other: &str = other.into();
+                    problems.push(format!(
+                        "{filename:?} is used by both region {other} and region {letter}"
+                    ));
+                }
+                None => {}
+            }
+        }
+    }
+
+    if Region::C
+        .logo_tpl_filename()
+        .eq_ignore_ascii_case(SHARED_LOGO_TPL_FILENAME)
+    {
+        problems.push(format!(
+            "region C's logo TPL is the shared one ({SHARED_LOGO_TPL_FILENAME:?})"
+        ));
+    }
+    let mut tpl_owners: HashMap<String, Region> = HashMap::new();
+    for region in Region::DEFAULT_ORDER {
+        let tpl_filename = region.logo_tpl_filename();
+        if let Some(other) = tpl_owners.insert(tpl_filename.to_lowercase(), region) {
+            let shared_by_p_and_e = RegionBitFlags::from_iter([region, other])
+                == RegionBitFlags::from_iter([Region::P, Region::E]);
+            if !shared_by_p_and_e {
+                problems.push(format!(
+                    "logo TPL {tpl_filename:?} is used by both region {other} and region {region}"
+                ));
+            }
+        }
+    }
+
+    problems
+}
+
+/// Shows the region's letter.
+impl fmt::Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.into())
+    }
+}
+
+/// Extra text to show after a region's letter in messages about
+/// regional filenames. P is described in full, since it uses "EU"
+/// filenames despite not being specific to Europe, which is easy to
+/// confuse.
+fn region_filename_note(region: Region) -> &'static str {
+    if region == Region::P {
+        " (international, uses EU_00 filenames)"
+    } else {
+        ""
+    }
 }
 
 /// An error that can occur when parsing a `Region` from a string.
@@ -119,6 +358,7 @@ impl From<&Region> for &str {
 /// Indicates how a function should proceed if it finds that it needs to
 /// merge two or more conflicting things.
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ConflictStrategy {
     /// Treat the conflict as an error and return `Err`. For safety,
     /// this is the default.
@@ -127,6 +367,169 @@ pub enum ConflictStrategy {
     /// Select one of the conflicting things, discard the other one(s),
     /// and continue.
     Overwrite,
+    /// Ask `ConvertOpeningTitleBetweenRegionsOptions::resolve_conflict`
+    /// what to do about each conflict. Acts like `Fail` if that isn't
+    /// set.
+    Callback,
+}
+
+/// The types of conflicts that can occur in
+/// `convert_openingtitle_between_regions()`. See
+/// `ConvertOpeningTitleBetweenRegionsConflictStrategies`.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum ConflictKind {
+    /// Two copies of a regional file had different data.
+    FileContents,
+    /// An output filename already existed.
+    Filename,
+}
+
+/// How a conflict was resolved under `ConflictStrategy::Overwrite` or
+/// `ConflictStrategy::Callback`.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum ConflictResolution {
+    /// `file_a` was kept, and `file_b` was discarded.
+    KeptA,
+    /// `file_a` was replaced by `file_b`.
+    ReplacedA,
+}
+
+/// A conflict that was found (and resolved) during a conversion.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct ConflictInfo {
+    /// What kind of conflict this was.
+    pub kind: ConflictKind,
+    /// Filename of the first file involved.
+    pub file_a: String,
+    /// The region `file_a` belongs to.
+    pub region_a: Region,
+    /// Filename of the second file involved. For
+    /// `ConflictKind::Filename`, this is the same as `file_a`.
+    pub file_b: String,
+    /// The region `file_b` belongs to.
+    pub region_b: Region,
+    /// How the conflict was resolved.
+    pub resolution: ConflictResolution,
+}
+
+impl fmt::Display for ConflictInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ConflictKind::FileContents => write!(
+                f,
+                "{:?} from region {}{} and {:?} from region {}{} have different data; kept {:?}",
+                self.file_a,
+                self.region_a,
+                region_filename_note(self.region_a),
+                self.file_b,
+                self.region_b,
+                region_filename_note(self.region_b),
+                match self.resolution {
+                    ConflictResolution::KeptA => &self.file_a,
+                    ConflictResolution::ReplacedA => &self.file_b,
+                }
+            ),
+            ConflictKind::Filename => match self.resolution {
+                ConflictResolution::KeptA => write!(
+                    f,
+                    "{:?} already existed, and was kept instead of adding it for region {}{}",
+                    self.file_a,
+                    self.region_a,
+                    region_filename_note(self.region_a)
+                ),
+                ConflictResolution::ReplacedA => write!(
+                    f,
+                    "{:?} already existed, and was overwritten for region {}{}",
+                    self.file_a,
+                    self.region_a,
+                    region_filename_note(self.region_a)
+                ),
+            },
+        }
+    }
+}
+
+/// Decides whether two files in an arc have the same data, for the
+/// purposes of detecting conflicts between regional files.
+///
+/// Each file's data is reduced to a `Digest`, and two files are
+/// considered identical if their digests are equal. A file's digest is
+/// calculated at most once, no matter how many other files it's
+/// compared against.
+pub trait FileComparator {
+    /// The value that files' data is reduced to for comparison.
+    type Digest: Eq;
+
+    /// Calculate the digest of `size` bytes of data at absolute offset
+    /// `offset` in `reader`.
+    fn digest<SR: Seek + Read>(
+        &self,
+        reader: &mut SR,
+        offset: u64,
+        size: usize,
+    ) -> Result<Self::Digest, io::Error>;
+}
+
+/// A `FileComparator` that compares 64-bit non-cryptographic hashes of
+/// file data. This is fast and uses little memory, but there's a
+/// (vanishingly small) chance of two different files being considered
+/// identical. This is the default.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct HashFileComparator;
+
+impl FileComparator for HashFileComparator {
+    type Digest = u64;
+
+    fn digest<SR: Seek + Read>(
+        &self,
+        reader: &mut SR,
+        offset: u64,
+        size: usize,
+    ) -> Result<u64, io::Error> {
+        util::calc_hash_from_file_slice(reader, offset, size, false)
+    }
+}
+
+/// Like `HashFileComparator`, but tolerates files whose data runs a
+/// little past the end of the arc (less than 0x20 bytes), hashing
+/// whatever data is actually there and logging a warning. This happens
+/// in arcs that count trailing padding that isn't present in the last
+/// file's size.
+///
+/// This can mask real truncation, so it's never used by default.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct LenientHashFileComparator;
+
+impl FileComparator for LenientHashFileComparator {
+    type Digest = u64;
+
+    fn digest<SR: Seek + Read>(
+        &self,
+        reader: &mut SR,
+        offset: u64,
+        size: usize,
+    ) -> Result<u64, io::Error> {
+        util::calc_hash_from_file_slice(reader, offset, size, true)
+    }
+}
+
+/// A `FileComparator` that compares the full file data byte-for-byte.
+/// Files will never be mistakenly considered identical, at the cost of
+/// keeping copies of their data in memory.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct ByteFileComparator;
+
+impl FileComparator for ByteFileComparator {
+    type Digest = Vec<u8>;
+
+    fn digest<SR: Seek + Read>(
+        &self,
+        reader: &mut SR,
+        offset: u64,
+        size: usize,
+    ) -> Result<Vec<u8>, io::Error> {
+        util::read_file_slice(reader, offset, size)
+    }
 }
 
 /// All errors that can be encountered when converting an
@@ -143,24 +546,85 @@ pub enum ConvertOpeningTitleBetweenRegionsError {
     #[error("{0}")]
     InvalidOpeningTitleStructure(String),
 
+    /// The file has an openingTitle-like structure, but no regional
+    /// files for any region, so it's probably a different arc entirely.
+    #[error(
+        "no regional files found for any region (is this really openingTitle.arc, and not \
+         some other arc?)"
+    )]
+    NotAnOpeningTitle,
+
     /// At least one required file was missing. The file might exist but
     /// for a different region, or it might just be completely absent.
     #[error("{0} not found")]
     MissingFiles(String),
 
+    /// A source region has regional files in only one of the anim and
+    /// blyt folders, and no source region has the kind it's missing.
+    #[error("{0}, and no other source region has the missing files")]
+    RegionCoverageMismatch(RegionCoverageMismatch),
+
+    /// A region was listed more than once in `from_regions`.
+    #[error("region {0} specified more than once in the source regions")]
+    DuplicateSourceRegion(Region),
+
     /// Two files that need to be merged have different data.
     #[error("conflicting files: {0:?} and {1:?} are different")]
     FileDataConflict(String, String),
 
-    /// A filename that needs to be added already exists.
-    #[error("{0:?} already exists")]
-    FilenameAlreadyExists(String),
+    /// A filename that needs to be added (for the given region)
+    /// already exists.
+    #[error("{0:?} already exists (needed for region {1}{})", region_filename_note(*.1))]
+    FilenameAlreadyExists(String, Region),
+
+    /// The output would fully support fewer regions (first value) than
+    /// the requested minimum (second value).
+    #[error("output would only support {0} complete region(s), but at least {1} are required")]
+    TooFewRegions(usize, usize),
+
+    /// The output would be larger (first value) than the requested
+    /// maximum size (second value), in bytes.
+    #[error("output would be {0:#x} bytes, but the maximum is {1:#x}")]
+    OutputTooLarge(u64, u64),
+
+    /// The output would be too large for a U8 file, whose offsets and
+    /// sizes are all 32-bit. The value is a lower bound on the output
+    /// size, in bytes.
+    #[error("output would be at least {0:#x} bytes, but U8 files can't be larger than 4 GiB")]
+    TooLarge(u64),
+
+    /// An I/O error occurred during a particular operation (described
+    /// by `op`, e.g. "building new FAT").
+    #[error("I/O error while {op}")]
+    IoAt {
+        op: &'static str,
+        #[source]
+        source: io::Error,
+    },
 
     /// All other errors.
     #[error("I/O error")]
     IoError(#[from] io::Error),
 }
 
+/// Extension trait for attaching the name of the current operation to
+/// I/O errors, turning `IoError` into `IoAt`. Other errors are passed
+/// through unchanged.
+trait IoContext<T> {
+    fn io_context(self, op: &'static str) -> Result<T, ConvertOpeningTitleBetweenRegionsError>;
+}
+
+impl<T, E: Into<ConvertOpeningTitleBetweenRegionsError>> IoContext<T> for Result<T, E> {
+    fn io_context(self, op: &'static str) -> Result<T, ConvertOpeningTitleBetweenRegionsError> {
+        self.map_err(|error| match error.into() {
+            ConvertOpeningTitleBetweenRegionsError::IoError(source) => {
+                ConvertOpeningTitleBetweenRegionsError::IoAt { op, source }
+            }
+            other => other,
+        })
+    }
+}
+
 /// A `U8FileNode` with a filename attached to it. The filename is an
 /// owned string, honestly to make lifetimes simpler.
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -182,6 +646,30 @@ struct OptionalNamedRegionalFiles {
     brlyt: Option<NamedU8FileNode>,
 }
 
+impl OptionalNamedRegionalFiles {
+    /// All five files, in the same order as the fields.
+    fn as_array(&self) -> [&Option<NamedU8FileNode>; 5] {
+        [
+            &self.in_press_brlan,
+            &self.in_title_brlan,
+            &self.loop_press_brlan,
+            &self.out_press_brlan,
+            &self.brlyt,
+        ]
+    }
+
+    /// Mutable version of `as_array()`.
+    fn as_mut_array(&mut self) -> [&mut Option<NamedU8FileNode>; 5] {
+        [
+            &mut self.in_press_brlan,
+            &mut self.in_title_brlan,
+            &mut self.loop_press_brlan,
+            &mut self.out_press_brlan,
+            &mut self.brlyt,
+        ]
+    }
+}
+
 /// Contains `U8FileNode`s that correspond to the five files with
 /// region-dependent filenames ("regional files"). Useful for when the
 /// exact set of files to use has been chosen.
@@ -274,6 +762,40 @@ fn get_mut_blyt_folder(
         })
 }
 
+/// Remove every file whose full path (e.g. "/arc/anim/file.bak")
+/// matches any of the given glob patterns (see `util::glob_matches()`)
+/// from a FNT root node, and return the removed paths. Folders are left
+/// in place, even if they end up empty.
+fn remove_excluded_files(fnt: &mut U8Node, patterns: &[&str]) -> Vec<String> {
+    /// Helper function to remove matching files from a node,
+    /// recursively
+    fn visit_node(node: &mut U8Node, path: &str, patterns: &[&str], removed: &mut Vec<String>) {
+        let Some(folder) = node.as_mut_folder() else {
+            return;
+        };
+        folder.retain(|name, child| {
+            let child_path = format!("{path}/{name}");
+            if let U8Node::Folder(_) = child {
+                visit_node(child, &child_path, patterns, removed);
+                true
+            } else if patterns
+                .iter()
+                .any(|pattern| util::glob_matches(pattern, &child_path))
+            {
+                removed.push(child_path);
+                false
+            } else {
+                true
+            }
+        });
+    }
+
+    let mut removed = Vec::new();
+    visit_node(fnt, "", patterns, &mut removed);
+    removed.sort();
+    removed
+}
+
 /// Remove all files corresponding to the requested regions from a FNT
 /// root node, and create a `HashMap` mapping each region to the file
 /// nodes from it that were found and removed.
@@ -303,7 +825,7 @@ fn remove_regional_files(
     // Remove all requested files from /arc/anim
     for region in regions {
         let region_name = region.into();
-        let mut region_files = map.get_mut(&region).unwrap();
+        let region_files = map.get_mut(&region).unwrap();
 
         let filename = ALL_FILENAMES[region_name].in_press_brlan;
         if let Some(U8Node::File(file_node)) = folder.remove(filename) {
@@ -351,7 +873,7 @@ fn remove_regional_files(
 
     for region in regions {
         let region_name = region.into();
-        let mut region_files = map.get_mut(&region).unwrap();
+        let region_files = map.get_mut(&region).unwrap();
 
         let filename = ALL_FILENAMES[region_name].brlyt;
         if let Some(U8Node::File(file_node)) = folder.remove(filename) {
@@ -366,21 +888,511 @@ fn remove_regional_files(
     Ok(map)
 }
 
+/// Find every file in a FNT named exactly `filename`, as full paths, in
+/// FNT order.
+fn find_file_paths(fnt: &U8Node, filename: &str) -> Vec<String> {
+    /// Helper function to search a folder, recursively
+    fn visit_node(node: &U8Node, path: &str, filename: &str, paths: &mut Vec<String>) {
+        for (name, child) in node.iter() {
+            let child_path = format!("{path}/{name}");
+            match child {
+                U8Node::File(_) if name == filename => paths.push(child_path),
+                U8Node::File(_) => (),
+                U8Node::Folder(_) => visit_node(child, &child_path, filename, paths),
+            }
+        }
+    }
+
+    let mut paths = Vec::new();
+    visit_node(fnt, "", filename, &mut paths);
+    paths
+}
+
+/// For `deep_search`: look through the whole FNT for the requested
+/// regions' regional files that `remove_regional_files()` didn't find
+/// in the anim and blyt folders, and remove those too, adding them to
+/// `map`. Returns the full path of each file found this way.
+fn remove_misplaced_regional_files(
+    fnt: &mut U8Node,
+    regions: RegionBitFlags,
+    map: &mut HashMap<Region, OptionalNamedRegionalFiles>,
+) -> Vec<String> {
+    let mut found = Vec::new();
+
+    for region in regions {
+        let filenames = region.filenames();
+        let filenames = [
+            filenames.in_press_brlan,
+            filenames.in_title_brlan,
+            filenames.loop_press_brlan,
+            filenames.out_press_brlan,
+            filenames.brlyt,
+        ];
+        let Some(region_files) = map.get_mut(&region) else {
+            continue;
+        };
+
+        for (file, filename) in region_files.as_mut_array().into_iter().zip(filenames) {
+            if file.is_some() {
+                continue;
+            }
+            let paths = find_file_paths(fnt, filename);
+            let Some(path) = paths.first() else {
+                continue;
+            };
+            if paths.len() > 1 {
+                warn!("Found {filename:?} in more than one place; using {path:?}");
+            }
+
+            let (folder_path, _) = path.rsplit_once('/').unwrap();
+            if let Some(U8Node::File(node)) = fnt
+                .get_mut(folder_path)
+                .and_then(U8Node::as_mut_folder)
+                .and_then(|folder| folder.remove(filename))
+            {
+                info!("Found {path:?}");
+                *file = Some(NamedU8FileNode {
+                    node,
+                    filename: filename.to_owned(),
+                });
+                found.push(path.clone());
+            }
+        }
+    }
+
+    found
+}
+
+/// For `deep_search`: move regional files that were found elsewhere by
+/// `remove_misplaced_regional_files()`, and have since been added back
+/// to the anim or blyt folder, to where they were found. Files whose
+/// original folder no longer exists (e.g. because of `minimal`) are
+/// left where they are.
+fn restore_misplaced_regional_files(fnt: &mut U8Node, paths: &[String]) {
+    for path in paths {
+        let (folder_path, filename) = path.rsplit_once('/').unwrap();
+        if fnt
+            .get(folder_path)
+            .and_then(U8Node::as_folder)
+            .is_none_or(|folder| folder.contains_key(filename))
+        {
+            continue;
+        }
+
+        for standard_folder_path in [ANIM_FOLDER_PATH, BLYT_FOLDER_PATH] {
+            if let Some(node) = fnt
+                .get_mut(standard_folder_path)
+                .and_then(U8Node::as_mut_folder)
+                .and_then(|folder| folder.remove(filename))
+            {
+                trace!("Moving {filename:?} back to {folder_path:?}");
+                fnt.get_mut(folder_path)
+                    .and_then(U8Node::as_mut_folder)
+                    .unwrap()
+                    .insert(filename.to_owned(), node);
+                break;
+            }
+        }
+    }
+}
+
+/// List the regional filenames for the requested regions that are
+/// missing from a FNT root node, as `(region, filename)` pairs in
+/// `Region::DEFAULT_ORDER`.
+fn missing_regional_filenames(
+    fnt: &U8Node,
+    regions: RegionBitFlags,
+) -> Result<Vec<(Region, &'static str)>, ConvertOpeningTitleBetweenRegionsError> {
+    let anim_folder = get_anim_folder(fnt)?;
+    let blyt_folder = get_blyt_folder(fnt)?;
+
+    let mut missing = Vec::new();
+
+    for region in Region::DEFAULT_ORDER {
+        if !regions.contains(region) {
+            continue;
+        }
+        let filenames = &ALL_FILENAMES[region.into()];
+
+        for filename in [
+            filenames.in_press_brlan,
+            filenames.in_title_brlan,
+            filenames.loop_press_brlan,
+            filenames.out_press_brlan,
+        ] {
+            if !anim_folder.contains_key(filename) {
+                missing.push((region, filename));
+            }
+        }
+
+        if !blyt_folder.contains_key(filenames.brlyt) {
+            missing.push((region, filenames.brlyt));
+        }
+    }
+
+    Ok(missing)
+}
+
+/// Build the folder structure of an openingTitle.arc with no files in
+/// it: a top-level "arc" folder containing empty "anim", "blyt", and
+/// "timg" folders. Files can then be added with `U8Node::insert_path()`.
+pub fn empty_openingtitle_tree() -> U8Node {
+    U8Node::Folder(U8FolderNode::from([(
+        "arc".to_owned(),
+        U8Node::Folder(U8FolderNode::from([
+            ("anim".to_owned(), U8Node::Folder(U8FolderNode::new())),
+            ("blyt".to_owned(), U8Node::Folder(U8FolderNode::new())),
+            ("timg".to_owned(), U8Node::Folder(U8FolderNode::new())),
+        ])),
+    )]))
+}
+
+/// Check that a FNT root node with an "arc" folder contains at least
+/// one regional file for at least one region. (FNTs without an "arc"
+/// folder are left for later steps to report on.)
+fn check_is_openingtitle(fnt: &U8Node) -> Result<(), ConvertOpeningTitleBetweenRegionsError> {
+    if fnt
+        .get(ARC_FOLDER_PATH)
+        .and_then(U8Node::as_folder)
+        .is_none()
+    {
+        return Ok(());
+    }
+
+    let anim_folder = fnt.get(ANIM_FOLDER_PATH).and_then(U8Node::as_folder);
+    let blyt_folder = fnt.get(BLYT_FOLDER_PATH).and_then(U8Node::as_folder);
+    let contains = |folder: Option<&U8FolderNode>, filename| {
+        folder.is_some_and(|folder| folder.contains_key(filename))
+    };
+
+    let any_regional_files = Region::all_filenames().any(|(_, filenames)| {
+        [
+            filenames.in_press_brlan,
+            filenames.in_title_brlan,
+            filenames.loop_press_brlan,
+            filenames.out_press_brlan,
+        ]
+        .into_iter()
+        .any(|filename| contains(anim_folder, filename))
+            || contains(blyt_folder, filenames.brlyt)
+    });
+
+    if any_regional_files {
+        Ok(())
+    } else {
+        Err(ConvertOpeningTitleBetweenRegionsError::NotAnOpeningTitle)
+    }
+}
+
+/// Get the set of regions that a FNT root node has every regional
+/// filename for.
+fn complete_regions(
+    fnt: &U8Node,
+) -> Result<RegionBitFlags, ConvertOpeningTitleBetweenRegionsError> {
+    let mut regions = RegionBitFlags::ALL;
+    for (region, _) in missing_regional_filenames(fnt, RegionBitFlags::ALL)? {
+        regions.remove(region);
+    }
+    Ok(regions)
+}
+
+/// Read openingTitle.arc from a `Seek+Read`, and list the regional
+/// filenames for the requested regions that it doesn't contain, as
+/// `(region, filename)` pairs in `Region::DEFAULT_ORDER`. An empty
+/// `Vec` means the arc fully supports all of those regions.
+pub fn find_missing_regional_filenames<SR: Seek + Read>(
+    mut in_file: SR,
+    regions: RegionBitFlags,
+) -> Result<Vec<(Region, &'static str)>, ConvertOpeningTitleBetweenRegionsError> {
+    let (fnt, _) = u8_fnt::read(&mut in_file)?;
+    missing_regional_filenames(&fnt, regions)
+}
+
+/// Read openingTitle.arc from a `Seek+Read`, and check whether it's
+/// already region-free: that is, whether it contains every regional
+/// filename for all six regions.
+pub fn is_region_free<SR: Seek + Read>(
+    reader: &mut SR,
+) -> Result<bool, ConvertOpeningTitleBetweenRegionsError> {
+    let (fnt, _) = u8_fnt::read(reader)?;
+    Ok(complete_regions(&fnt)? == RegionBitFlags::ALL)
+}
+
+/// How ready an openingTitle.arc is to be used as a region-free arc. See
+/// `region_free_readiness()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RegionFreeStatus {
+    /// Every regional filename for all six regions is present, and all
+    /// of the regions share the same data for each kind of file.
+    RegionFree,
+    /// Not region-free yet, but every kind of regional file exists for
+    /// at least one region, so a region-free arc can be built from it.
+    Convertible,
+    /// Some kinds of regional file (e.g. "brlyt") don't exist for any
+    /// region, so a region-free arc can't be built. The missing kinds
+    /// are listed in the same order and with the same names as in
+    /// `ConversionReport::sources`.
+    Incomplete(Vec<&'static str>),
+}
+
+/// Classify a FNT root node as already region-free, convertible to
+/// region-free, or incomplete, with a list of missing file kinds in the
+/// last case.
+///
+/// Only the FNT is checked, so files for different regions count as
+/// shared data only if they point to the same data in the FAT.
+/// Identical copies of the data at different offsets make the arc
+/// `Convertible`, not `RegionFree`.
+pub fn region_free_readiness(
+    fnt: &U8Node,
+) -> Result<RegionFreeStatus, ConvertOpeningTitleBetweenRegionsError> {
+    let anim_folder = get_anim_folder(fnt)?;
+    let blyt_folder = get_blyt_folder(fnt)?;
+
+    // The file nodes for each kind of regional file, from every region
+    // that has one
+    let mut nodes: [Vec<&U8FileNode>; 5] = Default::default();
+    for (_, filenames) in Region::all_filenames() {
+        let files = [
+            anim_folder.get(filenames.in_press_brlan),
+            anim_folder.get(filenames.in_title_brlan),
+            anim_folder.get(filenames.loop_press_brlan),
+            anim_folder.get(filenames.out_press_brlan),
+            blyt_folder.get(filenames.brlyt),
+        ];
+        for (kind_nodes, file) in nodes.iter_mut().zip(files) {
+            if let Some(file) = file.and_then(U8Node::as_file) {
+                kind_nodes.push(file);
+            }
+        }
+    }
+
+    let kinds = [
+        "inPress brlan",
+        "inTitle brlan",
+        "loopPress brlan",
+        "outPress brlan",
+        "brlyt",
+    ];
+    let mut missing = Vec::new();
+    let mut region_free = true;
+    for (kind, kind_nodes) in kinds.into_iter().zip(nodes) {
+        if kind_nodes.is_empty() {
+            missing.push(kind);
+        }
+        if kind_nodes.len() < Region::DEFAULT_ORDER.len()
+            || !kind_nodes.windows(2).all(|pair| pair[0] == pair[1])
+        {
+            region_free = false;
+        }
+    }
+
+    Ok(if !missing.is_empty() {
+        RegionFreeStatus::Incomplete(missing)
+    } else if region_free {
+        RegionFreeStatus::RegionFree
+    } else {
+        RegionFreeStatus::Convertible
+    })
+}
+
+/// A region with regional files in only one of the anim and blyt
+/// folders. See `region_coverage_mismatches()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RegionCoverageMismatch {
+    /// The region has at least one brlan, but no brlyt.
+    AnimWithoutBlyt(Region),
+    /// The region has a brlyt, but no brlans.
+    BlytWithoutAnim(Region),
+}
+
+impl RegionCoverageMismatch {
+    /// Classify a region by whether it has any brlans and whether it
+    /// has a brlyt.
+    fn check(region: Region, has_brlans: bool, has_brlyt: bool) -> Option<Self> {
+        match (has_brlans, has_brlyt) {
+            (true, false) => Some(Self::AnimWithoutBlyt(region)),
+            (false, true) => Some(Self::BlytWithoutAnim(region)),
+            _ => None,
+        }
+    }
+
+    /// The region this mismatch is about.
+    pub fn region(self) -> Region {
+        match self {
+            Self::AnimWithoutBlyt(region) | Self::BlytWithoutAnim(region) => region,
+        }
+    }
+}
+
+impl fmt::Display for RegionCoverageMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::AnimWithoutBlyt(region) => {
+                write!(f, "region {region} has anim files but no brlyt")
+            }
+            Self::BlytWithoutAnim(region) => {
+                write!(f, "region {region} has a brlyt but no anim files")
+            }
+        }
+    }
+}
+
+/// Find every region that has regional files in the anim folder but
+/// not the blyt folder, or the other way around. Regions that are only
+/// missing some of their brlans aren't included, since that's not a
+/// mismatch between the folders.
+pub fn region_coverage_mismatches(
+    fnt: &U8Node,
+) -> Result<Vec<RegionCoverageMismatch>, ConvertOpeningTitleBetweenRegionsError> {
+    let anim_folder = get_anim_folder(fnt)?;
+    let blyt_folder = get_blyt_folder(fnt)?;
+    let is_file = |folder: &U8FolderNode, filename: &str| {
+        folder.get(filename).and_then(U8Node::as_file).is_some()
+    };
+
+    Ok(Region::all_filenames()
+        .filter_map(|(region, filenames)| {
+            let has_brlans = [
+                filenames.in_press_brlan,
+                filenames.in_title_brlan,
+                filenames.loop_press_brlan,
+                filenames.out_press_brlan,
+            ]
+            .into_iter()
+            .any(|filename| is_file(anim_folder, filename));
+            let has_brlyt = is_file(blyt_folder, filenames.brlyt);
+            RegionCoverageMismatch::check(region, has_brlans, has_brlyt)
+        })
+        .collect())
+}
+
+/// Count how many regional filenames a conversion from `from_regions`
+/// to `to_regions` would add to and remove from a FNT root node, as
+/// `(added, removed)`, without actually converting anything.
+///
+/// This assumes the conversion succeeds with the default options: every
+/// `to_regions` filename ends up present, and every `from_regions`
+/// filename that isn't also a `to_regions` one is removed. Filenames
+/// that are already present and kept aren't counted either way.
+pub fn filename_delta(
+    fnt: &U8Node,
+    from_regions: RegionBitFlags,
+    to_regions: RegionBitFlags,
+) -> Result<(usize, usize), ConvertOpeningTitleBetweenRegionsError> {
+    let anim_folder = get_anim_folder(fnt)?;
+    let blyt_folder = get_blyt_folder(fnt)?;
+
+    let mut added = 0;
+    let mut removed = 0;
+    for (region, filenames) in Region::all_filenames() {
+        for (folder, filename) in [
+            (anim_folder, filenames.in_press_brlan),
+            (anim_folder, filenames.in_title_brlan),
+            (anim_folder, filenames.loop_press_brlan),
+            (anim_folder, filenames.out_press_brlan),
+            (blyt_folder, filenames.brlyt),
+        ] {
+            let present = folder.contains_key(filename);
+            if to_regions.contains(region) {
+                if !present {
+                    added += 1;
+                }
+            } else if from_regions.contains(region) && present {
+                removed += 1;
+            }
+        }
+    }
+
+    Ok((added, removed))
+}
+
+/// Every conflict that a conversion would run into, as found by
+/// `find_conflicts()`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Conflicts {
+    /// Pairs of regional files with different data, where only one of
+    /// them can be kept. `file_a` is always the copy from the region
+    /// that appears earliest in `from_regions`.
+    pub file_contents: Vec<ConflictInfo>,
+
+    /// Existing filenames that adding the `to_regions` filenames would
+    /// overwrite.
+    pub filenames: Vec<ConflictInfo>,
+}
+
+/// Read openingTitle.arc from a `Seek+Read`, and list every conflict
+/// that converting it from `from_regions` to `to_regions` would run
+/// into, without resolving any of them. Nothing is written.
+///
+/// Each conflict's `resolution` is what `ConflictStrategy::Overwrite`
+/// would do about it, so a frontend can show that as the default and
+/// let the user pick for each one (e.g. with
+/// `ConflictStrategy::Callback`).
+pub fn find_conflicts<SR: Seek + Read>(
+    reader: &mut SR,
+    from_regions: &[Region],
+    to_regions: RegionBitFlags,
+) -> Result<Conflicts, ConvertOpeningTitleBetweenRegionsError> {
+    for (i, region) in from_regions.iter().enumerate() {
+        if from_regions[..i].contains(region) {
+            return Err(ConvertOpeningTitleBetweenRegionsError::DuplicateSourceRegion(*region));
+        }
+    }
+
+    let u8_fnt::ParsedArc {
+        root: mut fnt,
+        data_table_offs,
+        ..
+    } = u8_fnt::read_parsed_arc(reader)?;
+    check_is_openingtitle(&fnt)?;
+
+    let all_regional_files =
+        remove_regional_files(&mut fnt, RegionBitFlags::from_iter(from_regions))?;
+    check_region_coverage(&all_regional_files, from_regions)?;
+    let file_contents = check_all_files_for_conflicts(
+        &all_regional_files,
+        from_regions,
+        data_table_offs,
+        reader,
+        &HashFileComparator,
+        ConflictStrategy::Overwrite,
+        None,
+    )
+    .io_context("checking for conflicts")?;
+
+    let (regional_files, _) = select_regional_files(&all_regional_files, from_regions, None)?;
+    let filenames = add_new_filenames(
+        &mut fnt,
+        &regional_files,
+        to_regions,
+        ConflictStrategy::Overwrite,
+        None,
+        &|filename: &str| filename.to_owned(),
+    )?;
+
+    Ok(Conflicts {
+        file_contents,
+        filenames,
+    })
+}
+
 /// Compare the data for two `NamedU8FileNode`s, and return `Err` if
 /// they don't match.
 ///
 /// If `previous_found_file` is None, it's set to a clone of the other
-/// file, and no actual check (or hashing) is performed.
+/// file, and no actual check (or digesting) is performed.
 ///
-/// If `previous_found_file` is Some, the new file is hashed (and also
-/// the old file, if it hasn't yet been hashed), and the hashes are
-/// compared.
-fn check_file_pair_for_conflicts<SR: Seek + Read>(
+/// If `previous_found_file` is Some, the new file is digested (and
+/// also the old file, if it hasn't yet been digested), and the digests
+/// are compared.
+fn check_file_pair_for_conflicts<SR: Seek + Read, C: FileComparator>(
     previous_found_file: &mut Option<NamedU8FileNode>,
-    previous_found_file_hash: &mut u64,
+    previous_found_file_digest: &mut Option<C::Digest>,
     found_file: &NamedU8FileNode,
     data_table_offs: u32,
     reader: &mut SR,
+    comparator: &C,
 ) -> Result<(), ConvertOpeningTitleBetweenRegionsError> {
     if let Some(previous_found_file) = &*previous_found_file {
         if found_file.node == previous_found_file.node {
@@ -389,44 +1401,44 @@ fn check_file_pair_for_conflicts<SR: Seek + Read>(
             return Ok(());
         }
 
-        // Ensure that the first file's hash has been calculated
-        if *previous_found_file_hash == 0 {
+        // Ensure that the first file's digest has been calculated
+        if previous_found_file_digest.is_none() {
             trace!(
-                "Calculating hash of {:?} ({:#x}-{:#x})",
+                "Calculating digest of {:?} ({:#x}-{:#x})",
                 previous_found_file.filename,
                 previous_found_file.node.offset,
                 previous_found_file.node.offset + previous_found_file.node.size
             );
 
-            *previous_found_file_hash = util::calc_hash_from_file_slice(
+            *previous_found_file_digest = Some(comparator.digest(
                 reader,
-                (data_table_offs + previous_found_file.node.offset).into(),
+                u64::from(data_table_offs) + u64::from(previous_found_file.node.offset),
                 previous_found_file.node.size.try_into().unwrap(),
-            )?;
+            )?);
         }
 
-        // Calculate the hash of the new file
+        // Calculate the digest of the new file
         trace!(
-            "Calculating hash of {:?} ({:#x}-{:#x})",
+            "Calculating digest of {:?} ({:#x}-{:#x})",
             found_file.filename,
             found_file.node.offset,
             found_file.node.offset + found_file.node.size
         );
 
-        let new_hash = util::calc_hash_from_file_slice(
+        let new_digest = comparator.digest(
             reader,
-            (data_table_offs + found_file.node.offset).into(),
+            u64::from(data_table_offs) + u64::from(found_file.node.offset),
             found_file.node.size.try_into().unwrap(),
         )?;
 
-        if *previous_found_file_hash != new_hash {
+        if previous_found_file_digest.as_ref() != Some(&new_digest) {
             return Err(ConvertOpeningTitleBetweenRegionsError::FileDataConflict(
                 previous_found_file.filename.clone(),
                 found_file.filename.clone(),
             ));
         }
     } else {
-        // We'll calculate the hash later, only if we actually find
+        // We'll calculate the digest later, only if we actually find
         // another file that it needs to be compared against
         *previous_found_file = Some(found_file.clone());
     }
@@ -434,167 +1446,452 @@ fn check_file_pair_for_conflicts<SR: Seek + Read>(
     Ok(())
 }
 
-/// Check all of the provided regional files for data conflicts, and
-/// return an `Err` if any are found.
-fn check_all_files_for_conflicts<SR: Seek + Read>(
+/// Check all of the provided regional files for data conflicts. With
+/// `ConflictStrategy::Fail`, an `Err` is returned for the first one
+/// found; with `ConflictStrategy::Overwrite`, they're all returned
+/// instead. Either way, each file is compared against the copy from the
+/// region that appears earliest in `from_regions` (which is the one
+/// `select_regional_files()` will pick).
+///
+/// With `ConflictStrategy::Callback`, `resolver` decides each conflict
+/// instead. If it picks the later file, that file is compared against
+/// from then on, and `apply_conflict_resolutions()` should be used
+/// before selecting files so that it's the one that gets picked.
+///
+/// Files that have identical nodes (offset and size) are grouped
+/// together first, so each group is only compared once. This matters
+/// for arcs that already support most regions, where many regions'
+/// files are typically shared.
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+fn check_all_files_for_conflicts<SR: Seek + Read, C: FileComparator>(
     all_regional_files: &HashMap<Region, OptionalNamedRegionalFiles>,
+    from_regions: &[Region],
     data_table_offs: u32,
-    mut reader: &mut SR,
-) -> Result<(), ConvertOpeningTitleBetweenRegionsError> {
-    // Keep track of one copy of each file, and their hashes
-    let mut in_press_brlan = None;
-    let mut in_title_brlan = None;
-    let mut loop_press_brlan = None;
-    let mut out_press_brlan = None;
-    let mut brlyt = None;
-    let mut in_press_brlan_hash = 0;
-    let mut in_title_brlan_hash = 0;
-    let mut loop_press_brlan_hash = 0;
-    let mut out_press_brlan_hash = 0;
-    let mut brlyt_hash = 0;
-
-    for regional_files in all_regional_files.values() {
-        if let Some(file) = &regional_files.in_press_brlan {
-            check_file_pair_for_conflicts(
-                &mut in_press_brlan,
-                &mut in_press_brlan_hash,
+    reader: &mut SR,
+    comparator: &C,
+    strategy: ConflictStrategy,
+    resolver: Option<ConflictResolver>,
+) -> Result<Vec<ConflictInfo>, ConvertOpeningTitleBetweenRegionsError> {
+    /// Helper function to check all copies of one of the five regional
+    /// files
+    fn check_files<'a, SR: Seek + Read, C: FileComparator>(
+        files: impl Iterator<Item = (Region, &'a NamedU8FileNode)>,
+        data_table_offs: u32,
+        reader: &mut SR,
+        comparator: &C,
+        strategy: ConflictStrategy,
+        resolver: Option<ConflictResolver>,
+        conflicts: &mut Vec<ConflictInfo>,
+    ) -> Result<(), ConvertOpeningTitleBetweenRegionsError> {
+        // Keep track of one copy of the file (and its region), and its
+        // digest
+        let mut previous_found_file = None;
+        let mut previous_found_file_region = None;
+        let mut previous_found_file_digest = None;
+
+        // Only the first file with any given node needs to be checked
+        let mut checked_nodes: Vec<&U8FileNode> = Vec::new();
+
+        for (region, file) in files {
+            if checked_nodes.contains(&&file.node) {
+                trace!(
+                    "Skipping {:?} (same node as a file already checked)",
+                    file.filename
+                );
+                continue;
+            }
+            checked_nodes.push(&file.node);
+            let region_a = *previous_found_file_region.get_or_insert(region);
+
+            match check_file_pair_for_conflicts(
+                &mut previous_found_file,
+                &mut previous_found_file_digest,
                 file,
                 data_table_offs,
-                &mut reader,
-            )?;
+                reader,
+                comparator,
+            ) {
+                Err(ConvertOpeningTitleBetweenRegionsError::FileDataConflict(file_a, file_b))
+                    if strategy != ConflictStrategy::Fail =>
+                {
+                    let mut conflict = ConflictInfo {
+                        kind: ConflictKind::FileContents,
+                        file_a,
+                        region_a,
+                        file_b,
+                        region_b: region,
+                        resolution: ConflictResolution::KeptA,
+                    };
+                    if strategy == ConflictStrategy::Callback {
+                        conflict.resolution = resolver
+                            .and_then(|resolve| resolve(&conflict))
+                            .ok_or_else(|| {
+                                ConvertOpeningTitleBetweenRegionsError::FileDataConflict(
+                                    conflict.file_a.clone(),
+                                    conflict.file_b.clone(),
+                                )
+                            })?;
+                    }
+
+                    if conflict.resolution == ConflictResolution::ReplacedA {
+                        debug!("Replacing {:?} with {:?}", conflict.file_a, conflict.file_b);
+                        previous_found_file = Some(file.clone());
+                        previous_found_file_region = Some(region);
+                        previous_found_file_digest = None;
+                    } else {
+                        debug!(
+                            "Ignoring conflict between {:?} and {:?}",
+                            conflict.file_a, conflict.file_b
+                        );
+                    }
+                    conflicts.push(conflict);
+                }
+                result => result?,
+            }
         }
 
-        if let Some(file) = &regional_files.in_title_brlan {
-            check_file_pair_for_conflicts(
-                &mut in_title_brlan,
-                &mut in_title_brlan_hash,
-                file,
-                data_table_offs,
-                &mut reader,
-            )?;
-        }
+        Ok(())
+    }
 
-        if let Some(file) = &regional_files.loop_press_brlan {
-            check_file_pair_for_conflicts(
-                &mut loop_press_brlan,
-                &mut loop_press_brlan_hash,
-                file,
-                data_table_offs,
-                &mut reader,
-            )?;
+    let files = || {
+        from_regions
+            .iter()
+            .filter_map(|region| Some((*region, all_regional_files.get(region)?)))
+    };
+    let mut conflicts = Vec::new();
+
+    check_files(
+        files().filter_map(|(region, files)| Some((region, files.in_press_brlan.as_ref()?))),
+        data_table_offs,
+        reader,
+        comparator,
+        strategy,
+        resolver,
+        &mut conflicts,
+    )?;
+    check_files(
+        files().filter_map(|(region, files)| Some((region, files.in_title_brlan.as_ref()?))),
+        data_table_offs,
+        reader,
+        comparator,
+        strategy,
+        resolver,
+        &mut conflicts,
+    )?;
+    check_files(
+        files().filter_map(|(region, files)| Some((region, files.loop_press_brlan.as_ref()?))),
+        data_table_offs,
+        reader,
+        comparator,
+        strategy,
+        resolver,
+        &mut conflicts,
+    )?;
+    check_files(
+        files().filter_map(|(region, files)| Some((region, files.out_press_brlan.as_ref()?))),
+        data_table_offs,
+        reader,
+        comparator,
+        strategy,
+        resolver,
+        &mut conflicts,
+    )?;
+    check_files(
+        files().filter_map(|(region, files)| Some((region, files.brlyt.as_ref()?))),
+        data_table_offs,
+        reader,
+        comparator,
+        strategy,
+        resolver,
+        &mut conflicts,
+    )?;
+
+    Ok(conflicts)
+}
+
+/// Fail if a source region has regional files in only one of the anim
+/// and blyt folders, and no source region has any of the other kind.
+/// `select_regional_files()` would fail anyway in that case, but this
+/// explains why before the conflict checks run.
+fn check_region_coverage(
+    all_regional_files: &HashMap<Region, OptionalNamedRegionalFiles>,
+    from_regions: &[Region],
+) -> Result<(), ConvertOpeningTitleBetweenRegionsError> {
+    let coverage: Vec<(Region, bool, bool)> = from_regions
+        .iter()
+        .filter_map(|&region| {
+            let [brlans @ .., brlyt] = all_regional_files.get(&region)?.as_array();
+            Some((region, brlans.iter().any(|f| f.is_some()), brlyt.is_some()))
+        })
+        .collect();
+    let any_brlans = coverage.iter().any(|&(_, has_brlans, _)| has_brlans);
+    let any_brlyt = coverage.iter().any(|&(_, _, has_brlyt)| has_brlyt);
+
+    let mismatch = coverage
+        .into_iter()
+        .filter_map(|(region, has_brlans, has_brlyt)| {
+            RegionCoverageMismatch::check(region, has_brlans, has_brlyt)
+        })
+        .find(|mismatch| match mismatch {
+            RegionCoverageMismatch::AnimWithoutBlyt(_) => !any_brlyt,
+            RegionCoverageMismatch::BlytWithoutAnim(_) => !any_brlans,
+        });
+    match mismatch {
+        Some(mismatch) => {
+            Err(ConvertOpeningTitleBetweenRegionsError::RegionCoverageMismatch(mismatch))
         }
+        None => Ok(()),
+    }
+}
 
-        if let Some(file) = &regional_files.out_press_brlan {
-            check_file_pair_for_conflicts(
-                &mut out_press_brlan,
-                &mut out_press_brlan_hash,
-                file,
-                data_table_offs,
-                &mut reader,
-            )?;
+/// For each file-contents conflict where the later file was chosen
+/// (`ConflictResolution::ReplacedA`), remove that kind of file from
+/// every other region, so that `select_regional_files()` picks the
+/// chosen copy. Conflicts are applied in order, so if a file was
+/// replaced more than once, the last choice wins.
+fn apply_conflict_resolutions(
+    all_regional_files: &mut HashMap<Region, OptionalNamedRegionalFiles>,
+    conflicts: &[ConflictInfo],
+) {
+    for conflict in conflicts {
+        if conflict.kind != ConflictKind::FileContents
+            || conflict.resolution != ConflictResolution::ReplacedA
+        {
+            continue;
         }
 
-        if let Some(file) = &regional_files.brlyt {
-            check_file_pair_for_conflicts(
-                &mut brlyt,
-                &mut brlyt_hash,
-                file,
-                data_table_offs,
-                &mut reader,
-            )?;
+        // Figure out which kind of file it was
+        let Some(chosen_files) = all_regional_files.get(&conflict.region_b) else {
+            continue;
+        };
+        let Some(kind) = chosen_files.as_array().iter().position(|file| {
+            file.as_ref()
+                .is_some_and(|file| file.filename == conflict.file_b)
+        }) else {
+            continue;
+        };
+
+        for (region, files) in all_regional_files.iter_mut() {
+            if *region != conflict.region_b {
+                *files.as_mut_array()[kind] = None;
+            }
         }
     }
-
-    Ok(())
 }
 
+/// (file kind, region) pairs saying which region each regional file was
+/// taken from. See `ConversionReport::sources`.
+pub type RegionalFileSources = Vec<(&'static str, Region)>;
+
 /// Select exactly one of each regional file, favoring the ones from
-/// regions that appear earliest in `from_regions`.
+/// `canonical_region` (if given), and then the ones from regions that
+/// appear earliest in `from_regions`.
+///
+/// Also returns the region each file was taken from.
 fn select_regional_files(
     all_regional_files: &HashMap<Region, OptionalNamedRegionalFiles>,
     from_regions: &[Region],
-) -> Result<RegionalFiles, ConvertOpeningTitleBetweenRegionsError> {
-    // We're going to search for these
+    canonical_region: Option<Region>,
+) -> Result<(RegionalFiles, RegionalFileSources), ConvertOpeningTitleBetweenRegionsError> {
+    // We're going to search for these (along with the regions they
+    // came from)
     let mut in_press_brlan = None;
     let mut in_title_brlan = None;
     let mut loop_press_brlan = None;
     let mut out_press_brlan = None;
     let mut brlyt = None;
 
-    for region in from_regions {
-        if let Some(regional_files) = all_regional_files.get(region) {
+    let regions = canonical_region
+        .into_iter()
+        .chain(from_regions.iter().copied());
+    for region in regions {
+        if let Some(regional_files) = all_regional_files.get(&region) {
             if let Some(file_node) = &regional_files.in_press_brlan {
-                in_press_brlan = in_press_brlan.or_else(|| Some(file_node.node.clone()));
+                in_press_brlan = in_press_brlan.or_else(|| Some((file_node.node.clone(), region)));
             }
 
             if let Some(file_node) = &regional_files.in_title_brlan {
-                in_title_brlan = in_title_brlan.or_else(|| Some(file_node.node.clone()));
+                in_title_brlan = in_title_brlan.or_else(|| Some((file_node.node.clone(), region)));
             }
 
             if let Some(file_node) = &regional_files.loop_press_brlan {
-                loop_press_brlan = loop_press_brlan.or_else(|| Some(file_node.node.clone()));
+                loop_press_brlan =
+                    loop_press_brlan.or_else(|| Some((file_node.node.clone(), region)));
             }
 
             if let Some(file_node) = &regional_files.out_press_brlan {
-                out_press_brlan = out_press_brlan.or_else(|| Some(file_node.node.clone()));
+                out_press_brlan =
+                    out_press_brlan.or_else(|| Some((file_node.node.clone(), region)));
             }
 
             if let Some(file_node) = &regional_files.brlyt {
-                brlyt = brlyt.or_else(|| Some(file_node.node.clone()));
+                brlyt = brlyt.or_else(|| Some((file_node.node.clone(), region)));
             }
         }
     }
 
-    Ok(RegionalFiles {
-        in_press_brlan: in_press_brlan.ok_or_else(|| {
-            ConvertOpeningTitleBetweenRegionsError::MissingFiles("inPress brlan".to_owned())
-        })?,
-        in_title_brlan: in_title_brlan.ok_or_else(|| {
-            ConvertOpeningTitleBetweenRegionsError::MissingFiles("inTitle brlan".to_owned())
-        })?,
-        loop_press_brlan: loop_press_brlan.ok_or_else(|| {
-            ConvertOpeningTitleBetweenRegionsError::MissingFiles("loopPress brlan".to_owned())
-        })?,
-        out_press_brlan: out_press_brlan.ok_or_else(|| {
-            ConvertOpeningTitleBetweenRegionsError::MissingFiles("outPress brlan".to_owned())
-        })?,
-        brlyt: brlyt.ok_or_else(|| {
-            ConvertOpeningTitleBetweenRegionsError::MissingFiles("brlyt".to_owned())
-        })?,
-    })
+    let missing =
+        |kind: &str| ConvertOpeningTitleBetweenRegionsError::MissingFiles(kind.to_owned());
+    let (in_press_brlan, in_press_brlan_region) =
+        in_press_brlan.ok_or_else(|| missing("inPress brlan"))?;
+    let (in_title_brlan, in_title_brlan_region) =
+        in_title_brlan.ok_or_else(|| missing("inTitle brlan"))?;
+    let (loop_press_brlan, loop_press_brlan_region) =
+        loop_press_brlan.ok_or_else(|| missing("loopPress brlan"))?;
+    let (out_press_brlan, out_press_brlan_region) =
+        out_press_brlan.ok_or_else(|| missing("outPress brlan"))?;
+    let (brlyt, brlyt_region) = brlyt.ok_or_else(|| missing("brlyt"))?;
+
+    Ok((
+        RegionalFiles {
+            in_press_brlan,
+            in_title_brlan,
+            loop_press_brlan,
+            out_press_brlan,
+            brlyt,
+        },
+        vec![
+            ("inPress brlan", in_press_brlan_region),
+            ("inTitle brlan", in_title_brlan_region),
+            ("loopPress brlan", loop_press_brlan_region),
+            ("outPress brlan", out_press_brlan_region),
+            ("brlyt", brlyt_region),
+        ],
+    ))
+}
+
+/// Build a new FNT from scratch, containing empty anim and blyt folders
+/// and the TPLs from the original FNT's timg folder (or, with
+/// `keep_timg`, everything in it), but nothing else. The regional files
+/// can then be added with `add_new_filenames()`.
+fn make_minimal_fnt(fnt: &U8Node, keep_timg: bool) -> U8Node {
+    let mut arc = U8FolderNode::from([
+        ("anim".to_owned(), U8Node::Folder(U8FolderNode::new())),
+        ("blyt".to_owned(), U8Node::Folder(U8FolderNode::new())),
+    ]);
+
+    if let Some(timg @ U8Node::Folder(_)) = fnt.get(TIMG_FOLDER_PATH).filter(|_| keep_timg) {
+        arc.insert("timg".to_owned(), timg.clone());
+    } else if let Some(U8Node::Folder(timg)) = fnt.get(TIMG_FOLDER_PATH) {
+        let tpls = timg
+            .iter()
+            .filter(|(name, node)| {
+                matches!(node, U8Node::File(_)) && name.to_lowercase().ends_with(".tpl")
+            })
+            .map(|(name, node)| (name.clone(), node.clone()))
+            .collect();
+        arc.insert("timg".to_owned(), U8Node::Folder(tpls));
+    }
+
+    U8Node::Folder(U8FolderNode::from([(
+        "arc".to_owned(),
+        U8Node::Folder(arc),
+    )]))
+}
+
+/// If China is one of the `to_regions` and the timg folder has no
+/// Chinese title logo, add one pointing to the same data as the logo
+/// shared by the P and E regions (if there is one). Region-free arcs
+/// made from P or E arcs otherwise have no logo for China to use.
+///
+/// TPLs aren't regional files as far as the rest of the conversion is
+/// concerned (they're never removed), so this is the only place a logo
+/// is ever added.
+fn add_china_logo_fallback(fnt: &mut U8Node, to_regions: RegionBitFlags) {
+    if !to_regions.contains(Region::C) {
+        return;
+    }
+    let Some(U8Node::Folder(timg)) = fnt.get_mut(TIMG_FOLDER_PATH) else {
+        return;
+    };
+    if timg.contains_key(CHINA_LOGO_TPL_FILENAME) {
+        return;
+    }
+    let Some(shared_logo @ U8Node::File(_)) = timg.get(SHARED_LOGO_TPL_FILENAME) else {
+        return;
+    };
+
+    info!("Using {SHARED_LOGO_TPL_FILENAME} as {CHINA_LOGO_TPL_FILENAME}");
+    let shared_logo = shared_logo.clone();
+    timg.insert(CHINA_LOGO_TPL_FILENAME.to_owned(), shared_logo);
 }
 
 /// Add new entries to the U8 FNT pointing to (clones of) the indicated
 /// regional-file nodes, with filenames appropriate for the indicated
 /// output region.
 ///
-/// If `ignore_conflicts` is `true` and any of the filenames already
-/// exist, they'll be overwritten. Otherwise, `Err` will be returned.
+/// If `filename_conflict_strategy` is `ConflictStrategy::Overwrite` and
+/// any of the filenames already exist, they'll be overwritten, and
+/// returned as conflicts. With `ConflictStrategy::Callback`, `resolver`
+/// chooses between overwriting and keeping the existing file instead.
+/// Otherwise, `Err` will be returned.
+///
+/// Each filename is passed through `name_transform` before being added.
 fn add_new_filenames(
     fnt: &mut U8Node,
     regional_files: &RegionalFiles,
     regions: RegionBitFlags,
     filename_conflict_strategy: ConflictStrategy,
-) -> Result<(), ConvertOpeningTitleBetweenRegionsError> {
+    resolver: Option<ConflictResolver>,
+    name_transform: NameTransform,
+) -> Result<Vec<ConflictInfo>, ConvertOpeningTitleBetweenRegionsError> {
     /// Helper function to add a single file to a folder, and perform
     /// the optional conflict check if enabled
+    #[allow(clippy::too_many_arguments)]
     fn insert(
         folder: &mut U8FolderNode,
         filename: &str,
         file_node: &U8FileNode,
+        region: Region,
         filename_conflict_strategy: ConflictStrategy,
+        resolver: Option<ConflictResolver>,
+        name_transform: NameTransform,
+        conflicts: &mut Vec<ConflictInfo>,
     ) -> Result<(), ConvertOpeningTitleBetweenRegionsError> {
-        if filename_conflict_strategy == ConflictStrategy::Fail && folder.contains_key(filename) {
-            return Err(
-                ConvertOpeningTitleBetweenRegionsError::FilenameAlreadyExists(filename.to_owned()),
-            );
+        let filename = name_transform(filename);
+
+        // The game looks filenames up case-insensitively, so an
+        // existing file whose name only differs in case counts too
+        let existing = folder
+            .get_key_value(&filename)
+            .map(|(name, _)| name.clone());
+        if let Some(existing) = existing {
+            let mut conflict = ConflictInfo {
+                kind: ConflictKind::Filename,
+                file_a: existing.clone(),
+                region_a: region,
+                file_b: filename.clone(),
+                region_b: region,
+                resolution: ConflictResolution::ReplacedA,
+            };
+            let resolution = match filename_conflict_strategy {
+                ConflictStrategy::Fail => None,
+                ConflictStrategy::Overwrite => Some(ConflictResolution::ReplacedA),
+                ConflictStrategy::Callback => resolver.and_then(|resolve| resolve(&conflict)),
+            };
+            let Some(resolution) = resolution else {
+                return Err(
+                    ConvertOpeningTitleBetweenRegionsError::FilenameAlreadyExists(existing, region),
+                );
+            };
+            conflict.resolution = resolution;
+            conflicts.push(conflict);
+
+            if resolution == ConflictResolution::KeptA {
+                debug!("Keeping existing file {existing:?}");
+                return Ok(());
+            }
+            debug!("Overwriting existing file {existing:?}");
+            folder.remove(&existing);
         }
 
-        folder.insert(filename.to_owned(), U8Node::File(file_node.clone()));
+        folder.insert(filename, U8Node::File(file_node.clone()));
 
         Ok(())
     }
 
+    let mut conflicts = Vec::new();
+
     for this_region in regions {
         let this_region_name = this_region.into();
         trace!("Adding filenames for {this_region_name:?}");
@@ -605,25 +1902,41 @@ fn add_new_filenames(
             folder,
             ALL_FILENAMES[this_region_name].in_press_brlan,
             &regional_files.in_press_brlan,
+            this_region,
             filename_conflict_strategy,
+            resolver,
+            name_transform,
+            &mut conflicts,
         )?;
         insert(
             folder,
             ALL_FILENAMES[this_region_name].in_title_brlan,
             &regional_files.in_title_brlan,
+            this_region,
             filename_conflict_strategy,
+            resolver,
+            name_transform,
+            &mut conflicts,
         )?;
         insert(
             folder,
             ALL_FILENAMES[this_region_name].loop_press_brlan,
             &regional_files.loop_press_brlan,
+            this_region,
             filename_conflict_strategy,
+            resolver,
+            name_transform,
+            &mut conflicts,
         )?;
         insert(
             folder,
             ALL_FILENAMES[this_region_name].out_press_brlan,
             &regional_files.out_press_brlan,
+            this_region,
             filename_conflict_strategy,
+            resolver,
+            name_transform,
+            &mut conflicts,
         )?;
 
         let folder = get_mut_blyt_folder(fnt)?;
@@ -632,43 +1945,61 @@ fn add_new_filenames(
             folder,
             ALL_FILENAMES[this_region_name].brlyt,
             &regional_files.brlyt,
+            this_region,
             filename_conflict_strategy,
+            resolver,
+            name_transform,
+            &mut conflicts,
         )?;
     }
 
-    Ok(())
+    Ok(conflicts)
 }
 
-/// Given a U8 root node and a reader for its corresponding file data,
-/// build a new FAT and update the FNT offsets to match. The FAT will be
-/// written starting at the writer's current position.
+/// A piece of file data to be copied from the original FAT into the
+/// new one. Both offsets are relative to the start of their FATs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct FatCopy {
+    old_offset: u32,
+    new_offset: u32,
+    size: u32,
+}
+
+/// Given a U8 root node, decide where each file's data will go in a new
+/// FAT, and update the FNT offsets to match. Returns the list of copies
+/// needed to build that FAT, in order of increasing new offset.
 ///
 /// File data may be shuffled in order to match the order Nintendo would
 /// usually put them in. More importantly for this application, files
 /// with matching offsets are guaranteed to be maintained as such.
-fn build_new_fat<SR: Seek + Read, SW: Seek + Write>(
+///
+/// Files are visited in FNT order (via `U8Node::iter_mut()`), so the
+/// layout only depends on the tree's contents, not on how it was built.
+///
+/// Returns `TooLarge` if the new FAT wouldn't fit in 32 bits.
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+fn lay_out_new_fat(
     fnt: &mut U8Node,
-    data_table_offs: u32,
-    in_file: &mut SR,
-    out_file: &mut SW,
-) -> Result<(), ConvertOpeningTitleBetweenRegionsError> {
-    let initial_fat_offset = out_file.stream_position()?;
-
-    // A mapping {old_offset: new_offset}, which lets us keep track of
-    // where we've remapped the original file data offsets to.
+) -> Result<Vec<FatCopy>, ConvertOpeningTitleBetweenRegionsError> {
+    // A mapping {(old_offset, size): new_offset}, which lets us keep
+    // track of where we've remapped the original file data offsets to.
     // This is needed for detecting and properly handling files that
-    // have the same offsets.
+    // have the same offsets. The size is part of the key because a
+    // zero-size file can share its offset with the (non-empty) file
+    // after it, and the latter still needs its data copied.
     let mut offset_remapping = HashMap::new();
+    let mut copies = Vec::new();
 
     // Inner function for recursion
-    fn visit_node<SR: Seek + Read, SW: Seek + Write>(
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", skip_all, fields(name))
+    )]
+    fn visit_node(
         name: &str,
         node: &mut U8Node,
-        data_table_offs: u32,
-        in_file: &mut SR,
-        out_file: &mut SW,
-        initial_fat_offset: u64,
-        offset_remapping: &mut HashMap<u32, u32>,
+        offset_remapping: &mut HashMap<(u32, u32), u32>,
+        copies: &mut Vec<FatCopy>,
     ) -> Result<(), ConvertOpeningTitleBetweenRegionsError> {
         trace!("Visiting {name:?}");
 
@@ -677,59 +2008,104 @@ fn build_new_fat<SR: Seek + Read, SW: Seek + Write>(
                 ref mut offset,
                 size,
             }) => {
-                // Have we seen this offset before?
-                *offset = if let Some(value) = offset_remapping.get(offset) {
+                // Have we seen this offset (and size) before?
+                *offset = if let Some(value) = offset_remapping.get(&(*offset, *size)) {
                     // Yes? Re-use the same updated offset, and move on
                     *value
                 } else {
-                    // Align to 0x20,
-                    util::write_zeros_to_align_to(out_file, 0x20, 0)?;
-                    // check the new offset,
-                    let new_file_pos: u32 = (out_file.stream_position()? - initial_fat_offset)
-                        .try_into()
-                        .unwrap();
-                    trace!("Moved from {:#x} to {:#x}", *offset, new_file_pos);
-                    // seek the input file to the original offset,
-                    in_file.seek(SeekFrom::Start((data_table_offs + *offset).into()))?;
-                    // copy the file data across,
-                    util::read_from_into(in_file, out_file, (*size).try_into().unwrap())?;
+                    // Put the data right after the previous copy,
+                    // aligned to 0x20 (if there's room),
+                    let new_offset = copies.last().map_or(0, |copy: &FatCopy| {
+                        (u64::from(copy.new_offset) + u64::from(copy.size) + 0x1f) & !0x1f
+                    });
+                    let new_end = new_offset + u64::from(*size);
+                    if new_end > u32::MAX.into() {
+                        return Err(ConvertOpeningTitleBetweenRegionsError::TooLarge(new_end));
+                    }
+                    let new_offset = new_offset as u32;
+                    trace!("Moved from {:#x} to {:#x}", *offset, new_offset);
+                    // make a note to copy the data there,
+                    copies.push(FatCopy {
+                        old_offset: *offset,
+                        new_offset,
+                        size: *size,
+                    });
                     // and add a new entry to offset_remapping.
-                    offset_remapping.insert(*offset, new_file_pos);
+                    offset_remapping.insert((*offset, *size), new_offset);
                     // And update the actual node offset
-                    new_file_pos
+                    new_offset
                 }
             }
             U8Node::Folder(_) => {
                 // Visit all the children recursively
                 trace!("Visiting children of {name:?}");
                 for (child_name, child) in node.iter_mut() {
-                    visit_node(
-                        child_name,
-                        child,
-                        data_table_offs,
-                        in_file,
-                        out_file,
-                        initial_fat_offset,
-                        offset_remapping,
-                    )?;
+                    visit_node(child_name, child, offset_remapping, copies)?;
                 }
                 trace!("Returning to parent dir ({name:?})");
             }
         };
-
         Ok(())
     }
 
     // Visit recursively, starting at the root node
-    visit_node(
-        "(root)",
-        fnt,
-        data_table_offs,
-        in_file,
-        out_file,
-        initial_fat_offset,
-        &mut offset_remapping,
-    )
+    visit_node("(root)", fnt, &mut offset_remapping, &mut copies)?;
+    Ok(copies)
+}
+
+/// Count the bytes of file data that a FNT's files refer to, counting
+/// data shared by multiple files only once.
+fn used_fat_bytes(fnt: &U8Node) -> u64 {
+    /// Helper function to collect file data ranges, recursively
+    fn visit_node(node: &U8Node, ranges: &mut HashSet<(u32, u32)>) {
+        match node {
+            U8Node::File(U8FileNode { offset, size }) => {
+                ranges.insert((*offset, *size));
+            }
+            U8Node::Folder(children) => {
+                for child in children.values() {
+                    visit_node(child, ranges);
+                }
+            }
+        }
+    }
+
+    let mut ranges = HashSet::new();
+    visit_node(fnt, &mut ranges);
+    ranges.into_iter().map(|(_, size)| u64::from(size)).sum()
+}
+
+/// Get the total size of a FAT laid out by `lay_out_new_fat()`.
+fn new_fat_size(copies: &[FatCopy]) -> u32 {
+    copies.last().map_or(0, |copy| copy.new_offset + copy.size)
+}
+
+/// Build a FAT laid out by `lay_out_new_fat()`, by copying file data
+/// from the original FAT. The new FAT will be written starting at the
+/// writer's current position.
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(copies = copies.len())))]
+fn write_new_fat<SR: Seek + Read, SW: Seek + Write>(
+    copies: &[FatCopy],
+    data_table_offs: u32,
+    in_file: &mut SR,
+    out_file: &mut SW,
+) -> Result<(), ConvertOpeningTitleBetweenRegionsError> {
+    let initial_fat_offset = out_file.stream_position()?;
+    let mut buf = vec![0; FAT_COPY_BUFFER_SIZE];
+
+    for copy in copies {
+        util::write_zeros_to_align_to(out_file, 0x20, initial_fat_offset)?;
+        debug_assert_eq!(
+            out_file.stream_position()? - initial_fat_offset,
+            u64::from(copy.new_offset)
+        );
+        in_file.seek(SeekFrom::Start(
+            u64::from(data_table_offs) + u64::from(copy.old_offset),
+        ))?;
+        util::copy_n(in_file, out_file, copy.size.into(), &mut buf)?;
+    }
+
+    Ok(())
 }
 
 /// Specifies strategies for handling the types of conflicts that can
@@ -749,86 +2125,615 @@ pub struct ConvertOpeningTitleBetweenRegionsConflictStrategies {
     pub filenames: ConflictStrategy,
 }
 
+/// A function that transforms each regional filename before it's added
+/// to the output arc. See
+/// `ConvertOpeningTitleBetweenRegionsOptions::name_transform`.
+pub type NameTransform<'a> = &'a dyn Fn(&str) -> String;
+
+/// A function that decides how to resolve a conflict, for
+/// `ConflictStrategy::Callback`. It's given the conflict with
+/// `resolution` set to what `ConflictStrategy::Overwrite` would do, and
+/// returns the resolution to use instead, or `None` to abort the
+/// conversion. See
+/// `ConvertOpeningTitleBetweenRegionsOptions::resolve_conflict`.
+pub type ConflictResolver<'a> = &'a dyn Fn(&ConflictInfo) -> Option<ConflictResolution>;
+
+/// Points during a conversion at which
+/// `ConvertOpeningTitleBetweenRegionsOptions::inspect` is called.
+#[non_exhaustive]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum ConversionStage {
+    /// The original FNT has just been read (step 1).
+    Parsed,
+    /// The new regional filenames have just been added (step 5).
+    FilenamesAdded,
+    /// The new FAT has just been laid out (step 6), so every file
+    /// node's offset is now where its data will be in the output.
+    LaidOut,
+}
+
+/// A function that's shown the FNT at various points during a
+/// conversion. See `ConvertOpeningTitleBetweenRegionsOptions::inspect`.
+pub type InspectHook<'a> = &'a dyn Fn(ConversionStage, &U8Node);
+
+/// A function that's told how long each conversion step took, as the
+/// step's name (e.g. "read_fnt") and its wall-clock duration. See
+/// `ConvertOpeningTitleBetweenRegionsOptions::step_timings`.
+pub type StepTimingsHook<'a> = &'a dyn Fn(&'static str, Duration);
+
+/// Additional options for
+/// `convert_openingtitle_between_regions_with_options()`. The defaults
+/// match the behavior of `convert_openingtitle_between_regions()`.
+#[derive(Copy, Clone, Default)]
+pub struct ConvertOpeningTitleBetweenRegionsOptions<'a> {
+    /// Fail with `ConvertOpeningTitleBetweenRegionsError::TooFewRegions`
+    /// if the output arc would fully support fewer than this many
+    /// regions. 0 disables the check.
+    pub min_regions: usize,
+
+    /// Instead of preserving every file in the input arc, only include
+    /// the regional files for `to_regions` and the TPLs in the timg
+    /// folder. Everything else (and all other regions' files) is
+    /// dropped.
+    pub minimal: bool,
+
+    /// Also remove the regional files of regions that aren't in
+    /// `from_regions`, instead of leaving them untouched. Unlike
+    /// `minimal`, non-regional files are kept. With every region in
+    /// `to_regions`, this gives the smallest region-free arc that can
+    /// be made without discarding anything else.
+    pub strip_unselected: bool,
+
+    /// Fail with `ConvertOpeningTitleBetweenRegionsError::OutputTooLarge`
+    /// if the output would be larger than this many bytes. This is
+    /// detected before anything is written to the output file.
+    pub max_size: Option<u64>,
+
+    /// Before converting, rename files whose names clearly correspond
+    /// to a regional file, but aren't quite right (e.g. a different
+    /// index, or an extra suffix), to the canonical names. Use
+    /// `find_nonstandard_regional_filenames()` to preview the renames.
+    pub normalize_names: bool,
+
+    /// If set, each regional filename added to the output arc is passed
+    /// through this function first (e.g. to add a prefix for a mod that
+    /// renames its assets). The transformed names must still be unique
+    /// within each folder, or they'll be treated as filename conflicts.
+    /// Note that `min_regions` only counts regions whose files have the
+    /// standard names.
+    pub name_transform: Option<NameTransform<'a>>,
+
+    /// If set, this is called with the FNT at each `ConversionStage`,
+    /// e.g. so that a frontend can display or log it. (The FNT is also
+    /// logged at those points at the debug level regardless.)
+    pub inspect: Option<InspectHook<'a>>,
+
+    /// Stop once the new FAT has been laid out (after the `max_size`
+    /// check), without writing anything to the output file. Combined
+    /// with `inspect`, this shows where each file would end up.
+    pub dry_run: bool,
+
+    /// The order to write each folder's children in. Anything other
+    /// than the default may not work in-game.
+    pub fnt_order: u8_fnt::FntOrder,
+
+    /// If set, each regional file's data is taken from this region
+    /// whenever it has that file, regardless of its position in
+    /// `from_regions`. The order of `from_regions` still decides how
+    /// conflicts are reported, and which region fills in any files this
+    /// one is missing. Has no effect unless this region is also in
+    /// `from_regions`.
+    pub canonical_region: Option<Region>,
+
+    /// Glob patterns (`*` and `?` only) for files to drop from the
+    /// output, matched against each file's full path, such as
+    /// "/arc/anim/file.bak". Matching files are removed right after the
+    /// FNT is read, so excluded regional files are treated as missing.
+    pub exclude: &'a [&'a str],
+
+    /// Keep the data table at the same offset as in the original arc,
+    /// by padding the new FNT, and copy the original FAT over exactly
+    /// as-is (without removing unused data), so that every file's data
+    /// stays at the same absolute position. This makes for very small
+    /// binary diffs. If the new FNT is too large to fit before the
+    /// original data table, a warning is logged and the FAT is laid out
+    /// normally instead.
+    pub pin_data_offset: bool,
+
+    /// Called for each conflict whose strategy is
+    /// `ConflictStrategy::Callback`, to decide how to resolve it (e.g.
+    /// by asking the user). For file-contents conflicts, choosing
+    /// `ReplacedA` means the later region's copy is used instead.
+    pub resolve_conflict: Option<ConflictResolver<'a>>,
+
+    /// If set, this is called as each conversion step finishes, with how
+    /// long it took, e.g. to find out which step dominates for a large
+    /// arc. Steps that aren't reached (because of an error or
+    /// `dry_run`) aren't reported. Leave this unset on targets without
+    /// a clock (such as `wasm32-unknown-unknown`).
+    pub step_timings: Option<StepTimingsHook<'a>>,
+
+    /// For any of `from_regions`' regional files that aren't in the
+    /// anim or blyt folder, search the rest of the arc for files with
+    /// exactly the same names, and use those instead (e.g. for edited
+    /// arcs that keep each region's files in its own subfolder). Files
+    /// found this way are listed in `ConversionReport::found_elsewhere`,
+    /// and are put back where they were found if their names are in the
+    /// output. Other regions' files are still added to the anim and blyt
+    /// folders.
+    pub deep_search: bool,
+
+    /// Leave the timg folder exactly as it is, for arcs with a custom
+    /// logo that shouldn't depend on the region. Normally a logo is
+    /// added for China if it's a target region and doesn't have one
+    /// (see `SHARED_LOGO_TPL_FILENAME`), and `minimal` drops any
+    /// non-TPL files from the folder.
+    pub keep_logo: bool,
+}
+
+impl fmt::Debug for ConvertOpeningTitleBetweenRegionsOptions<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConvertOpeningTitleBetweenRegionsOptions")
+            .field("min_regions", &self.min_regions)
+            .field("minimal", &self.minimal)
+            .field("strip_unselected", &self.strip_unselected)
+            .field("max_size", &self.max_size)
+            .field("normalize_names", &self.normalize_names)
+            .field(
+                "name_transform",
+                &self.name_transform.map(|_| "Fn(&str) -> String"),
+            )
+            .field(
+                "inspect",
+                &self.inspect.map(|_| "Fn(ConversionStage, &U8Node)"),
+            )
+            .field("dry_run", &self.dry_run)
+            .field("fnt_order", &self.fnt_order)
+            .field("canonical_region", &self.canonical_region)
+            .field("exclude", &self.exclude)
+            .field("pin_data_offset", &self.pin_data_offset)
+            .field(
+                "resolve_conflict",
+                &self
+                    .resolve_conflict
+                    .map(|_| "Fn(&ConflictInfo) -> Option<ConflictResolution>"),
+            )
+            .field(
+                "step_timings",
+                &self.step_timings.map(|_| "Fn(&'static str, Duration)"),
+            )
+            .field("deep_search", &self.deep_search)
+            .field("keep_logo", &self.keep_logo)
+            .finish()
+    }
+}
+
+/// Information about a successful conversion, returned by
+/// `convert_openingtitle_between_regions()`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConversionReport {
+    /// Every conflict that was found and resolved (which can only
+    /// happen with `ConflictStrategy::Overwrite` or
+    /// `ConflictStrategy::Callback`), in the order they were found.
+    pub conflicts: Vec<ConflictInfo>,
+
+    /// The region each regional file's data was taken from, as (file
+    /// kind, region) pairs, e.g. `("brlyt", Region::E)`.
+    pub sources: RegionalFileSources,
+
+    /// Size of the new FNT (including the header and padding), in
+    /// bytes.
+    pub fnt_size: u64,
+
+    /// Size of the new FAT, in bytes.
+    pub fat_size: u64,
+
+    /// How many of the FAT's bytes are padding between files, rather
+    /// than file data.
+    pub fat_padding: u64,
+
+    /// Full paths of the regional files that were found outside of the
+    /// anim and blyt folders, with
+    /// `ConvertOpeningTitleBetweenRegionsOptions::deep_search`.
+    pub found_elsewhere: Vec<String>,
+}
+
 /// Read openingTitle.arc from a `Seek+Read`, create a version of it
 /// with filenames corrected to match the requested output regions, and
 /// write it to a `Seek+Write`. The `Seek+Write` is assumed to be
 /// initially empty.
 ///
 /// The default value for `from_regions` is `Region::DEFAULT_ORDER`.
+///
+/// On success, the writer is handed back along with the report, so
+/// that e.g. a `Cursor<Vec<u8>>` can be turned back into its buffer.
+/// Use `convert_openingtitle_between_regions_in_place()` instead if you
+/// want to keep your own handles, or
+/// `convert_openingtitle_between_regions_with_options()` for more
+/// control over the conversion.
 pub fn convert_openingtitle_between_regions<SR: Seek + Read, SW: Seek + Write>(
     mut in_file: SR,
     mut out_file: SW,
     from_regions: Option<&[Region]>,
     to_regions: RegionBitFlags,
     conflict_strategies: &ConvertOpeningTitleBetweenRegionsConflictStrategies,
-) -> Result<(), ConvertOpeningTitleBetweenRegionsError> {
-    info!("Converting an openingTitle to regions: {to_regions:?}");
+) -> Result<(SW, ConversionReport), ConvertOpeningTitleBetweenRegionsError> {
+    let report = convert_openingtitle_between_regions_in_place(
+        &mut in_file,
+        &mut out_file,
+        from_regions,
+        to_regions,
+        conflict_strategies,
+    )?;
+    Ok((out_file, report))
+}
+
+/// Same as `convert_openingtitle_between_regions()`, but borrows the
+/// reader and writer instead of taking ownership of them.
+pub fn convert_openingtitle_between_regions_in_place<SR: Seek + Read, SW: Seek + Write>(
+    in_file: &mut SR,
+    out_file: &mut SW,
+    from_regions: Option<&[Region]>,
+    to_regions: RegionBitFlags,
+    conflict_strategies: &ConvertOpeningTitleBetweenRegionsConflictStrategies,
+) -> Result<ConversionReport, ConvertOpeningTitleBetweenRegionsError> {
+    convert_openingtitle_between_regions_with_options(
+        in_file,
+        out_file,
+        from_regions,
+        to_regions,
+        conflict_strategies,
+        &ConvertOpeningTitleBetweenRegionsOptions::default(),
+        &HashFileComparator,
+    )
+}
+
+/// Same as `convert_openingtitle_between_regions()`, but for input
+/// that's already in memory, returning the output as a new buffer. This
+/// suits bindings that deal in byte arrays (e.g. for WASM).
+///
+/// Malformed input is always reported as an `Err`, never a panic.
+pub fn convert_openingtitle_between_regions_in_memory(
+    input: &[u8],
+    from_regions: Option<&[Region]>,
+    to_regions: RegionBitFlags,
+    conflict_strategies: &ConvertOpeningTitleBetweenRegionsConflictStrategies,
+) -> Result<(Vec<u8>, ConversionReport), ConvertOpeningTitleBetweenRegionsError> {
+    let (out_buf, report) = convert_openingtitle_between_regions(
+        Cursor::new(input),
+        Cursor::new(Vec::new()),
+        from_regions,
+        to_regions,
+        conflict_strategies,
+    )?;
+    Ok((out_buf.into_inner(), report))
+}
+
+/// Keeps track of which conversion step is running: logs each step as
+/// it starts, and (with the `tracing` feature) gives each one its own
+/// span, exited when the next step starts so that steps are siblings
+/// rather than nested in each other. Also times the steps, if
+/// `step_timings` is set.
+struct StepTracker<'a> {
+    step_timings: Option<StepTimingsHook<'a>>,
+    current: Option<(&'static str, Option<Instant>)>,
+    #[cfg(feature = "tracing")]
+    span: Option<tracing::span::EnteredSpan>,
+}
+
+impl<'a> StepTracker<'a> {
+    /// The number of steps in a full conversion.
+    const TOTAL_STEPS: u32 = 8;
+
+    fn new(step_timings: Option<StepTimingsHook<'a>>) -> Self {
+        Self {
+            step_timings,
+            current: None,
+            #[cfg(feature = "tracing")]
+            span: None,
+        }
+    }
+
+    /// Finish the current step (if any), and start a new one. `name` is
+    /// what's reported to `step_timings` and tracing, and `message` is
+    /// what's logged.
+    fn start(&mut self, step: u32, name: &'static str, message: &str) {
+        self.finish();
+        info!("[{step}/{}] {message}", Self::TOTAL_STEPS);
+        #[cfg(feature = "tracing")]
+        {
+            self.span = Some(tracing::info_span!("step", step, name).entered());
+        }
+        self.current = Some((name, self.step_timings.map(|_| Instant::now())));
+    }
+
+    /// Finish the current step (if any), reporting how long it took.
+    fn finish(&mut self) {
+        #[cfg(feature = "tracing")]
+        self.span.take();
+        if let Some((name, Some(start))) = self.current.take() {
+            let duration = start.elapsed();
+            debug!("Step {name} took {duration:?}");
+            if let Some(step_timings) = self.step_timings {
+                step_timings(name, duration);
+            }
+        }
+    }
+}
 
-    const TOTAL_STEPS: u32 = 9;
+/// Same as `convert_openingtitle_between_regions()`, but with
+/// additional options, and using the provided `FileComparator` to check
+/// regional files for conflicting data (instead of
+/// `HashFileComparator`).
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        skip_all,
+        fields(
+            ?from_regions,
+            ?to_regions,
+            conflicts = tracing::field::Empty,
+            fnt_size = tracing::field::Empty,
+            fat_size = tracing::field::Empty,
+        )
+    )
+)]
+pub fn convert_openingtitle_between_regions_with_options<
+    SR: Seek + Read,
+    SW: Seek + Write,
+    C: FileComparator,
+>(
+    mut in_file: SR,
+    mut out_file: SW,
+    from_regions: Option<&[Region]>,
+    to_regions: RegionBitFlags,
+    conflict_strategies: &ConvertOpeningTitleBetweenRegionsConflictStrategies,
+    options: &ConvertOpeningTitleBetweenRegionsOptions,
+    comparator: &C,
+) -> Result<ConversionReport, ConvertOpeningTitleBetweenRegionsError> {
+    info!("Converting an openingTitle to regions: {to_regions:?}");
 
     let from_regions = match from_regions {
         Some(regions) => regions,
         None => &Region::DEFAULT_ORDER,
     };
+    for (i, region) in from_regions.iter().enumerate() {
+        if from_regions[..i].contains(region) {
+            return Err(ConvertOpeningTitleBetweenRegionsError::DuplicateSourceRegion(*region));
+        }
+    }
+
+    let mut steps = StepTracker::new(options.step_timings);
 
     // Read FNT
-    info!("[1/{TOTAL_STEPS}] Reading original FNT...");
-    let (mut fnt, data_table_offs) = u8_fnt::read(&mut in_file)?;
+    steps.start(1, "read_fnt", "Reading original FNT...");
+    let u8_fnt::ParsedArc {
+        root: mut fnt,
+        data_table_offs,
+        reserved,
+        root_name,
+        ..
+    } = u8_fnt::read_parsed_arc(&mut in_file)?;
     debug!("\n{fnt}");
+    if let Some(inspect) = options.inspect {
+        inspect(ConversionStage::Parsed, &fnt);
+    }
+
+    if options.normalize_names {
+        for normalization in normalize::normalize_regional_filenames(&mut fnt)? {
+            info!("Renamed {normalization}");
+        }
+    }
+    for path in remove_excluded_files(&mut fnt, options.exclude) {
+        info!("Excluded {path:?}");
+    }
+    // (With deep search, the regional files might not be in the anim
+    // and blyt folders at all, so missing files are left for step 4 to
+    // report)
+    if !options.deep_search {
+        check_is_openingtitle(&fnt)?;
+    }
 
     // Find existing regional files, make a note of their positions, and
     // delete them
-    info!("[2/{TOTAL_STEPS}] Removing all regional files...");
-    let all_regional_files =
+    steps.start(2, "remove_regional_files", "Removing all regional files...");
+    let mut all_regional_files =
         remove_regional_files(&mut fnt, RegionBitFlags::from_iter(from_regions))?;
+    let found_elsewhere = if options.deep_search {
+        remove_misplaced_regional_files(
+            &mut fnt,
+            RegionBitFlags::from_iter(from_regions),
+            &mut all_regional_files,
+        )
+    } else {
+        Vec::new()
+    };
+    if options.strip_unselected {
+        debug!("Discarding regional files from unselected regions");
+        remove_regional_files(&mut fnt, !RegionBitFlags::from_iter(from_regions))?;
+    }
+    check_region_coverage(&all_regional_files, from_regions)?;
 
     // Check for conflicts
-    if conflict_strategies.file_contents == ConflictStrategy::Fail {
-        info!("[3/{TOTAL_STEPS}] Checking for conflicts...");
-        check_all_files_for_conflicts(&all_regional_files, data_table_offs, &mut in_file)?;
-    }
+    steps.start(3, "check_conflicts", "Checking for conflicts...");
+    let mut report = ConversionReport {
+        conflicts: check_all_files_for_conflicts(
+            &all_regional_files,
+            from_regions,
+            data_table_offs,
+            &mut in_file,
+            comparator,
+            conflict_strategies.file_contents,
+            options.resolve_conflict,
+        )
+        .io_context("checking for conflicts")?,
+        found_elsewhere,
+        ..Default::default()
+    };
+    apply_conflict_resolutions(&mut all_regional_files, &report.conflicts);
 
     // Select the regional files that will be preserved in the output
     // file
-    info!("[4/{TOTAL_STEPS}] Selecting regional files...");
-    let regional_files = select_regional_files(&all_regional_files, from_regions)?;
+    steps.start(4, "select_regional_files", "Selecting regional files...");
+    if let Some(region) = options.canonical_region {
+        if !from_regions.contains(&region) {
+            warn!("Canonical region {region} isn't one of the regions being converted from, so it has no effect");
+        }
+    }
+    let (regional_files, sources) =
+        select_regional_files(&all_regional_files, from_regions, options.canonical_region)?;
+    report.sources = sources;
     debug!("\n{fnt}");
 
+    if options.minimal {
+        debug!("Discarding all non-regional files except TPLs");
+        fnt = make_minimal_fnt(&fnt, options.keep_logo);
+    }
+
     // Add new filenames as requested by the user
-    info!("[5/{TOTAL_STEPS}] Adding new regional filenames...");
-    add_new_filenames(
+    steps.start(5, "add_filenames", "Adding new regional filenames...");
+    report.conflicts.extend(add_new_filenames(
         &mut fnt,
         &regional_files,
         to_regions,
         conflict_strategies.filenames,
-    )?;
+        options.resolve_conflict,
+        options
+            .name_transform
+            .unwrap_or(&|filename: &str| filename.to_owned()),
+    )?);
+    if !options.keep_logo {
+        add_china_logo_fallback(&mut fnt, to_regions);
+    }
+    restore_misplaced_regional_files(&mut fnt, &report.found_elsewhere);
     debug!("\n{fnt}");
+    if let Some(inspect) = options.inspect {
+        inspect(ConversionStage::FilenamesAdded, &fnt);
+    }
 
-    // We don't yet have the correct file data offsets in the FNT, but
-    // its length won't change when we fill those in.
-    // So we can serialize it in memory to check what its length will be
-    info!("[6/{TOTAL_STEPS}] Predicting size of new FNT...");
-    let mut tmp_cursor = Cursor::new(Vec::new());
-    u8_fnt::write(&mut tmp_cursor, &fnt)?;
-    let fnt_length = tmp_cursor.into_inner().len();
+    if options.min_regions > 0 {
+        let num_regions = complete_regions(&fnt)?.len();
+        debug!("Output will support {num_regions} complete region(s)");
+        if num_regions < options.min_regions {
+            return Err(ConvertOpeningTitleBetweenRegionsError::TooFewRegions(
+                num_regions,
+                options.min_regions,
+            ));
+        }
+    }
+
+    // Decide where all of the file data will go, so that the FNT can be
+    // finalized before anything is written
+    steps.start(6, "lay_out_fat", "Laying out new FAT...");
+    let mut new_arc = u8_fnt::ParsedArc {
+        root: fnt,
+        data_table_offs: 0,
+        reserved,
+        root_name,
+        header: u8_fnt::U8Header::default(),
+    };
+    let mut fnt_length = new_arc.serialized_fnt_len();
     info!("...new FNT size will be {fnt_length:#x}");
+    if fnt_length as f64 > f64::from(data_table_offs) * FNT_GROWTH_WARNING_RATIO {
+        warn!(
+            "New FNT ({fnt_length:#x} bytes) is much larger than the original ({data_table_offs:#x} bytes)"
+        );
+    }
+
+    let pinned_data_table_offs = if !options.pin_data_offset {
+        None
+    } else if fnt_length <= data_table_offs as usize {
+        Some(data_table_offs)
+    } else {
+        warn!(
+            "New FNT ({fnt_length:#x} bytes) doesn't fit before the original data table \
+             ({data_table_offs:#x}), so the data can't stay in place"
+        );
+        None
+    };
+    let fat_copies = if pinned_data_table_offs.is_some() {
+        // Keep the original FAT exactly as it was, files' offsets and
+        // all (including any data that's no longer used)
+        let in_file_len = in_file
+            .seek(SeekFrom::End(0))
+            .io_context("reading original FAT")?;
+        let size = in_file_len.saturating_sub(data_table_offs.into());
+        fnt_length = data_table_offs as usize;
+        vec![FatCopy {
+            old_offset: 0,
+            new_offset: 0,
+            size: size
+                .try_into()
+                .map_err(|_| ConvertOpeningTitleBetweenRegionsError::TooLarge(in_file_len))?,
+        }]
+    } else {
+        lay_out_new_fat(&mut new_arc.root)?
+    };
+    debug!("\n{}", new_arc.root);
+    if let Some(inspect) = options.inspect {
+        inspect(ConversionStage::LaidOut, &new_arc.root);
+    }
+
+    report.fnt_size = fnt_length as u64;
+    report.fat_size = new_fat_size(&fat_copies).into();
+    report.fat_padding = if pinned_data_table_offs.is_some() {
+        report
+            .fat_size
+            .saturating_sub(used_fat_bytes(&new_arc.root))
+    } else {
+        report.fat_size
+            - fat_copies
+                .iter()
+                .map(|copy| u64::from(copy.size))
+                .sum::<u64>()
+    };
 
-    // Write nulls to reserve space
-    info!("[7/{TOTAL_STEPS}] Writing nulls to reserve space for FNT...");
-    out_file.write_all(&vec![0; fnt_length])?;
+    #[cfg(feature = "tracing")]
+    tracing::Span::current()
+        .record("conflicts", report.conflicts.len())
+        .record("fnt_size", report.fnt_size)
+        .record("fat_size", report.fat_size);
+
+    // Every file's data offset is relative to the start of the file, so
+    // the FAT fitting in 32 bits by itself isn't enough
+    let total_size = report.fnt_size + report.fat_size;
+    if total_size > u32::MAX.into() {
+        return Err(ConvertOpeningTitleBetweenRegionsError::TooLarge(total_size));
+    }
 
-    // Write the FAT and update offsets in the FNT
-    info!("[8/{TOTAL_STEPS}] Building new FAT and updating FNT...");
-    build_new_fat(&mut fnt, data_table_offs, &mut in_file, &mut out_file)?;
-    debug!("\n{fnt}");
+    if let Some(max_size) = options.max_size {
+        if total_size > max_size {
+            return Err(ConvertOpeningTitleBetweenRegionsError::OutputTooLarge(
+                total_size, max_size,
+            ));
+        }
+    }
+
+    if options.dry_run {
+        steps.finish();
+        info!("Dry run, so not writing anything");
+        return Ok(report);
+    }
 
-    // Go back and write the real FNT
-    info!("[9/{TOTAL_STEPS}] Writing final FNT...");
-    out_file.seek(SeekFrom::Start(0))?;
-    u8_fnt::write(&mut out_file, &fnt)?;
+    steps.start(7, "write_fnt", "Writing new FNT...");
+    let written_fnt_length = u8_fnt::write_parsed_arc_with_options(
+        &mut out_file,
+        &new_arc,
+        &u8_fnt::WriteOptions {
+            order: options.fnt_order,
+            data_table_offs: pinned_data_table_offs,
+            ..Default::default()
+        },
+    )
+    .io_context("writing new FNT")?;
+    debug_assert_eq!(written_fnt_length as usize, fnt_length);
+    steps.start(8, "write_fat", "Writing new FAT...");
+    write_new_fat(&fat_copies, data_table_offs, &mut in_file, &mut out_file)
+        .io_context("writing new FAT")?;
+    steps.finish();
 
     info!("Done switching regions!");
-    Ok(())
+    Ok(report)
 }
 
 #[cfg(test)]
@@ -836,6 +2741,10 @@ pub fn convert_openingtitle_between_regions<SR: Seek + Read, SW: Seek + Write>(
 mod tests {
     use super::*;
 
+    use std::io::Cursor;
+
+    use itertools::Itertools;
+
     type TestResult = Result<(), Box<dyn std::error::Error>>;
 
     /// Helper function to make an openingTitle FNT for a particular set
@@ -919,41 +2828,136 @@ mod tests {
         map
     }
 
-    mod remove_regional_files {
+    mod region {
         use super::*;
 
         #[test]
-        fn test_empty() -> TestResult {
-            let mut root = U8Node::Folder(U8FolderNode::new());
-            let regional_files = remove_regional_files(&mut root, RegionBitFlags::from(Region::P));
-            assert!(regional_files.is_err());
+        fn test_all() -> TestResult {
+            assert_eq!(Region::ALL, Region::DEFAULT_ORDER);
+            assert_eq!(RegionBitFlags::from_iter(Region::ALL), RegionBitFlags::ALL);
             Ok(())
         }
 
         #[test]
-        fn test_simple() -> TestResult {
-            let regional_files = RegionalFiles {
-                in_press_brlan: U8FileNode {
-                    offset: 0x1,
-                    size: 0x1,
-                },
-                in_title_brlan: U8FileNode {
-                    offset: 0x2,
-                    size: 0x2,
-                },
-                loop_press_brlan: U8FileNode {
-                    offset: 0x3,
-                    size: 0x3,
-                },
-                out_press_brlan: U8FileNode {
-                    offset: 0x4,
-                    size: 0x4,
-                },
-                brlyt: U8FileNode {
-                    offset: 0x5,
-                    size: 0x5,
-                },
-            };
+        fn test_from_filename() -> TestResult {
+            for (region, filenames) in Region::all_filenames() {
+                assert_eq!(
+                    Region::from_filename(filenames.in_press_brlan),
+                    Some(region)
+                );
+                assert_eq!(Region::from_filename(filenames.brlyt), Some(region));
+            }
+            assert_eq!(
+                Region::from_filename("OPENINGTITLE_eu_00_inPress.brlan"),
+                Some(Region::P)
+            );
+            assert_eq!(Region::from_filename("openingTitle_13"), Some(Region::J));
+            assert_eq!(Region::from_filename("openingTitle_130.brlyt"), None);
+            assert_eq!(Region::from_filename("openingTitle_UK_00.brlyt"), None);
+            assert_eq!(Region::from_filename("openingTitle.brlyt"), None);
+            Ok(())
+        }
+
+        #[test]
+        fn test_market_name() -> TestResult {
+            assert_eq!(
+                Region::ALL.map(Region::market_name),
+                [
+                    "International",
+                    "North America",
+                    "Japan",
+                    "Korea",
+                    "Taiwan",
+                    "China"
+                ]
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn test_display() -> TestResult {
+            assert_eq!(Region::P.to_string(), "P");
+            assert_eq!(Region::E.to_string(), "E");
+            assert_eq!(
+                ConvertOpeningTitleBetweenRegionsError::FilenameAlreadyExists(
+                    ALL_FILENAMES["P"].brlyt.to_owned(),
+                    Region::P
+                )
+                .to_string(),
+                "\"openingTitle_EU_00.brlyt\" already exists (needed for region P \
+                 (international, uses EU_00 filenames))"
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn test_check_filename_table() -> TestResult {
+            assert_eq!(check_filename_table(), Vec::<String>::new());
+            Ok(())
+        }
+    }
+
+    mod empty_openingtitle_tree {
+        use super::*;
+
+        #[test]
+        fn test_insert_regional_files() -> TestResult {
+            let mut root = empty_openingtitle_tree();
+            assert_eq!(complete_regions(&root)?, RegionBitFlags::empty());
+            assert!(root.get(TIMG_FOLDER_PATH).is_some());
+
+            let file = || U8Node::File(U8FileNode { offset: 0, size: 1 });
+            let filenames = Region::K.filenames();
+            for filename in [
+                filenames.in_press_brlan,
+                filenames.in_title_brlan,
+                filenames.loop_press_brlan,
+                filenames.out_press_brlan,
+            ] {
+                root.insert_path(&format!("{ANIM_FOLDER_PATH}/{filename}"), file())?;
+            }
+            root.insert_path(&format!("{BLYT_FOLDER_PATH}/{}", filenames.brlyt), file())?;
+
+            assert_eq!(complete_regions(&root)?, RegionBitFlags::from(Region::K));
+            Ok(())
+        }
+    }
+
+    mod remove_regional_files {
+        use super::*;
+
+        #[test]
+        fn test_empty() -> TestResult {
+            let mut root = U8Node::Folder(U8FolderNode::new());
+            let regional_files = remove_regional_files(&mut root, RegionBitFlags::from(Region::P));
+            assert!(regional_files.is_err());
+            Ok(())
+        }
+
+        #[test]
+        fn test_simple() -> TestResult {
+            let regional_files = RegionalFiles {
+                in_press_brlan: U8FileNode {
+                    offset: 0x1,
+                    size: 0x1,
+                },
+                in_title_brlan: U8FileNode {
+                    offset: 0x2,
+                    size: 0x2,
+                },
+                loop_press_brlan: U8FileNode {
+                    offset: 0x3,
+                    size: 0x3,
+                },
+                out_press_brlan: U8FileNode {
+                    offset: 0x4,
+                    size: 0x4,
+                },
+                brlyt: U8FileNode {
+                    offset: 0x5,
+                    size: 0x5,
+                },
+            };
 
             // Input FNT has files for P, E, and J...
             let mut root =
@@ -996,6 +3000,295 @@ mod tests {
         }
     }
 
+    mod find_missing_regional_filenames {
+        use super::*;
+
+        #[test]
+        fn test_simple() -> TestResult {
+            let regional_files = RegionalFiles {
+                in_press_brlan: U8FileNode {
+                    offset: 0x0,
+                    size: 0x0,
+                },
+                in_title_brlan: U8FileNode {
+                    offset: 0x0,
+                    size: 0x0,
+                },
+                loop_press_brlan: U8FileNode {
+                    offset: 0x0,
+                    size: 0x0,
+                },
+                out_press_brlan: U8FileNode {
+                    offset: 0x0,
+                    size: 0x0,
+                },
+                brlyt: U8FileNode {
+                    offset: 0x0,
+                    size: 0x0,
+                },
+            };
+
+            // P is complete, and E is only missing its brlyt
+            let mut root = make_openingtitle_fnt(Region::P | Region::E, &regional_files);
+            get_mut_blyt_folder(&mut root)?.remove(ALL_FILENAMES["E"].brlyt);
+
+            let mut buf = Cursor::new(Vec::new());
+            u8_fnt::write(&mut buf, &root)?;
+            buf.seek(SeekFrom::Start(0))?;
+
+            assert_eq!(
+                find_missing_regional_filenames(&mut buf, Region::P | Region::E)?,
+                vec![(Region::E, ALL_FILENAMES["E"].brlyt)]
+            );
+
+            // The other four regions are missing entirely
+            buf.seek(SeekFrom::Start(0))?;
+            assert_eq!(
+                find_missing_regional_filenames(&mut buf, RegionBitFlags::ALL)?.len(),
+                1 + 4 * 5
+            );
+            buf.seek(SeekFrom::Start(0))?;
+            assert!(!is_region_free(&mut buf)?);
+
+            let root = make_openingtitle_fnt(RegionBitFlags::ALL, &regional_files);
+            let mut buf = Cursor::new(Vec::new());
+            u8_fnt::write(&mut buf, &root)?;
+            buf.seek(SeekFrom::Start(0))?;
+            assert!(is_region_free(&mut buf)?);
+
+            Ok(())
+        }
+    }
+
+    mod region_free_readiness {
+        use super::*;
+
+        #[test]
+        fn test_simple() -> TestResult {
+            let file = |offset| U8FileNode { offset, size: 0x10 };
+            let regional_files = RegionalFiles {
+                in_press_brlan: file(0x0),
+                in_title_brlan: file(0x20),
+                loop_press_brlan: file(0x40),
+                out_press_brlan: file(0x60),
+                brlyt: file(0x80),
+            };
+
+            let mut root = make_openingtitle_fnt(RegionBitFlags::ALL, &regional_files);
+            assert_eq!(region_free_readiness(&root)?, RegionFreeStatus::RegionFree);
+
+            // All names present, but one region's brlyt has its own data
+            get_mut_blyt_folder(&mut root)?.insert(
+                ALL_FILENAMES["K"].brlyt.to_owned(),
+                U8Node::File(file(0xa0)),
+            );
+            assert_eq!(region_free_readiness(&root)?, RegionFreeStatus::Convertible);
+
+            // Only one region
+            let mut root = make_openingtitle_fnt(RegionBitFlags::from(Region::J), &regional_files);
+            assert_eq!(region_free_readiness(&root)?, RegionFreeStatus::Convertible);
+
+            // Missing some kinds of files entirely
+            get_mut_blyt_folder(&mut root)?.remove(ALL_FILENAMES["J"].brlyt);
+            get_mut_anim_folder(&mut root)?.remove(ALL_FILENAMES["J"].in_title_brlan);
+            assert_eq!(
+                region_free_readiness(&root)?,
+                RegionFreeStatus::Incomplete(vec!["inTitle brlan", "brlyt"])
+            );
+            Ok(())
+        }
+    }
+
+    mod filename_delta {
+        use super::*;
+
+        #[test]
+        fn test_simple() -> TestResult {
+            let file = |offset| U8FileNode { offset, size: 0x10 };
+            let regional_files = RegionalFiles {
+                in_press_brlan: file(0x0),
+                in_title_brlan: file(0x20),
+                loop_press_brlan: file(0x40),
+                out_press_brlan: file(0x60),
+                brlyt: file(0x80),
+            };
+            let root = make_openingtitle_fnt(Region::E | Region::J, &regional_files);
+
+            // Converting to region-free adds the other four regions' files
+            assert_eq!(
+                filename_delta(&root, RegionBitFlags::ALL, RegionBitFlags::ALL)?,
+                (20, 0)
+            );
+            // Swapping one region for another
+            assert_eq!(
+                filename_delta(&root, Region::E.into(), Region::P.into())?,
+                (5, 5)
+            );
+            // Regions that aren't in `from_regions` are left alone
+            assert_eq!(
+                filename_delta(&root, Region::E.into(), Region::J.into())?,
+                (0, 5)
+            );
+            assert_eq!(
+                filename_delta(&root, Region::K.into(), Region::J.into())?,
+                (0, 0)
+            );
+            Ok(())
+        }
+    }
+
+    mod region_coverage_mismatches {
+        use super::*;
+
+        #[test]
+        fn test_simple() -> TestResult {
+            let file = |offset| U8FileNode { offset, size: 4 };
+            let regional_files = RegionalFiles {
+                in_press_brlan: file(0),
+                in_title_brlan: file(0),
+                loop_press_brlan: file(0),
+                out_press_brlan: file(0),
+                brlyt: file(0),
+            };
+
+            let mut root = make_openingtitle_fnt(Region::P | Region::E, &regional_files);
+            assert!(region_coverage_mismatches(&root)?.is_empty());
+
+            // E loses its brlyt, and J only has one
+            get_mut_blyt_folder(&mut root)?.remove(ALL_FILENAMES["E"].brlyt);
+            get_mut_blyt_folder(&mut root)?
+                .insert(ALL_FILENAMES["J"].brlyt.to_owned(), U8Node::File(file(0)));
+            assert_eq!(
+                region_coverage_mismatches(&root)?,
+                [
+                    RegionCoverageMismatch::AnimWithoutBlyt(Region::E),
+                    RegionCoverageMismatch::BlytWithoutAnim(Region::J),
+                ]
+            );
+
+            // Only missing some brlans isn't a mismatch
+            get_mut_anim_folder(&mut root)?.remove(ALL_FILENAMES["P"].in_title_brlan);
+            assert_eq!(region_coverage_mismatches(&root)?.len(), 2);
+
+            Ok(())
+        }
+
+        #[test]
+        fn test_conversion() -> TestResult {
+            let file = |offset| U8FileNode { offset, size: 4 };
+            let regional_files = RegionalFiles {
+                in_press_brlan: file(0),
+                in_title_brlan: file(0),
+                loop_press_brlan: file(0),
+                out_press_brlan: file(0),
+                brlyt: file(0),
+            };
+
+            let mut root = make_openingtitle_fnt(Region::P | Region::E, &regional_files);
+            get_mut_blyt_folder(&mut root)?.remove(ALL_FILENAMES["E"].brlyt);
+            let mut buf = Cursor::new(Vec::new());
+            u8_fnt::write(&mut buf, &root)?;
+            buf.write_all(b"abcd")?;
+
+            // P can make up for E's missing brlyt...
+            buf.seek(SeekFrom::Start(0))?;
+            find_conflicts(&mut buf, &[Region::E, Region::P], RegionBitFlags::ALL)?;
+
+            // ...but not if it isn't a source region
+            buf.seek(SeekFrom::Start(0))?;
+            let err = find_conflicts(&mut buf, &[Region::E], RegionBitFlags::ALL).unwrap_err();
+            assert!(matches!(
+                err,
+                ConvertOpeningTitleBetweenRegionsError::RegionCoverageMismatch(
+                    RegionCoverageMismatch::AnimWithoutBlyt(Region::E)
+                )
+            ));
+            assert_eq!(
+                err.to_string(),
+                "region E has anim files but no brlyt, and no other source region has the \
+                 missing files"
+            );
+
+            Ok(())
+        }
+    }
+
+    mod find_conflicts {
+        use super::*;
+
+        #[test]
+        fn test_simple() -> TestResult {
+            let file = |offset| U8FileNode { offset, size: 4 };
+            let regional_files = RegionalFiles {
+                in_press_brlan: file(0),
+                in_title_brlan: file(0),
+                loop_press_brlan: file(0),
+                out_press_brlan: file(0),
+                brlyt: file(0),
+            };
+
+            // E's brlyt has different data from P's, and J already has
+            // one file (which won't be removed, since J isn't a source)
+            let mut root = make_openingtitle_fnt(Region::P | Region::E, &regional_files);
+            get_mut_blyt_folder(&mut root)?
+                .insert(ALL_FILENAMES["E"].brlyt.to_owned(), U8Node::File(file(4)));
+            get_mut_anim_folder(&mut root)?.insert(
+                ALL_FILENAMES["J"].in_press_brlan.to_owned(),
+                U8Node::File(file(0)),
+            );
+
+            let mut buf = Cursor::new(Vec::new());
+            u8_fnt::write(&mut buf, &root)?;
+            buf.write_all(b"abcdwxyz")?;
+            buf.seek(SeekFrom::Start(0))?;
+
+            let conflicts = find_conflicts(
+                &mut buf,
+                &[Region::P, Region::E],
+                Region::P | Region::E | Region::J,
+            )?;
+            assert_eq!(
+                conflicts.file_contents,
+                [ConflictInfo {
+                    kind: ConflictKind::FileContents,
+                    file_a: ALL_FILENAMES["P"].brlyt.to_owned(),
+                    region_a: Region::P,
+                    file_b: ALL_FILENAMES["E"].brlyt.to_owned(),
+                    region_b: Region::E,
+                    resolution: ConflictResolution::KeptA,
+                }]
+            );
+            assert_eq!(
+                conflicts.filenames,
+                [ConflictInfo {
+                    kind: ConflictKind::Filename,
+                    file_a: ALL_FILENAMES["J"].in_press_brlan.to_owned(),
+                    region_a: Region::J,
+                    file_b: ALL_FILENAMES["J"].in_press_brlan.to_owned(),
+                    region_b: Region::J,
+                    resolution: ConflictResolution::ReplacedA,
+                }]
+            );
+
+            // With J as a source too, its file is just another copy
+            buf.seek(SeekFrom::Start(0))?;
+            let conflicts = find_conflicts(
+                &mut buf,
+                &[Region::P, Region::E, Region::J],
+                Region::P | Region::E | Region::J,
+            )?;
+            assert_eq!(conflicts.file_contents.len(), 1);
+            assert!(conflicts.filenames.is_empty());
+
+            buf.seek(SeekFrom::Start(0))?;
+            assert!(matches!(
+                find_conflicts(&mut buf, &[Region::P, Region::P], Region::P.into()),
+                Err(ConvertOpeningTitleBetweenRegionsError::DuplicateSourceRegion(Region::P))
+            ));
+            Ok(())
+        }
+    }
+
     mod check_file_pair_for_conflicts {
         use super::*;
 
@@ -1019,27 +3312,70 @@ mod tests {
             let mut cursor = Cursor::new((0..255).collect::<Vec<u8>>());
 
             let mut current_file = None;
-            let mut current_hash = 0;
+            let mut current_digest = None;
 
             // Check both files -- no errors should be returned
             check_file_pair_for_conflicts(
                 &mut current_file,
-                &mut current_hash,
+                &mut current_digest,
                 &old,
                 10,
                 &mut cursor,
+                &HashFileComparator,
             )?;
             check_file_pair_for_conflicts(
                 &mut current_file,
-                &mut current_hash,
+                &mut current_digest,
                 &new,
                 10,
                 &mut cursor,
+                &HashFileComparator,
             )?;
 
             // Since the nodes (offsets/sizes) are identical, it
-            // shouldn't've needed to calculate any hashes
-            assert_eq!(current_hash, 0);
+            // shouldn't've needed to calculate any digests
+            assert!(current_digest.is_none());
+            Ok(())
+        }
+
+        #[test]
+        fn test_zero_size() -> TestResult {
+            let old = NamedU8FileNode {
+                node: U8FileNode { offset: 0, size: 0 },
+                filename: "old".to_owned(),
+            };
+            let new = NamedU8FileNode {
+                node: U8FileNode {
+                    offset: 0x100,
+                    size: 0,
+                },
+                filename: "new".to_owned(),
+            };
+
+            // Zero-size files have no data to disagree on, even if one
+            // of them points past the end of the file
+            let mut cursor = Cursor::new((0..8).collect::<Vec<u8>>());
+
+            let mut current_file = None;
+            let mut current_digest = None;
+
+            check_file_pair_for_conflicts(
+                &mut current_file,
+                &mut current_digest,
+                &old,
+                0,
+                &mut cursor,
+                &HashFileComparator,
+            )?;
+            check_file_pair_for_conflicts(
+                &mut current_file,
+                &mut current_digest,
+                &new,
+                0,
+                &mut cursor,
+                &HashFileComparator,
+            )?;
+            assert!(current_digest.is_some());
             Ok(())
         }
 
@@ -1059,27 +3395,29 @@ mod tests {
             let mut cursor = Cursor::new(data.to_vec());
 
             let mut current_file = None;
-            let mut current_hash = 0;
+            let mut current_digest = None;
 
             // Check both files -- no errors should be returned
             check_file_pair_for_conflicts(
                 &mut current_file,
-                &mut current_hash,
+                &mut current_digest,
                 &old,
                 3,
                 &mut cursor,
+                &HashFileComparator,
             )?;
             check_file_pair_for_conflicts(
                 &mut current_file,
-                &mut current_hash,
+                &mut current_digest,
                 &new,
                 3,
                 &mut cursor,
+                &HashFileComparator,
             )?;
 
             // The offsets were different, so it should've calculated
-            // hashes in order to compare the data
-            assert_ne!(current_hash, 0);
+            // digests in order to compare the data
+            assert!(current_digest.is_some());
             Ok(())
         }
 
@@ -1099,27 +3437,29 @@ mod tests {
             let mut cursor = Cursor::new(data.to_vec());
 
             let mut current_file = None;
-            let mut current_hash = 0;
+            let mut current_digest = None;
 
             // Check both files -- second one should return an error
             check_file_pair_for_conflicts(
                 &mut current_file,
-                &mut current_hash,
+                &mut current_digest,
                 &old,
                 3,
                 &mut cursor,
+                &HashFileComparator,
             )?;
             assert!(check_file_pair_for_conflicts(
                 &mut current_file,
-                &mut current_hash,
+                &mut current_digest,
                 &new,
                 3,
-                &mut cursor
+                &mut cursor,
+                &HashFileComparator,
             )
             .is_err());
 
-            // And it should've calculated the hash in order to check
-            assert_ne!(current_hash, 0);
+            // And it should've calculated the digest in order to check
+            assert!(current_digest.is_some());
             Ok(())
         }
     }
@@ -1127,6 +3467,62 @@ mod tests {
     mod check_all_files_for_conflicts {
         use super::*;
 
+        #[test]
+        fn test_grouped() -> TestResult {
+            /// A `FileComparator` that counts how many digests it's
+            /// asked to calculate
+            struct CountingFileComparator(std::cell::Cell<usize>);
+
+            impl FileComparator for CountingFileComparator {
+                type Digest = u64;
+
+                fn digest<SR: Seek + Read>(
+                    &self,
+                    reader: &mut SR,
+                    offset: u64,
+                    size: usize,
+                ) -> Result<u64, io::Error> {
+                    self.0.set(self.0.get() + 1);
+                    HashFileComparator.digest(reader, offset, size)
+                }
+            }
+
+            // Two groups of regions, each sharing one copy of every file
+            // (with identical data between the groups)
+            let group_files = |offset| RegionalFiles {
+                in_press_brlan: U8FileNode { offset, size: 0x10 },
+                in_title_brlan: U8FileNode { offset, size: 0x10 },
+                loop_press_brlan: U8FileNode { offset, size: 0x10 },
+                out_press_brlan: U8FileNode { offset, size: 0x10 },
+                brlyt: U8FileNode { offset, size: 0x10 },
+            };
+            let mut files = make_hash_map_to_optional_named_regional_files(
+                Region::P | Region::E | Region::J,
+                &group_files(0x00),
+            );
+            files.extend(make_hash_map_to_optional_named_regional_files(
+                Region::K | Region::W | Region::C,
+                &group_files(0x10),
+            ));
+
+            let mut cursor = Cursor::new(b"0123456789abcdef0123456789abcdef".to_vec());
+
+            let comparator = CountingFileComparator(std::cell::Cell::new(0));
+            check_all_files_for_conflicts(
+                &files,
+                &Region::DEFAULT_ORDER,
+                0,
+                &mut cursor,
+                &comparator,
+                ConflictStrategy::Fail,
+                None,
+            )?;
+
+            // One digest per group, for each of the five files
+            assert_eq!(comparator.0.get(), 2 * 5);
+            Ok(())
+        }
+
         #[test]
         fn test_no_conflicts() -> TestResult {
             let mut files = make_hash_map_to_optional_named_regional_files(
@@ -1184,7 +3580,24 @@ mod tests {
             let data = b"0123456789abcdef0123456789abcdef";
             let mut cursor = Cursor::new(data.to_vec());
 
-            check_all_files_for_conflicts(&files, 0, &mut cursor)?;
+            check_all_files_for_conflicts(
+                &files,
+                &Region::DEFAULT_ORDER,
+                0,
+                &mut cursor,
+                &HashFileComparator,
+                ConflictStrategy::Fail,
+                None,
+            )?;
+            check_all_files_for_conflicts(
+                &files,
+                &Region::DEFAULT_ORDER,
+                0,
+                &mut cursor,
+                &ByteFileComparator,
+                ConflictStrategy::Fail,
+                None,
+            )?;
 
             Ok(())
         }
@@ -1246,19 +3659,170 @@ mod tests {
             let data = b"0123456789abcdef0123_56789abcdef";
             let mut cursor = Cursor::new(data.to_vec());
 
-            assert!(check_all_files_for_conflicts(&files, 0, &mut cursor).is_err());
+            assert!(check_all_files_for_conflicts(
+                &files,
+                &Region::DEFAULT_ORDER,
+                0,
+                &mut cursor,
+                &HashFileComparator,
+                ConflictStrategy::Fail,
+                None
+            )
+            .is_err());
+            assert!(check_all_files_for_conflicts(
+                &files,
+                &Region::DEFAULT_ORDER,
+                0,
+                &mut cursor,
+                &ByteFileComparator,
+                ConflictStrategy::Fail,
+                None
+            )
+            .is_err());
+
+            let conflicts = check_all_files_for_conflicts(
+                &files,
+                &[Region::E, Region::P],
+                0,
+                &mut cursor,
+                &HashFileComparator,
+                ConflictStrategy::Overwrite,
+                None,
+            )?;
+            assert_eq!(
+                conflicts,
+                [
+                    ConflictInfo {
+                        kind: ConflictKind::FileContents,
+                        file_a: ALL_FILENAMES["E"].loop_press_brlan.to_owned(),
+                        region_a: Region::E,
+                        file_b: ALL_FILENAMES["P"].loop_press_brlan.to_owned(),
+                        region_b: Region::P,
+                        resolution: ConflictResolution::KeptA,
+                    },
+                    ConflictInfo {
+                        kind: ConflictKind::FileContents,
+                        file_a: ALL_FILENAMES["E"].out_press_brlan.to_owned(),
+                        region_a: Region::E,
+                        file_b: ALL_FILENAMES["P"].out_press_brlan.to_owned(),
+                        region_b: Region::P,
+                        resolution: ConflictResolution::KeptA,
+                    },
+                ]
+            );
 
             Ok(())
         }
     }
 
-    mod select_regional_files {
+    mod apply_conflict_resolutions {
         use super::*;
 
         #[test]
-        fn test_simple() -> TestResult {
-            let mut files = make_hash_map_to_optional_named_regional_files(
-                RegionBitFlags::from(Region::K),
+        fn test_callback() -> TestResult {
+            let regional_files = |base: u32| RegionalFiles {
+                in_press_brlan: U8FileNode {
+                    offset: base + 0x1,
+                    size: 0x1,
+                },
+                in_title_brlan: U8FileNode {
+                    offset: base + 0x2,
+                    size: 0x2,
+                },
+                loop_press_brlan: U8FileNode {
+                    offset: base + 0x3,
+                    size: 0x3,
+                },
+                out_press_brlan: U8FileNode {
+                    offset: base + 0x4,
+                    size: 0x4,
+                },
+                brlyt: U8FileNode {
+                    offset: base + 0x5,
+                    size: 0x5,
+                },
+            };
+            let mut files = make_hash_map_to_optional_named_regional_files(
+                RegionBitFlags::from(Region::P),
+                &regional_files(0x0),
+            );
+            files.extend(make_hash_map_to_optional_named_regional_files(
+                RegionBitFlags::from(Region::E),
+                &regional_files(0x10),
+            ));
+
+            // (the loopPress and outPress brlans conflict)
+            let data = b"0123456789abcdef0123_56789abcdef";
+            let mut cursor = Cursor::new(data.to_vec());
+
+            // Aborting, or not providing a callback at all
+            let abort: ConflictResolver = &|_| None;
+            for resolver in [None, Some(abort)] {
+                assert!(matches!(
+                    check_all_files_for_conflicts(
+                        &files,
+                        &[Region::E, Region::P],
+                        0,
+                        &mut cursor,
+                        &HashFileComparator,
+                        ConflictStrategy::Callback,
+                        resolver,
+                    ),
+                    Err(ConvertOpeningTitleBetweenRegionsError::FileDataConflict(
+                        _,
+                        _
+                    ))
+                ));
+            }
+
+            // Keep E's loopPress brlan, but use P's outPress brlan
+            let resolve = |conflict: &ConflictInfo| {
+                Some(if conflict.file_b == ALL_FILENAMES["P"].out_press_brlan {
+                    ConflictResolution::ReplacedA
+                } else {
+                    ConflictResolution::KeptA
+                })
+            };
+            let conflicts = check_all_files_for_conflicts(
+                &files,
+                &[Region::E, Region::P],
+                0,
+                &mut cursor,
+                &HashFileComparator,
+                ConflictStrategy::Callback,
+                Some(&resolve),
+            )?;
+            assert_eq!(
+                conflicts
+                    .iter()
+                    .map(|conflict| conflict.resolution)
+                    .collect_vec(),
+                [ConflictResolution::KeptA, ConflictResolution::ReplacedA]
+            );
+
+            apply_conflict_resolutions(&mut files, &conflicts);
+            let (selected, sources) = select_regional_files(&files, &[Region::E, Region::P], None)?;
+            assert_eq!(
+                selected.loop_press_brlan,
+                regional_files(0x10).loop_press_brlan
+            );
+            assert_eq!(
+                selected.out_press_brlan,
+                regional_files(0x0).out_press_brlan
+            );
+            assert_eq!(sources[2], ("loopPress brlan", Region::E));
+            assert_eq!(sources[3], ("outPress brlan", Region::P));
+            Ok(())
+        }
+    }
+
+    mod select_regional_files {
+        use super::*;
+
+        #[test]
+        fn test_simple() -> TestResult {
+            let mut files = make_hash_map_to_optional_named_regional_files(
+                RegionBitFlags::from(Region::K),
                 &RegionalFiles {
                     in_press_brlan: U8FileNode {
                         offset: 0x1,
@@ -1313,7 +3877,7 @@ mod tests {
             // Prioritize K over E (opposite of the default)
             // K is missing in_title_brlan, so it should get that one
             // from E, and the rest from K
-            let selected = select_regional_files(&files, &[Region::K, Region::E])?;
+            let (selected, sources) = select_regional_files(&files, &[Region::K, Region::E], None)?;
 
             assert_eq!(
                 selected,
@@ -1340,6 +3904,34 @@ mod tests {
                     },
                 }
             );
+            assert_eq!(
+                sources,
+                [
+                    ("inPress brlan", Region::K),
+                    ("inTitle brlan", Region::E),
+                    ("loopPress brlan", Region::K),
+                    ("outPress brlan", Region::K),
+                    ("brlyt", Region::K),
+                ]
+            );
+
+            // A canonical region wins over the priority order, but
+            // still falls back to it for files it doesn't have
+            let (_, sources) =
+                select_regional_files(&files, &[Region::E, Region::K], Some(Region::K))?;
+            assert_eq!(
+                sources,
+                [
+                    ("inPress brlan", Region::K),
+                    ("inTitle brlan", Region::E),
+                    ("loopPress brlan", Region::K),
+                    ("outPress brlan", Region::K),
+                    ("brlyt", Region::K),
+                ]
+            );
+            let (_, sources) =
+                select_regional_files(&files, &[Region::K, Region::E], Some(Region::E))?;
+            assert!(sources.iter().all(|&(_, region)| region == Region::E));
 
             Ok(())
         }
@@ -1402,7 +3994,7 @@ mod tests {
 
             // in_title_brlan is missing from all regions, so this
             // should fail
-            assert!(select_regional_files(&files, &[Region::K, Region::E]).is_err());
+            assert!(select_regional_files(&files, &[Region::K, Region::E], None).is_err());
 
             Ok(())
         }
@@ -1448,6 +4040,8 @@ mod tests {
                 &regional_files,
                 RegionBitFlags::from(Region::K),
                 ConflictStrategy::default(),
+                None,
+                &str::to_owned,
             )?;
 
             assert_eq!(
@@ -1494,6 +4088,8 @@ mod tests {
                 &regional_files,
                 RegionBitFlags::ALL,
                 ConflictStrategy::default(),
+                None,
+                &str::to_owned,
             )?;
 
             assert_eq!(
@@ -1547,7 +4143,9 @@ mod tests {
                 &mut root,
                 &regional_files,
                 RegionBitFlags::from(Region::W),
-                ConflictStrategy::Fail
+                ConflictStrategy::Fail,
+                None,
+                &str::to_owned,
             )
             .is_err());
 
@@ -1592,18 +4190,196 @@ mod tests {
                 },
             };
 
-            add_new_filenames(
+            let conflicts = add_new_filenames(
                 &mut root,
                 &regional_files,
                 RegionBitFlags::from(Region::W),
                 ConflictStrategy::Overwrite,
+                None,
+                &str::to_owned,
+            )?;
+
+            assert_eq!(
+                root,
+                make_openingtitle_fnt(RegionBitFlags::from(Region::W), &regional_files)
+            );
+            assert_eq!(
+                conflicts,
+                [ConflictInfo {
+                    kind: ConflictKind::Filename,
+                    file_a: ALL_FILENAMES["W"].loop_press_brlan.to_owned(),
+                    region_a: Region::W,
+                    file_b: ALL_FILENAMES["W"].loop_press_brlan.to_owned(),
+                    region_b: Region::W,
+                    resolution: ConflictResolution::ReplacedA,
+                }]
+            );
+
+            // Keeping the existing file with a callback instead
+            let existing_file = U8FileNode {
+                offset: 0x10,
+                size: 0,
+            };
+            get_mut_anim_folder(&mut root)?.insert(
+                ALL_FILENAMES["W"].loop_press_brlan.to_owned(),
+                U8Node::File(existing_file.clone()),
+            );
+            let conflicts = add_new_filenames(
+                &mut root,
+                &regional_files,
+                RegionBitFlags::from(Region::W),
+                ConflictStrategy::Callback,
+                Some(&|_| Some(ConflictResolution::KeptA)),
+                &str::to_owned,
             )?;
+            assert_eq!(conflicts.len(), 5);
+            assert!(conflicts
+                .iter()
+                .all(|conflict| conflict.resolution == ConflictResolution::KeptA));
+            assert_eq!(
+                root.get(ANIM_FOLDER_PATH)
+                    .and_then(|anim| anim.child(ALL_FILENAMES["W"].loop_press_brlan)),
+                Some(&U8Node::File(existing_file))
+            );
+
+            // Aborting
+            assert!(matches!(
+                add_new_filenames(
+                    &mut root,
+                    &regional_files,
+                    RegionBitFlags::from(Region::W),
+                    ConflictStrategy::Callback,
+                    Some(&|_| None),
+                    &str::to_owned,
+                ),
+                Err(ConvertOpeningTitleBetweenRegionsError::FilenameAlreadyExists(_, Region::W))
+            ));
+
+            Ok(())
+        }
+
+        #[test]
+        fn test_case_variant_conflicts() -> TestResult {
+            let lowercase_brlyt = ALL_FILENAMES["W"].brlyt.to_lowercase();
+            let make_root = || {
+                U8Node::Folder(U8FolderNode::from([(
+                    "arc".to_owned(),
+                    U8Node::Folder(U8FolderNode::from([
+                        ("anim".to_owned(), U8Node::Folder(U8FolderNode::new())),
+                        (
+                            "blyt".to_owned(),
+                            U8Node::Folder(U8FolderNode::from([(
+                                lowercase_brlyt.clone(),
+                                U8Node::File(U8FileNode { offset: 0, size: 0 }),
+                            )])),
+                        ),
+                    ])),
+                )]))
+            };
+            let file = U8FileNode {
+                offset: 0x20,
+                size: 1,
+            };
+            let regional_files = RegionalFiles {
+                in_press_brlan: file.clone(),
+                in_title_brlan: file.clone(),
+                loop_press_brlan: file.clone(),
+                out_press_brlan: file.clone(),
+                brlyt: file,
+            };
+
+            // The game would see two files with the same name, so this
+            // is a conflict even though the names aren't identical
+            let mut root = make_root();
+            assert!(matches!(
+                add_new_filenames(
+                    &mut root,
+                    &regional_files,
+                    RegionBitFlags::from(Region::W),
+                    ConflictStrategy::Fail,
+                    None,
+                    &str::to_owned,
+                ),
+                Err(ConvertOpeningTitleBetweenRegionsError::FilenameAlreadyExists(name, Region::W))
+                    if name == lowercase_brlyt
+            ));
 
+            // Overwriting replaces the case variant, instead of keeping
+            // both
+            let mut root = make_root();
+            let conflicts = add_new_filenames(
+                &mut root,
+                &regional_files,
+                RegionBitFlags::from(Region::W),
+                ConflictStrategy::Overwrite,
+                None,
+                &str::to_owned,
+            )?;
             assert_eq!(
                 root,
                 make_openingtitle_fnt(RegionBitFlags::from(Region::W), &regional_files)
             );
+            assert_eq!(
+                conflicts,
+                [ConflictInfo {
+                    kind: ConflictKind::Filename,
+                    file_a: lowercase_brlyt,
+                    region_a: Region::W,
+                    file_b: ALL_FILENAMES["W"].brlyt.to_owned(),
+                    region_b: Region::W,
+                    resolution: ConflictResolution::ReplacedA,
+                }]
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn test_name_transform() -> TestResult {
+            let mut root = make_openingtitle_fnt(
+                RegionBitFlags::empty(),
+                &RegionalFiles {
+                    in_press_brlan: U8FileNode { offset: 0, size: 0 },
+                    in_title_brlan: U8FileNode { offset: 0, size: 0 },
+                    loop_press_brlan: U8FileNode { offset: 0, size: 0 },
+                    out_press_brlan: U8FileNode { offset: 0, size: 0 },
+                    brlyt: U8FileNode { offset: 0, size: 0 },
+                },
+            );
+            let regional_files = RegionalFiles {
+                in_press_brlan: U8FileNode { offset: 0, size: 1 },
+                in_title_brlan: U8FileNode { offset: 0, size: 2 },
+                loop_press_brlan: U8FileNode { offset: 0, size: 3 },
+                out_press_brlan: U8FileNode { offset: 0, size: 4 },
+                brlyt: U8FileNode { offset: 0, size: 5 },
+            };
+
+            add_new_filenames(
+                &mut root,
+                &regional_files,
+                RegionBitFlags::from(Region::J),
+                ConflictStrategy::Fail,
+                None,
+                &|filename| format!("mod_{filename}"),
+            )?;
 
+            let anim_folder = get_anim_folder(&root)?;
+            assert_eq!(anim_folder.len(), 4);
+            assert!(anim_folder.contains_key("mod_openingTitle_13_inPress.brlan"));
+            assert!(anim_folder.contains_key("mod_openingTitle_13_outPress.brlan"));
+            assert!(get_blyt_folder(&root)?.contains_key("mod_openingTitle_13.brlyt"));
+
+            // Transformed names that collide are conflicts, as usual
+            assert!(matches!(
+                add_new_filenames(
+                    &mut root,
+                    &regional_files,
+                    RegionBitFlags::from(Region::K),
+                    ConflictStrategy::Fail,
+                    None,
+                    &|_| "same".to_owned(),
+                ),
+                Err(ConvertOpeningTitleBetweenRegionsError::FilenameAlreadyExists(_, _))
+            ));
             Ok(())
         }
     }
@@ -1611,6 +4387,23 @@ mod tests {
     mod build_new_fat {
         use super::*;
 
+        /// Helper function to lay out and write a new FAT in one go,
+        /// the same way the conversion does
+        fn build_new_fat(
+            fnt: &mut U8Node,
+            data_table_offs: u32,
+            in_file: &mut Cursor<Vec<u8>>,
+            out_file: &mut Cursor<Vec<u8>>,
+        ) -> Result<(), ConvertOpeningTitleBetweenRegionsError> {
+            let copies = lay_out_new_fat(fnt)?;
+            write_new_fat(&copies, data_table_offs, in_file, out_file)?;
+            assert_eq!(
+                out_file.stream_position()?,
+                u64::from(new_fat_size(&copies))
+            );
+            Ok(())
+        }
+
         #[test]
         fn test_empty() -> TestResult {
             let mut root = U8Node::Folder(U8FolderNode::new());
@@ -1736,6 +4529,133 @@ mod tests {
             );
             Ok(())
         }
+
+        #[test]
+        fn test_zero_size() -> TestResult {
+            // "a" and "c" are empty, and "a" shares its offset with "b",
+            // which isn't
+            let mut root = U8Node::Folder(U8FolderNode::from([
+                (
+                    "a".to_owned(),
+                    U8Node::File(U8FileNode {
+                        offset: 0x10,
+                        size: 0,
+                    }),
+                ),
+                (
+                    "b".to_owned(),
+                    U8Node::File(U8FileNode {
+                        offset: 0x10,
+                        size: 0x10,
+                    }),
+                ),
+                (
+                    "c".to_owned(),
+                    U8Node::File(U8FileNode {
+                        offset: 0x30,
+                        size: 0,
+                    }),
+                ),
+                (
+                    "d".to_owned(),
+                    U8Node::File(U8FileNode {
+                        offset: 0x30,
+                        size: 0,
+                    }),
+                ),
+            ]));
+            let mut in_buf = Cursor::new((0..255).collect::<Vec<u8>>());
+
+            let mut out_buf = Cursor::new(Vec::new());
+            build_new_fat(&mut root, 0, &mut in_buf, &mut out_buf)?;
+
+            assert_eq!(
+                root,
+                U8Node::Folder(U8FolderNode::from([
+                    (
+                        "a".to_owned(),
+                        U8Node::File(U8FileNode {
+                            offset: 0x00,
+                            size: 0
+                        })
+                    ),
+                    (
+                        "b".to_owned(),
+                        U8Node::File(U8FileNode {
+                            offset: 0x00,
+                            size: 0x10
+                        })
+                    ),
+                    (
+                        "c".to_owned(),
+                        U8Node::File(U8FileNode {
+                            offset: 0x20,
+                            size: 0
+                        })
+                    ),
+                    (
+                        "d".to_owned(),
+                        U8Node::File(U8FileNode {
+                            offset: 0x20,
+                            size: 0
+                        })
+                    ),
+                ]))
+            );
+
+            // "b"'s data must still have been copied, even though "a"
+            // was visited first
+            assert_eq!(
+                &out_buf.into_inner(),
+                concat_bytes!(
+                    b"\x10\x11\x12\x13\x14\x15\x16\x17\x18\x19\x1a\x1b\x1c\x1d\x1e\x1f",
+                    b"\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+                )
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn test_deterministic() -> TestResult {
+            // Names that only differ in case, nested folders, and shared
+            // offsets are all sensitive to the order files are visited in
+            let files = [
+                ("B", 0x00, 0x08),
+                ("b", 0x20, 0x08),
+                ("a", 0x40, 0x10),
+                ("A", 0x00, 0x08),
+                ("c", 0x60, 0x04),
+            ];
+
+            // Build the same tree many times, inserting the children in a
+            // different order each time (every folder also gets its
+            // own random iteration order)
+            let mut results = Vec::new();
+            for i in 0..files.len() * 4 {
+                let mut rotated = files;
+                rotated.rotate_left(i % files.len());
+                if i >= files.len() * 2 {
+                    rotated.reverse();
+                }
+
+                let mut folder = U8FolderNode::new();
+                for (name, offset, size) in rotated {
+                    folder.insert(name.to_owned(), U8Node::File(U8FileNode { offset, size }));
+                }
+                let mut root = U8Node::Folder(U8FolderNode::from([
+                    ("x".to_owned(), U8Node::Folder(folder.clone())),
+                    ("X".to_owned(), U8Node::Folder(folder)),
+                ]));
+
+                let copies = lay_out_new_fat(&mut root)?;
+                let mut fnt = Cursor::new(Vec::new());
+                u8_fnt::write(&mut fnt, &root)?;
+                results.push((copies, fnt.into_inner()));
+            }
+
+            assert!(results.iter().all_equal());
+            Ok(())
+        }
     }
 
     mod convert_openingtitle_between_regions {
@@ -1848,55 +4768,207 @@ mod tests {
         }
 
         #[test]
-        fn test_region_free() -> TestResult {
-            let in_root = U8Node::Folder(U8FolderNode::from([(
-                "arc".to_owned(),
-                U8Node::Folder(U8FolderNode::from([
-                    (
-                        "anim".to_owned(),
-                        U8Node::Folder(U8FolderNode::from([
-                            (
-                                ALL_FILENAMES["P"].in_press_brlan.to_owned(),
-                                U8Node::File(U8FileNode {
-                                    offset: 0x00,
-                                    size: 0x8,
-                                }),
-                            ),
-                            (
-                                ALL_FILENAMES["E"].in_title_brlan.to_owned(),
-                                U8Node::File(U8FileNode {
-                                    offset: 0x20,
-                                    size: 0x8,
-                                }),
-                            ),
-                            (
-                                ALL_FILENAMES["J"].loop_press_brlan.to_owned(),
-                                U8Node::File(U8FileNode {
-                                    offset: 0x40,
-                                    size: 0x8,
-                                }),
-                            ),
-                            (
-                                ALL_FILENAMES["K"].out_press_brlan.to_owned(),
-                                U8Node::File(U8FileNode {
-                                    offset: 0x60,
-                                    size: 0x8,
-                                }),
-                            ),
-                        ])),
-                    ),
-                    (
-                        "blyt".to_owned(),
-                        U8Node::Folder(U8FolderNode::from([(
-                            ALL_FILENAMES["W"].brlyt.to_owned(),
-                            U8Node::File(U8FileNode {
-                                offset: 0x80,
-                                size: 0x8,
-                            }),
-                        )])),
-                    ),
-                ])),
-            )]));
+        fn test_from_region_also_in_to_regions() -> TestResult {
+            // The input region's files are removed and then re-added
+            // under the same names, which mustn't count as a filename
+            // conflict
+            let regional_files = RegionalFiles {
+                in_press_brlan: U8FileNode {
+                    offset: 0x00,
+                    size: 0x8,
+                },
+                in_title_brlan: U8FileNode {
+                    offset: 0x20,
+                    size: 0x8,
+                },
+                loop_press_brlan: U8FileNode {
+                    offset: 0x40,
+                    size: 0x8,
+                },
+                out_press_brlan: U8FileNode {
+                    offset: 0x60,
+                    size: 0x8,
+                },
+                brlyt: U8FileNode {
+                    offset: 0x80,
+                    size: 0x8,
+                },
+            };
+
+            let mut in_buf = Cursor::new(Vec::new());
+            u8_fnt::write(
+                &mut in_buf,
+                &make_openingtitle_fnt(RegionBitFlags::from(Region::E), &regional_files),
+            )?;
+            in_buf.write_all(concat_bytes!(
+                b"INPBRLAN\0\0\0\0\0\0\0\0",
+                b"\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+                b"INTBRLAN\0\0\0\0\0\0\0\0",
+                b"\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+                b"LPPBRLAN\0\0\0\0\0\0\0\0",
+                b"\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+                b"OTPBRLAN\0\0\0\0\0\0\0\0",
+                b"\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+                b"THEBRLYT\0\0\0\0\0\0\0\0",
+                b"\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+            ))?;
+
+            in_buf.seek(SeekFrom::Start(0))?;
+            let mut out_buf = Cursor::new(Vec::new());
+
+            let report = convert_openingtitle_between_regions_in_place(
+                &mut in_buf,
+                &mut out_buf,
+                Some(&[Region::E]),
+                Region::E | Region::J,
+                &ConvertOpeningTitleBetweenRegionsConflictStrategies::default(),
+            )?;
+            assert!(report.conflicts.is_empty());
+            assert!(report
+                .sources
+                .iter()
+                .all(|&(_, region)| region == Region::E));
+            assert_eq!(report.fat_size, 0x88);
+            assert_eq!(report.fat_padding, 0x60);
+            assert_eq!(
+                report.fnt_size + report.fat_size,
+                out_buf.get_ref().len() as u64
+            );
+
+            out_buf.seek(SeekFrom::Start(0))?;
+            let (out_root, _) = u8_fnt::read(&mut out_buf)?;
+            assert_eq!(
+                out_root,
+                make_openingtitle_fnt(Region::E | Region::J, &regional_files)
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn test_strip_unselected() -> TestResult {
+            let file = |offset| U8FileNode { offset, size: 0x8 };
+            let mut in_root = make_openingtitle_fnt(
+                RegionBitFlags::from(Region::P),
+                &RegionalFiles {
+                    in_press_brlan: file(0x00),
+                    in_title_brlan: file(0x20),
+                    loop_press_brlan: file(0x40),
+                    out_press_brlan: file(0x60),
+                    brlyt: file(0x80),
+                },
+            );
+
+            // E has its own (different) copies of everything
+            let anim = get_mut_anim_folder(&mut in_root)?;
+            for (filename, offset) in [
+                (ALL_FILENAMES["E"].in_press_brlan, 0xa0),
+                (ALL_FILENAMES["E"].in_title_brlan, 0xc0),
+                (ALL_FILENAMES["E"].loop_press_brlan, 0xe0),
+                (ALL_FILENAMES["E"].out_press_brlan, 0x100),
+            ] {
+                anim.insert(filename.to_owned(), U8Node::File(file(offset)));
+            }
+            anim.insert("something else".to_owned(), U8Node::File(file(0x140)));
+            get_mut_blyt_folder(&mut in_root)?.insert(
+                ALL_FILENAMES["E"].brlyt.to_owned(),
+                U8Node::File(file(0x120)),
+            );
+
+            let mut in_buf = Cursor::new(Vec::new());
+            u8_fnt::write(&mut in_buf, &in_root)?;
+            in_buf.write_all(&(0..0x148).map(|i| i as u8).collect::<Vec<_>>())?;
+            in_buf.seek(SeekFrom::Start(0))?;
+            let mut out_buf = Cursor::new(Vec::new());
+
+            convert_openingtitle_between_regions_with_options(
+                &mut in_buf,
+                &mut out_buf,
+                Some(&[Region::P]),
+                RegionBitFlags::ALL,
+                &ConvertOpeningTitleBetweenRegionsConflictStrategies::default(),
+                &ConvertOpeningTitleBetweenRegionsOptions {
+                    strip_unselected: true,
+                    ..Default::default()
+                },
+                &HashFileComparator,
+            )?;
+
+            out_buf.seek(SeekFrom::Start(0))?;
+            let (out_root, _) = u8_fnt::read(&mut out_buf)?;
+
+            // Every region is supported, using only P's five files (plus
+            // the non-regional file)
+            assert_eq!(complete_regions(&out_root)?.len(), 6);
+            let mut nodes = Vec::new();
+            for folder in [get_anim_folder(&out_root)?, get_blyt_folder(&out_root)?] {
+                nodes.extend(folder.values().filter_map(U8Node::as_file));
+            }
+            assert_eq!(nodes.len(), 6 * 5 + 1);
+            assert_eq!(
+                nodes
+                    .iter()
+                    .map(|node| (node.offset, node.size))
+                    .unique()
+                    .count(),
+                5 + 1
+            );
+            assert_eq!(
+                nodes.iter().map(|node| node.offset + node.size).max(),
+                Some(0xa8)
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn test_region_free() -> TestResult {
+            let in_root = U8Node::Folder(U8FolderNode::from([(
+                "arc".to_owned(),
+                U8Node::Folder(U8FolderNode::from([
+                    (
+                        "anim".to_owned(),
+                        U8Node::Folder(U8FolderNode::from([
+                            (
+                                ALL_FILENAMES["P"].in_press_brlan.to_owned(),
+                                U8Node::File(U8FileNode {
+                                    offset: 0x00,
+                                    size: 0x8,
+                                }),
+                            ),
+                            (
+                                ALL_FILENAMES["E"].in_title_brlan.to_owned(),
+                                U8Node::File(U8FileNode {
+                                    offset: 0x20,
+                                    size: 0x8,
+                                }),
+                            ),
+                            (
+                                ALL_FILENAMES["J"].loop_press_brlan.to_owned(),
+                                U8Node::File(U8FileNode {
+                                    offset: 0x40,
+                                    size: 0x8,
+                                }),
+                            ),
+                            (
+                                ALL_FILENAMES["K"].out_press_brlan.to_owned(),
+                                U8Node::File(U8FileNode {
+                                    offset: 0x60,
+                                    size: 0x8,
+                                }),
+                            ),
+                        ])),
+                    ),
+                    (
+                        "blyt".to_owned(),
+                        U8Node::Folder(U8FolderNode::from([(
+                            ALL_FILENAMES["W"].brlyt.to_owned(),
+                            U8Node::File(U8FileNode {
+                                offset: 0x80,
+                                size: 0x8,
+                            }),
+                        )])),
+                    ),
+                ])),
+            )]));
 
             let mut in_buf = Cursor::new(Vec::new());
             u8_fnt::write(&mut in_buf, &in_root)?;
@@ -1948,5 +5020,1326 @@ mod tests {
             assert_eq!(out_data_offset, 0x580);
             Ok(())
         }
+
+        #[test]
+        fn test_shared_brlyt() -> TestResult {
+            // P and E, with separate (but identical) copies of the
+            // brlans, and a single brlyt that both regions point to
+            let mut in_root = make_openingtitle_fnt(
+                RegionBitFlags::from(Region::P),
+                &RegionalFiles {
+                    in_press_brlan: U8FileNode {
+                        offset: 0x00,
+                        size: 0x8,
+                    },
+                    in_title_brlan: U8FileNode {
+                        offset: 0x20,
+                        size: 0x8,
+                    },
+                    loop_press_brlan: U8FileNode {
+                        offset: 0x40,
+                        size: 0x8,
+                    },
+                    out_press_brlan: U8FileNode {
+                        offset: 0x60,
+                        size: 0x8,
+                    },
+                    brlyt: U8FileNode {
+                        offset: 0x80,
+                        size: 0x8,
+                    },
+                },
+            );
+            let e_root = make_openingtitle_fnt(
+                RegionBitFlags::from(Region::E),
+                &RegionalFiles {
+                    in_press_brlan: U8FileNode {
+                        offset: 0xa0,
+                        size: 0x8,
+                    },
+                    in_title_brlan: U8FileNode {
+                        offset: 0xc0,
+                        size: 0x8,
+                    },
+                    loop_press_brlan: U8FileNode {
+                        offset: 0xe0,
+                        size: 0x8,
+                    },
+                    out_press_brlan: U8FileNode {
+                        offset: 0x100,
+                        size: 0x8,
+                    },
+                    brlyt: U8FileNode {
+                        offset: 0x80,
+                        size: 0x8,
+                    },
+                },
+            );
+            get_mut_anim_folder(&mut in_root)?.extend(get_anim_folder(&e_root)?.clone());
+            get_mut_blyt_folder(&mut in_root)?.extend(get_blyt_folder(&e_root)?.clone());
+
+            let mut in_buf = Cursor::new(Vec::new());
+            u8_fnt::write(&mut in_buf, &in_root)?;
+            in_buf.write_all(concat_bytes!(
+                b"INPBRLAN\0\0\0\0\0\0\0\0",
+                b"\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+                b"INTBRLAN\0\0\0\0\0\0\0\0",
+                b"\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+                b"LPPBRLAN\0\0\0\0\0\0\0\0",
+                b"\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+                b"OTPBRLAN\0\0\0\0\0\0\0\0",
+                b"\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+                b"THEBRLYT\0\0\0\0\0\0\0\0",
+                b"\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+                b"INPBRLAN\0\0\0\0\0\0\0\0",
+                b"\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+                b"INTBRLAN\0\0\0\0\0\0\0\0",
+                b"\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+                b"LPPBRLAN\0\0\0\0\0\0\0\0",
+                b"\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+                b"OTPBRLAN",
+            ))?;
+
+            in_buf.seek(SeekFrom::Start(0))?;
+            let mut out_buf = Cursor::new(Vec::new());
+
+            convert_openingtitle_between_regions(
+                &mut in_buf,
+                &mut out_buf,
+                None,
+                RegionBitFlags::ALL,
+                &ConvertOpeningTitleBetweenRegionsConflictStrategies::default(),
+            )?;
+
+            out_buf.seek(SeekFrom::Start(0))?;
+            let (out_root, out_data_offset) = u8_fnt::read(&mut out_buf)?;
+            let out_data = out_buf.into_inner();
+
+            // Every region's brlyt should still point to the same data
+            let blyt_folder = get_blyt_folder(&out_root)?;
+            let brlyt_nodes: Vec<_> = Region::all_filenames()
+                .map(|(_, filenames)| blyt_folder.get(filenames.brlyt))
+                .collect();
+            assert!(brlyt_nodes.iter().all(|node| node.is_some()));
+            assert!(brlyt_nodes.iter().all_equal());
+
+            // ...which should only be stored once
+            let data_table = &out_data[out_data_offset as usize..];
+            assert_eq!(
+                data_table.windows(8).filter(|w| w == b"THEBRLYT").count(),
+                1
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn test_zero_size_files() -> TestResult {
+            // W and K, with all brlans empty (but at different offsets
+            // per region), and a shared non-empty brlyt that starts
+            // where W's brlans point
+            let mut in_root = make_openingtitle_fnt(
+                RegionBitFlags::from(Region::W),
+                &RegionalFiles {
+                    in_press_brlan: U8FileNode { offset: 0, size: 0 },
+                    in_title_brlan: U8FileNode { offset: 0, size: 0 },
+                    loop_press_brlan: U8FileNode { offset: 0, size: 0 },
+                    out_press_brlan: U8FileNode { offset: 0, size: 0 },
+                    brlyt: U8FileNode { offset: 0, size: 8 },
+                },
+            );
+            let k_root = make_openingtitle_fnt(
+                RegionBitFlags::from(Region::K),
+                &RegionalFiles {
+                    in_press_brlan: U8FileNode {
+                        offset: 0x20,
+                        size: 0,
+                    },
+                    in_title_brlan: U8FileNode {
+                        offset: 0x20,
+                        size: 0,
+                    },
+                    loop_press_brlan: U8FileNode {
+                        offset: 0x20,
+                        size: 0,
+                    },
+                    out_press_brlan: U8FileNode {
+                        offset: 0x20,
+                        size: 0,
+                    },
+                    brlyt: U8FileNode { offset: 0, size: 8 },
+                },
+            );
+            get_mut_anim_folder(&mut in_root)?.extend(get_anim_folder(&k_root)?.clone());
+            get_mut_blyt_folder(&mut in_root)?.extend(get_blyt_folder(&k_root)?.clone());
+
+            let mut in_buf = Cursor::new(Vec::new());
+            u8_fnt::write(&mut in_buf, &in_root)?;
+            in_buf.write_all(b"THEBRLYT")?;
+
+            in_buf.seek(SeekFrom::Start(0))?;
+            let mut out_buf = Cursor::new(Vec::new());
+
+            // The empty brlans don't conflict with each other, even with
+            // the strictest strategies
+            convert_openingtitle_between_regions(
+                &mut in_buf,
+                &mut out_buf,
+                Some(&[Region::W, Region::K]),
+                RegionBitFlags::ALL,
+                &ConvertOpeningTitleBetweenRegionsConflictStrategies::default(),
+            )?;
+
+            out_buf.seek(SeekFrom::Start(0))?;
+            let (out_root, out_data_offset) = u8_fnt::read(&mut out_buf)?;
+            let out_data = out_buf.into_inner();
+
+            let anim_folder = get_anim_folder(&out_root)?;
+            let blyt_folder = get_blyt_folder(&out_root)?;
+            for region in RegionBitFlags::ALL {
+                let filenames = &ALL_FILENAMES[region.into()];
+                for brlan_name in [
+                    filenames.in_press_brlan,
+                    filenames.in_title_brlan,
+                    filenames.loop_press_brlan,
+                    filenames.out_press_brlan,
+                ] {
+                    assert!(matches!(
+                        anim_folder.get(brlan_name),
+                        Some(U8Node::File(U8FileNode { size: 0, .. }))
+                    ));
+                }
+
+                let Some(U8Node::File(brlyt)) = blyt_folder.get(filenames.brlyt) else {
+                    panic!("missing {}", filenames.brlyt);
+                };
+                let start = (out_data_offset + brlyt.offset) as usize;
+                assert_eq!(&out_data[start..start + brlyt.size as usize], b"THEBRLYT");
+            }
+            Ok(())
+        }
+
+        #[test]
+        fn test_minimal() -> TestResult {
+            let regional_file = U8FileNode { offset: 0, size: 8 };
+            let mut in_root = make_openingtitle_fnt(
+                Region::E | Region::W,
+                &RegionalFiles {
+                    in_press_brlan: regional_file.clone(),
+                    in_title_brlan: regional_file.clone(),
+                    loop_press_brlan: regional_file.clone(),
+                    out_press_brlan: regional_file.clone(),
+                    brlyt: regional_file.clone(),
+                },
+            );
+            get_mut_anim_folder(&mut in_root)?.insert(
+                "something else".to_owned(),
+                U8Node::File(regional_file.clone()),
+            );
+            in_root
+                .get_mut("/arc")
+                .unwrap()
+                .as_mut_folder()
+                .unwrap()
+                .insert(
+                    "timg".to_owned(),
+                    U8Node::Folder(U8FolderNode::from([
+                        (
+                            "wiiMario_Title_logo_local_00.tpl".to_owned(),
+                            U8Node::File(regional_file.clone()),
+                        ),
+                        ("notes.txt".to_owned(), U8Node::File(regional_file.clone())),
+                    ])),
+                );
+
+            let mut in_buf = Cursor::new(Vec::new());
+            u8_fnt::write(&mut in_buf, &in_root)?;
+            in_buf.write_all(b"FILEDATA")?;
+
+            in_buf.seek(SeekFrom::Start(0))?;
+            let mut out_buf = Cursor::new(Vec::new());
+
+            // Only convert from W, so the (complete) E files would
+            // normally have been left alone
+            convert_openingtitle_between_regions_with_options(
+                &mut in_buf,
+                &mut out_buf,
+                Some(&[Region::W]),
+                RegionBitFlags::from(Region::J),
+                &ConvertOpeningTitleBetweenRegionsConflictStrategies::default(),
+                &ConvertOpeningTitleBetweenRegionsOptions {
+                    minimal: true,
+                    ..Default::default()
+                },
+                &HashFileComparator,
+            )?;
+
+            out_buf.seek(SeekFrom::Start(0))?;
+            let (out_root, _) = u8_fnt::read(&mut out_buf)?;
+
+            let mut expected_out_root = make_openingtitle_fnt(
+                RegionBitFlags::from(Region::J),
+                &RegionalFiles {
+                    in_press_brlan: regional_file.clone(),
+                    in_title_brlan: regional_file.clone(),
+                    loop_press_brlan: regional_file.clone(),
+                    out_press_brlan: regional_file.clone(),
+                    brlyt: regional_file.clone(),
+                },
+            );
+            expected_out_root
+                .get_mut("/arc")
+                .unwrap()
+                .as_mut_folder()
+                .unwrap()
+                .insert(
+                    "timg".to_owned(),
+                    U8Node::Folder(U8FolderNode::from([(
+                        "wiiMario_Title_logo_local_00.tpl".to_owned(),
+                        U8Node::File(regional_file.clone()),
+                    )])),
+                );
+
+            assert_eq!(out_root, expected_out_root);
+            Ok(())
+        }
+
+        #[test]
+        fn test_returns_writer() -> TestResult {
+            let regional_file = U8FileNode { offset: 0, size: 8 };
+            let in_root = make_openingtitle_fnt(
+                RegionBitFlags::from(Region::P),
+                &RegionalFiles {
+                    in_press_brlan: regional_file.clone(),
+                    in_title_brlan: regional_file.clone(),
+                    loop_press_brlan: regional_file.clone(),
+                    out_press_brlan: regional_file.clone(),
+                    brlyt: regional_file.clone(),
+                },
+            );
+            let mut in_buf = Cursor::new(Vec::new());
+            u8_fnt::write(&mut in_buf, &in_root)?;
+            in_buf.write_all(b"FILEDATA")?;
+
+            in_buf.seek(SeekFrom::Start(0))?;
+            let (out_buf, report) = convert_openingtitle_between_regions(
+                Cursor::new(in_buf.get_ref().clone()),
+                Cursor::new(Vec::new()),
+                None,
+                RegionBitFlags::ALL,
+                &ConvertOpeningTitleBetweenRegionsConflictStrategies::default(),
+            )?;
+            let out_bytes = out_buf.into_inner();
+
+            let mut out_buf_2 = Cursor::new(Vec::new());
+            let report_2 = convert_openingtitle_between_regions_in_place(
+                &mut in_buf,
+                &mut out_buf_2,
+                None,
+                RegionBitFlags::ALL,
+                &ConvertOpeningTitleBetweenRegionsConflictStrategies::default(),
+            )?;
+
+            assert!(!out_bytes.is_empty());
+            assert_eq!(out_bytes, out_buf_2.into_inner());
+            assert_eq!(report, report_2);
+
+            let (out_bytes_3, report_3) = convert_openingtitle_between_regions_in_memory(
+                in_buf.get_ref(),
+                None,
+                RegionBitFlags::ALL,
+                &ConvertOpeningTitleBetweenRegionsConflictStrategies::default(),
+            )?;
+            assert_eq!(out_bytes, out_bytes_3);
+            assert_eq!(report, report_3);
+            Ok(())
+        }
+
+        #[test]
+        fn test_malformed_input_doesnt_panic() -> TestResult {
+            let regional_file = U8FileNode { offset: 0, size: 8 };
+            let in_root = make_openingtitle_fnt(
+                Region::P | Region::J,
+                &RegionalFiles {
+                    in_press_brlan: regional_file.clone(),
+                    in_title_brlan: regional_file.clone(),
+                    loop_press_brlan: regional_file.clone(),
+                    out_press_brlan: regional_file.clone(),
+                    brlyt: regional_file.clone(),
+                },
+            );
+            let mut in_buf = Cursor::new(Vec::new());
+            u8_fnt::write(&mut in_buf, &in_root)?;
+            in_buf.write_all(b"FILEDATA")?;
+            let original = in_buf.into_inner();
+
+            // Corrupt a few bytes at a time (with a fixed-seed xorshift,
+            // so that failures are reproducible), and also try every
+            // truncation. Errors are fine; panics aren't.
+            let mut state: u32 = 0x2447;
+            let mut next = || {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state
+            };
+            let mut inputs: Vec<Vec<u8>> = (0..original.len())
+                .map(|len| original[..len].to_vec())
+                .collect();
+            for _ in 0..2000 {
+                let mut input = original.clone();
+                for _ in 0..=next() % 4 {
+                    let idx = next() as usize % input.len();
+                    input[idx] = next() as u8;
+                }
+                inputs.push(input);
+            }
+
+            for input in &inputs {
+                let _ = convert_openingtitle_between_regions_in_memory(
+                    input,
+                    None,
+                    RegionBitFlags::ALL,
+                    &ConvertOpeningTitleBetweenRegionsConflictStrategies::default(),
+                );
+            }
+            Ok(())
+        }
+
+        #[test]
+        fn test_max_size() -> TestResult {
+            let regional_file = U8FileNode { offset: 0, size: 8 };
+            let in_root = make_openingtitle_fnt(
+                RegionBitFlags::from(Region::P),
+                &RegionalFiles {
+                    in_press_brlan: regional_file.clone(),
+                    in_title_brlan: regional_file.clone(),
+                    loop_press_brlan: regional_file.clone(),
+                    out_press_brlan: regional_file.clone(),
+                    brlyt: regional_file.clone(),
+                },
+            );
+            let mut in_buf = Cursor::new(Vec::new());
+            u8_fnt::write(&mut in_buf, &in_root)?;
+            in_buf.write_all(b"FILEDATA")?;
+
+            // Figure out how large the output actually is
+            in_buf.seek(SeekFrom::Start(0))?;
+            let mut out_buf = Cursor::new(Vec::new());
+            convert_openingtitle_between_regions(
+                &mut in_buf,
+                &mut out_buf,
+                None,
+                RegionBitFlags::ALL,
+                &ConvertOpeningTitleBetweenRegionsConflictStrategies::default(),
+            )?;
+            let output_size = out_buf.into_inner().len() as u64;
+
+            for (max_size, should_succeed) in [(output_size, true), (output_size - 1, false)] {
+                in_buf.seek(SeekFrom::Start(0))?;
+                let result = convert_openingtitle_between_regions_with_options(
+                    &mut in_buf,
+                    Cursor::new(Vec::new()),
+                    None,
+                    RegionBitFlags::ALL,
+                    &ConvertOpeningTitleBetweenRegionsConflictStrategies::default(),
+                    &ConvertOpeningTitleBetweenRegionsOptions {
+                        max_size: Some(max_size),
+                        ..Default::default()
+                    },
+                    &HashFileComparator,
+                );
+
+                if should_succeed {
+                    result?;
+                } else {
+                    assert!(matches!(
+                        result,
+                        Err(ConvertOpeningTitleBetweenRegionsError::OutputTooLarge(size, max))
+                            if size == output_size && max == max_size
+                    ));
+                }
+            }
+            Ok(())
+        }
+
+        #[test]
+        fn test_china_logo_fallback() -> TestResult {
+            let regional_file = U8FileNode { offset: 0, size: 4 };
+            let in_root = make_openingtitle_fnt(
+                Region::P | Region::E,
+                &RegionalFiles {
+                    in_press_brlan: regional_file.clone(),
+                    in_title_brlan: regional_file.clone(),
+                    loop_press_brlan: regional_file.clone(),
+                    out_press_brlan: regional_file.clone(),
+                    brlyt: regional_file,
+                },
+            );
+            let logo = |offset| U8Node::File(U8FileNode { offset, size: 4 });
+
+            for (to_regions, timg, expected_cn_logo_data) in [
+                // Only the shared logo: China gets a copy of it
+                (
+                    RegionBitFlags::ALL,
+                    U8FolderNode::from([(SHARED_LOGO_TPL_FILENAME.to_owned(), logo(0x20))]),
+                    Some(&b"LOGO"[..]),
+                ),
+                // China isn't a target
+                (
+                    Region::P | Region::E | Region::J,
+                    U8FolderNode::from([(SHARED_LOGO_TPL_FILENAME.to_owned(), logo(0x20))]),
+                    None,
+                ),
+                // China already has its own logo
+                (
+                    RegionBitFlags::ALL,
+                    U8FolderNode::from([
+                        (SHARED_LOGO_TPL_FILENAME.to_owned(), logo(0x20)),
+                        (CHINA_LOGO_TPL_FILENAME.to_owned(), logo(0x40)),
+                    ]),
+                    Some(&b"CNLG"[..]),
+                ),
+            ] {
+                let mut in_root = in_root.clone();
+                in_root
+                    .get_mut(ARC_FOLDER_PATH)
+                    .unwrap()
+                    .as_mut_folder()
+                    .unwrap()
+                    .insert("timg".to_owned(), U8Node::Folder(timg));
+                let mut in_buf = Cursor::new(Vec::new());
+                u8_fnt::write(&mut in_buf, &in_root)?;
+                in_buf
+                    .write_all(b"FILE\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0")?;
+                in_buf
+                    .write_all(b"LOGO\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0")?;
+                in_buf.write_all(b"CNLG")?;
+
+                in_buf.seek(SeekFrom::Start(0))?;
+                let mut out_buf = Cursor::new(Vec::new());
+                convert_openingtitle_between_regions(
+                    &mut in_buf,
+                    &mut out_buf,
+                    None,
+                    to_regions,
+                    &ConvertOpeningTitleBetweenRegionsConflictStrategies::default(),
+                )?;
+
+                let (out_root, out_data_offset) = u8_fnt::read(&mut out_buf)?;
+                let cn_logo_path = format!("{TIMG_FOLDER_PATH}/{CHINA_LOGO_TPL_FILENAME}");
+                let cn_logo_data = out_root
+                    .get(&cn_logo_path)
+                    .and_then(U8Node::as_file)
+                    .map(|node| {
+                        util::read_file_slice(
+                            &mut out_buf,
+                            u64::from(out_data_offset) + u64::from(node.offset),
+                            node.size.try_into().unwrap(),
+                        )
+                    })
+                    .transpose()?;
+                assert_eq!(cn_logo_data.as_deref(), expected_cn_logo_data);
+            }
+            Ok(())
+        }
+
+        #[test]
+        fn test_keep_logo() -> TestResult {
+            let regional_file = U8FileNode { offset: 0, size: 4 };
+            let mut in_root = make_openingtitle_fnt(
+                Region::P | Region::E,
+                &RegionalFiles {
+                    in_press_brlan: regional_file.clone(),
+                    in_title_brlan: regional_file.clone(),
+                    loop_press_brlan: regional_file.clone(),
+                    out_press_brlan: regional_file.clone(),
+                    brlyt: regional_file.clone(),
+                },
+            );
+            let timg = U8FolderNode::from([
+                (
+                    SHARED_LOGO_TPL_FILENAME.to_owned(),
+                    U8Node::File(regional_file.clone()),
+                ),
+                ("notes.txt".to_owned(), U8Node::File(regional_file)),
+            ]);
+            in_root
+                .get_mut(ARC_FOLDER_PATH)
+                .unwrap()
+                .as_mut_folder()
+                .unwrap()
+                .insert("timg".to_owned(), U8Node::Folder(timg.clone()));
+            let mut in_buf = Cursor::new(Vec::new());
+            u8_fnt::write(&mut in_buf, &in_root)?;
+            in_buf.write_all(b"FILE")?;
+
+            // Neither the China logo fallback nor `minimal` touch it
+            for minimal in [false, true] {
+                in_buf.seek(SeekFrom::Start(0))?;
+                let mut out_buf = Cursor::new(Vec::new());
+                convert_openingtitle_between_regions_with_options(
+                    &mut in_buf,
+                    &mut out_buf,
+                    None,
+                    RegionBitFlags::ALL,
+                    &ConvertOpeningTitleBetweenRegionsConflictStrategies::default(),
+                    &ConvertOpeningTitleBetweenRegionsOptions {
+                        keep_logo: true,
+                        minimal,
+                        ..Default::default()
+                    },
+                    &HashFileComparator,
+                )?;
+
+                out_buf.seek(SeekFrom::Start(0))?;
+                let (out_root, _) = u8_fnt::read(&mut out_buf)?;
+                assert_eq!(
+                    out_root.get(TIMG_FOLDER_PATH),
+                    Some(&U8Node::Folder(timg.clone()))
+                );
+            }
+            Ok(())
+        }
+
+        #[test]
+        fn test_name_with_slash() -> TestResult {
+            let regional_file = U8FileNode { offset: 0, size: 4 };
+            let mut in_root = make_openingtitle_fnt(
+                RegionBitFlags::from(Region::E),
+                &RegionalFiles {
+                    in_press_brlan: regional_file.clone(),
+                    in_title_brlan: regional_file.clone(),
+                    loop_press_brlan: regional_file.clone(),
+                    out_press_brlan: regional_file.clone(),
+                    brlyt: regional_file,
+                },
+            );
+            in_root
+                .get_mut("/arc")
+                .unwrap()
+                .as_mut_folder()
+                .unwrap()
+                .insert(
+                    "odd/name".to_owned(),
+                    U8Node::File(U8FileNode {
+                        offset: 0x20,
+                        size: 4,
+                    }),
+                );
+            let mut in_buf = Cursor::new(Vec::new());
+            u8_fnt::write(&mut in_buf, &in_root)?;
+            in_buf.write_all(&[1; 0x20])?;
+            in_buf.write_all(&[2; 4])?;
+
+            in_buf.seek(SeekFrom::Start(0))?;
+            let mut out_buf = Cursor::new(Vec::new());
+            convert_openingtitle_between_regions(
+                &mut in_buf,
+                &mut out_buf,
+                None,
+                RegionBitFlags::ALL,
+                &ConvertOpeningTitleBetweenRegionsConflictStrategies::default(),
+            )?;
+
+            let (out_root, out_data_offset) = u8_fnt::read(&mut out_buf)?;
+            let node = out_root
+                .get("/arc")
+                .and_then(|arc| arc.child("odd/name"))
+                .and_then(U8Node::as_file)
+                .ok_or("file with a slash in its name is missing")?;
+            let data = util::read_file_slice(
+                &mut out_buf,
+                u64::from(out_data_offset) + u64::from(node.offset),
+                node.size.try_into()?,
+            )?;
+            assert_eq!(data, [2; 4]);
+            Ok(())
+        }
+
+        #[test]
+        fn test_too_large() -> TestResult {
+            /// A reader for a FNT followed by `len` bytes in total of
+            /// zeros, without actually allocating them
+            struct ZeroPaddedReader {
+                fnt: Cursor<Vec<u8>>,
+                len: u64,
+            }
+
+            impl Read for ZeroPaddedReader {
+                fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                    let pos = self.fnt.position();
+                    if pos < self.fnt.get_ref().len() as u64 {
+                        return self.fnt.read(buf);
+                    }
+                    let amount = buf.len().min(self.len.saturating_sub(pos) as usize);
+                    buf[..amount].fill(0);
+                    self.fnt.set_position(pos + amount as u64);
+                    Ok(amount)
+                }
+            }
+
+            impl Seek for ZeroPaddedReader {
+                fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+                    let pos = match pos {
+                        SeekFrom::End(offset) => {
+                            SeekFrom::Start(self.len.saturating_add_signed(offset))
+                        }
+                        pos => pos,
+                    };
+                    self.fnt.seek(pos)
+                }
+            }
+
+            // The files overlap, so the input itself is under 1 GiB.
+            // But laid out one after another, they'd need more than 4
+            // GiB.
+            let file = |offset| U8FileNode {
+                offset,
+                size: 0x3400_0000,
+            };
+            let in_root = make_openingtitle_fnt(
+                RegionBitFlags::from(Region::P),
+                &RegionalFiles {
+                    in_press_brlan: file(0),
+                    in_title_brlan: file(0x20),
+                    loop_press_brlan: file(0x40),
+                    out_press_brlan: file(0x60),
+                    brlyt: file(0x80),
+                },
+            );
+            let mut fnt = Cursor::new(Vec::new());
+            let data_table_offs = u8_fnt::write(&mut fnt, &in_root)?;
+            fnt.set_position(0);
+            let mut in_file = ZeroPaddedReader {
+                fnt,
+                len: u64::from(data_table_offs) + 0x80 + 0x3400_0000,
+            };
+
+            let mut out_buf = Cursor::new(Vec::new());
+            let result = convert_openingtitle_between_regions(
+                &mut in_file,
+                &mut out_buf,
+                None,
+                RegionBitFlags::from(Region::P),
+                &ConvertOpeningTitleBetweenRegionsConflictStrategies::default(),
+            );
+
+            assert!(matches!(
+                result,
+                Err(ConvertOpeningTitleBetweenRegionsError::TooLarge(size)) if size > u32::MAX.into()
+            ));
+            assert!(out_buf.into_inner().is_empty());
+            Ok(())
+        }
+
+        #[test]
+        fn test_not_an_openingtitle() -> TestResult {
+            let file = |offset| U8Node::File(U8FileNode { offset, size: 4 });
+
+            // Some other arc with a similar structure, and one without
+            // even an anim folder
+            for arc_contents in [
+                U8FolderNode::from([
+                    (
+                        "anim".to_owned(),
+                        U8Node::Folder(U8FolderNode::from([(
+                            "otherThing_00_in.brlan".to_owned(),
+                            file(0),
+                        )])),
+                    ),
+                    (
+                        "blyt".to_owned(),
+                        U8Node::Folder(U8FolderNode::from([(
+                            "otherThing_00.brlyt".to_owned(),
+                            file(4),
+                        )])),
+                    ),
+                ]),
+                U8FolderNode::from([("stage.bin".to_owned(), file(0))]),
+            ] {
+                let in_root = U8Node::Folder(U8FolderNode::from([(
+                    "arc".to_owned(),
+                    U8Node::Folder(arc_contents),
+                )]));
+                let mut in_buf = Cursor::new(Vec::new());
+                u8_fnt::write(&mut in_buf, &in_root)?;
+                in_buf.write_all(b"DATADATA")?;
+                in_buf.seek(SeekFrom::Start(0))?;
+
+                assert!(matches!(
+                    convert_openingtitle_between_regions(
+                        &mut in_buf,
+                        Cursor::new(Vec::new()),
+                        None,
+                        RegionBitFlags::ALL,
+                        &ConvertOpeningTitleBetweenRegionsConflictStrategies::default(),
+                    ),
+                    Err(ConvertOpeningTitleBetweenRegionsError::NotAnOpeningTitle)
+                ));
+            }
+            Ok(())
+        }
+
+        #[test]
+        fn test_duplicate_source_region() -> TestResult {
+            // (the check happens before the input is even read)
+            assert!(matches!(
+                convert_openingtitle_between_regions(
+                    Cursor::new(Vec::new()),
+                    Cursor::new(Vec::new()),
+                    Some(&[Region::E, Region::E]),
+                    RegionBitFlags::ALL,
+                    &ConvertOpeningTitleBetweenRegionsConflictStrategies::default(),
+                ),
+                Err(ConvertOpeningTitleBetweenRegionsError::DuplicateSourceRegion(Region::E))
+            ));
+            Ok(())
+        }
+
+        #[test]
+        fn test_normalize_names() -> TestResult {
+            let empty_file = U8FileNode { offset: 0, size: 0 };
+            let mut in_root = make_openingtitle_fnt(
+                RegionBitFlags::from(Region::W),
+                &RegionalFiles {
+                    in_press_brlan: empty_file.clone(),
+                    in_title_brlan: empty_file.clone(),
+                    loop_press_brlan: empty_file.clone(),
+                    out_press_brlan: empty_file.clone(),
+                    brlyt: empty_file.clone(),
+                },
+            );
+            let blyt_folder = get_mut_blyt_folder(&mut in_root)?;
+            let brlyt = blyt_folder.remove("openingTitle_TW_00.brlyt").unwrap();
+            blyt_folder.insert("openingTitle_TW_01.brlyt".to_owned(), brlyt);
+
+            let mut in_buf = Cursor::new(Vec::new());
+            u8_fnt::write(&mut in_buf, &in_root)?;
+
+            for normalize_names in [false, true] {
+                in_buf.seek(SeekFrom::Start(0))?;
+                let mut out_buf = Cursor::new(Vec::new());
+                let result = convert_openingtitle_between_regions_with_options(
+                    &mut in_buf,
+                    &mut out_buf,
+                    Some(&[Region::W]),
+                    RegionBitFlags::from(Region::J),
+                    &ConvertOpeningTitleBetweenRegionsConflictStrategies::default(),
+                    &ConvertOpeningTitleBetweenRegionsOptions {
+                        normalize_names,
+                        ..Default::default()
+                    },
+                    &HashFileComparator,
+                );
+
+                if normalize_names {
+                    result?;
+                    out_buf.seek(SeekFrom::Start(0))?;
+                    assert!(find_missing_regional_filenames(
+                        out_buf,
+                        RegionBitFlags::from(Region::J)
+                    )?
+                    .is_empty());
+                } else {
+                    // W's brlyt is only found under its nonstandard name
+                    assert!(matches!(
+                        result,
+                        Err(
+                            ConvertOpeningTitleBetweenRegionsError::RegionCoverageMismatch(
+                                RegionCoverageMismatch::AnimWithoutBlyt(Region::W)
+                            )
+                        )
+                    ));
+                }
+            }
+            Ok(())
+        }
+
+        #[test]
+        fn test_exclude() -> TestResult {
+            let empty_file = U8FileNode { offset: 0, size: 0 };
+            let mut in_root = make_openingtitle_fnt(
+                RegionBitFlags::from(Region::W),
+                &RegionalFiles {
+                    in_press_brlan: empty_file.clone(),
+                    in_title_brlan: empty_file.clone(),
+                    loop_press_brlan: empty_file.clone(),
+                    out_press_brlan: empty_file.clone(),
+                    brlyt: empty_file.clone(),
+                },
+            );
+            let anim_folder = get_mut_anim_folder(&mut in_root)?;
+            anim_folder.insert("junk.bak".to_owned(), U8Node::File(empty_file.clone()));
+            anim_folder.insert("keep.bak2".to_owned(), U8Node::File(empty_file.clone()));
+            get_mut_blyt_folder(&mut in_root)?
+                .insert("~temp".to_owned(), U8Node::File(empty_file.clone()));
+
+            let mut in_buf = Cursor::new(Vec::new());
+            u8_fnt::write(&mut in_buf, &in_root)?;
+            in_buf.seek(SeekFrom::Start(0))?;
+            let mut out_buf = Cursor::new(Vec::new());
+
+            convert_openingtitle_between_regions_with_options(
+                &mut in_buf,
+                &mut out_buf,
+                Some(&[Region::W]),
+                RegionBitFlags::from(Region::W),
+                &ConvertOpeningTitleBetweenRegionsConflictStrategies::default(),
+                &ConvertOpeningTitleBetweenRegionsOptions {
+                    exclude: &["*.bak", "/arc/blyt/~*"],
+                    ..Default::default()
+                },
+                &HashFileComparator,
+            )?;
+
+            out_buf.seek(SeekFrom::Start(0))?;
+            let (out_root, _) = u8_fnt::read(&mut out_buf)?;
+            assert!(out_root.get("/arc/anim/junk.bak").is_none());
+            assert!(out_root.get("/arc/blyt/~temp").is_none());
+            assert!(out_root.get("/arc/anim/keep.bak2").is_some());
+            assert_eq!(
+                complete_regions(&out_root)?,
+                RegionBitFlags::from(Region::W)
+            );
+
+            // Excluding a regional file makes it count as missing
+            in_buf.seek(SeekFrom::Start(0))?;
+            let result = convert_openingtitle_between_regions_with_options(
+                &mut in_buf,
+                Cursor::new(Vec::new()),
+                Some(&[Region::W]),
+                RegionBitFlags::from(Region::W),
+                &ConvertOpeningTitleBetweenRegionsConflictStrategies::default(),
+                &ConvertOpeningTitleBetweenRegionsOptions {
+                    exclude: &["*.brlyt"],
+                    ..Default::default()
+                },
+                &HashFileComparator,
+            );
+            assert!(matches!(
+                result,
+                Err(
+                    ConvertOpeningTitleBetweenRegionsError::RegionCoverageMismatch(
+                        RegionCoverageMismatch::AnimWithoutBlyt(Region::W)
+                    )
+                )
+            ));
+            Ok(())
+        }
+
+        #[test]
+        fn test_deep_search() -> TestResult {
+            let file = |offset| U8Node::File(U8FileNode { offset, size: 0x10 });
+            let p_filenames = Region::P.filenames();
+
+            // P's brlans are in their own subfolder, and its brlyt is
+            // where it should be
+            let mut in_root = make_openingtitle_fnt(
+                RegionBitFlags::EMPTY,
+                &RegionalFiles {
+                    in_press_brlan: U8FileNode { offset: 0, size: 0 },
+                    in_title_brlan: U8FileNode { offset: 0, size: 0 },
+                    loop_press_brlan: U8FileNode { offset: 0, size: 0 },
+                    out_press_brlan: U8FileNode { offset: 0, size: 0 },
+                    brlyt: U8FileNode { offset: 0, size: 0 },
+                },
+            );
+            get_mut_anim_folder(&mut in_root)?.insert(
+                "EU".to_owned(),
+                U8Node::Folder(U8FolderNode::from([
+                    (p_filenames.in_press_brlan.to_owned(), file(0x00)),
+                    (p_filenames.in_title_brlan.to_owned(), file(0x20)),
+                    (p_filenames.loop_press_brlan.to_owned(), file(0x40)),
+                    (p_filenames.out_press_brlan.to_owned(), file(0x60)),
+                ])),
+            );
+            get_mut_blyt_folder(&mut in_root)?.insert(p_filenames.brlyt.to_owned(), file(0x80));
+
+            let mut in_buf = Cursor::new(Vec::new());
+            u8_fnt::write(&mut in_buf, &in_root)?;
+            in_buf.write_all(&[0; 0x90])?;
+
+            // Without deep search, there's nothing to convert
+            in_buf.seek(SeekFrom::Start(0))?;
+            let result = convert_openingtitle_between_regions_in_place(
+                &mut in_buf,
+                &mut Cursor::new(Vec::new()),
+                Some(&[Region::P]),
+                Region::P | Region::E,
+                &ConvertOpeningTitleBetweenRegionsConflictStrategies::default(),
+            );
+            assert!(matches!(
+                result,
+                Err(
+                    ConvertOpeningTitleBetweenRegionsError::RegionCoverageMismatch(
+                        RegionCoverageMismatch::BlytWithoutAnim(Region::P)
+                    )
+                )
+            ));
+
+            in_buf.seek(SeekFrom::Start(0))?;
+            let mut out_buf = Cursor::new(Vec::new());
+            let report = convert_openingtitle_between_regions_with_options(
+                &mut in_buf,
+                &mut out_buf,
+                Some(&[Region::P]),
+                Region::P | Region::E,
+                &ConvertOpeningTitleBetweenRegionsConflictStrategies::default(),
+                &ConvertOpeningTitleBetweenRegionsOptions {
+                    deep_search: true,
+                    ..Default::default()
+                },
+                &HashFileComparator,
+            )?;
+            assert_eq!(
+                report.found_elsewhere,
+                [
+                    "/arc/anim/EU/openingTitle_EU_00_inPress.brlan",
+                    "/arc/anim/EU/openingTitle_EU_00_inTitle.brlan",
+                    "/arc/anim/EU/openingTitle_EU_00_loopPress.brlan",
+                    "/arc/anim/EU/openingTitle_EU_00_outPress.brlan",
+                ]
+            );
+
+            // P's files stay where they were, and E's go in the usual
+            // places
+            out_buf.seek(SeekFrom::Start(0))?;
+            let (out_root, _) = u8_fnt::read(&mut out_buf)?;
+            for path in &report.found_elsewhere {
+                assert!(out_root.get(path).is_some(), "{path} not found");
+            }
+            assert!(out_root
+                .get(&format!("/arc/anim/{}", p_filenames.in_press_brlan))
+                .is_none());
+            assert!(out_root
+                .get(&format!("/arc/blyt/{}", p_filenames.brlyt))
+                .is_some());
+            assert_eq!(missing_regional_filenames(&out_root, Region::E.into())?, []);
+            Ok(())
+        }
+
+        #[test]
+        fn test_pin_data_offset() -> TestResult {
+            let file = |offset| U8FileNode { offset, size: 0x10 };
+            let mut in_root = make_openingtitle_fnt(
+                RegionBitFlags::from(Region::W),
+                &RegionalFiles {
+                    in_press_brlan: file(0x00),
+                    in_title_brlan: file(0x20),
+                    loop_press_brlan: file(0x40),
+                    out_press_brlan: file(0x60),
+                    brlyt: file(0x80),
+                },
+            );
+            get_mut_anim_folder(&mut in_root)?.insert(
+                "a file with a rather long name.bak".to_owned(),
+                U8Node::File(file(0xa0)),
+            );
+
+            let mut in_buf = Cursor::new(Vec::new());
+            let data_table_offs = u8_fnt::write(&mut in_buf, &in_root)?;
+            in_buf.write_all(&(0..0xb0).map(|i| i as u8).collect::<Vec<_>>())?;
+            let in_data = in_buf.get_ref().clone();
+
+            // Removing the junk file shrinks the FNT, but the data
+            // stays exactly where it was (including the junk file's)
+            in_buf.seek(SeekFrom::Start(0))?;
+            let mut out_buf = Cursor::new(Vec::new());
+            let report = convert_openingtitle_between_regions_with_options(
+                &mut in_buf,
+                &mut out_buf,
+                Some(&[Region::W]),
+                RegionBitFlags::from(Region::W),
+                &ConvertOpeningTitleBetweenRegionsConflictStrategies::default(),
+                &ConvertOpeningTitleBetweenRegionsOptions {
+                    exclude: &["*.bak"],
+                    pin_data_offset: true,
+                    ..Default::default()
+                },
+                &HashFileComparator,
+            )?;
+            let out_data = out_buf.into_inner();
+            assert_eq!(report.fnt_size, u64::from(data_table_offs));
+            assert_eq!(report.fat_padding, 0x10 + 5 * 0x10);
+            assert_eq!(out_data.len(), in_data.len());
+            assert_eq!(
+                out_data[data_table_offs as usize..],
+                in_data[data_table_offs as usize..]
+            );
+            let (out_root, out_data_table_offs) = u8_fnt::read(&mut Cursor::new(&out_data))?;
+            assert_eq!(out_data_table_offs, data_table_offs);
+            assert!(out_root
+                .get("/arc/anim/a file with a rather long name.bak")
+                .is_none());
+
+            // Adding every region makes the FNT too large, so the FAT is
+            // laid out normally
+            in_buf.seek(SeekFrom::Start(0))?;
+            let mut out_buf = Cursor::new(Vec::new());
+            let report = convert_openingtitle_between_regions_with_options(
+                &mut in_buf,
+                &mut out_buf,
+                Some(&[Region::W]),
+                RegionBitFlags::ALL,
+                &ConvertOpeningTitleBetweenRegionsConflictStrategies::default(),
+                &ConvertOpeningTitleBetweenRegionsOptions {
+                    pin_data_offset: true,
+                    ..Default::default()
+                },
+                &HashFileComparator,
+            )?;
+            assert!(report.fnt_size > data_table_offs.into());
+            out_buf.seek(SeekFrom::Start(0))?;
+            let (_, out_data_table_offs) = u8_fnt::read(&mut out_buf)?;
+            assert_eq!(u64::from(out_data_table_offs), report.fnt_size);
+            Ok(())
+        }
+
+        #[test]
+        fn test_inspect() -> TestResult {
+            let empty_file = U8FileNode { offset: 0, size: 0 };
+            let in_root = make_openingtitle_fnt(
+                RegionBitFlags::from(Region::W),
+                &RegionalFiles {
+                    in_press_brlan: empty_file.clone(),
+                    in_title_brlan: empty_file.clone(),
+                    loop_press_brlan: empty_file.clone(),
+                    out_press_brlan: empty_file.clone(),
+                    brlyt: empty_file.clone(),
+                },
+            );
+            let mut in_buf = Cursor::new(Vec::new());
+            u8_fnt::write(&mut in_buf, &in_root)?;
+            in_buf.seek(SeekFrom::Start(0))?;
+
+            let snapshots = std::cell::RefCell::new(Vec::new());
+            convert_openingtitle_between_regions_with_options(
+                &mut in_buf,
+                Cursor::new(Vec::new()),
+                None,
+                RegionBitFlags::from(Region::J),
+                &ConvertOpeningTitleBetweenRegionsConflictStrategies::default(),
+                &ConvertOpeningTitleBetweenRegionsOptions {
+                    inspect: Some(&|stage, fnt: &U8Node| {
+                        snapshots.borrow_mut().push((stage, fnt.clone()));
+                    }),
+                    ..Default::default()
+                },
+                &HashFileComparator,
+            )?;
+
+            let snapshots = snapshots.into_inner();
+            assert_eq!(snapshots.len(), 3);
+            assert_eq!(snapshots[0], (ConversionStage::Parsed, in_root));
+            assert_eq!(snapshots[1].0, ConversionStage::FilenamesAdded);
+            assert!(snapshots[1]
+                .1
+                .get("/arc/blyt/openingTitle_13.brlyt")
+                .is_some());
+            assert!(snapshots[1]
+                .1
+                .get("/arc/blyt/openingTitle_TW_00.brlyt")
+                .is_none());
+            assert_eq!(snapshots[2].0, ConversionStage::LaidOut);
+            Ok(())
+        }
+
+        #[test]
+        fn test_dry_run() -> TestResult {
+            let in_root = make_openingtitle_fnt(
+                RegionBitFlags::from(Region::W),
+                &RegionalFiles {
+                    in_press_brlan: U8FileNode {
+                        offset: 0x00,
+                        size: 0x8,
+                    },
+                    in_title_brlan: U8FileNode {
+                        offset: 0x20,
+                        size: 0x8,
+                    },
+                    loop_press_brlan: U8FileNode {
+                        offset: 0x40,
+                        size: 0x8,
+                    },
+                    out_press_brlan: U8FileNode {
+                        offset: 0x40,
+                        size: 0x8,
+                    },
+                    brlyt: U8FileNode {
+                        offset: 0x80,
+                        size: 0x8,
+                    },
+                },
+            );
+            let mut in_buf = Cursor::new(Vec::new());
+            u8_fnt::write(&mut in_buf, &in_root)?;
+            in_buf.write_all(&[0; 0x88])?;
+            in_buf.seek(SeekFrom::Start(0))?;
+
+            let laid_out = std::cell::RefCell::new(None);
+            let mut out_buf = Cursor::new(Vec::new());
+            convert_openingtitle_between_regions_with_options(
+                &mut in_buf,
+                &mut out_buf,
+                None,
+                RegionBitFlags::from(Region::J),
+                &ConvertOpeningTitleBetweenRegionsConflictStrategies::default(),
+                &ConvertOpeningTitleBetweenRegionsOptions {
+                    inspect: Some(&|stage, fnt: &U8Node| {
+                        if stage == ConversionStage::LaidOut {
+                            *laid_out.borrow_mut() = Some(fnt.clone());
+                        }
+                    }),
+                    dry_run: true,
+                    ..Default::default()
+                },
+                &HashFileComparator,
+            )?;
+
+            assert!(out_buf.into_inner().is_empty());
+
+            // The shared file is only laid out once, and the gap before
+            // the brlyt is closed up
+            let laid_out = laid_out
+                .into_inner()
+                .ok_or("LaidOut stage wasn't inspected")?;
+            let brlyt = laid_out
+                .get("/arc/blyt/openingTitle_13.brlyt")
+                .and_then(U8Node::as_file)
+                .ok_or("brlyt is missing")?;
+            assert_eq!(brlyt.offset, 0x60);
+            Ok(())
+        }
+
+        #[test]
+        fn test_min_regions() -> TestResult {
+            let empty_file = U8FileNode { offset: 0, size: 0 };
+            let in_root = make_openingtitle_fnt(
+                Region::W | Region::C,
+                &RegionalFiles {
+                    in_press_brlan: empty_file.clone(),
+                    in_title_brlan: empty_file.clone(),
+                    loop_press_brlan: empty_file.clone(),
+                    out_press_brlan: empty_file.clone(),
+                    brlyt: empty_file.clone(),
+                },
+            );
+            let mut in_buf = Cursor::new(Vec::new());
+            u8_fnt::write(&mut in_buf, &in_root)?;
+
+            // W is converted to J, and C is left alone, so the output
+            // supports two regions
+            for (min_regions, should_succeed) in [(0, true), (2, true), (3, false)] {
+                in_buf.seek(SeekFrom::Start(0))?;
+                let result = convert_openingtitle_between_regions_with_options(
+                    &mut in_buf,
+                    Cursor::new(Vec::new()),
+                    Some(&[Region::W]),
+                    RegionBitFlags::from(Region::J),
+                    &ConvertOpeningTitleBetweenRegionsConflictStrategies::default(),
+                    &ConvertOpeningTitleBetweenRegionsOptions {
+                        min_regions,
+                        ..Default::default()
+                    },
+                    &HashFileComparator,
+                );
+
+                if should_succeed {
+                    result?;
+                } else {
+                    assert!(matches!(
+                        result,
+                        Err(ConvertOpeningTitleBetweenRegionsError::TooFewRegions(2, 3))
+                    ));
+                }
+            }
+            Ok(())
+        }
+
+        #[test]
+        fn test_io_error_context() -> TestResult {
+            /// A writer that can't be written to
+            struct ReadOnlyWriter;
+
+            impl Write for ReadOnlyWriter {
+                fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+                    Err(io::Error::from(io::ErrorKind::PermissionDenied))
+                }
+
+                fn flush(&mut self) -> io::Result<()> {
+                    Ok(())
+                }
+            }
+
+            impl Seek for ReadOnlyWriter {
+                fn seek(&mut self, _pos: SeekFrom) -> io::Result<u64> {
+                    Ok(0)
+                }
+            }
+
+            let empty_file = U8FileNode { offset: 0, size: 0 };
+            let in_root = make_openingtitle_fnt(
+                RegionBitFlags::from(Region::P),
+                &RegionalFiles {
+                    in_press_brlan: empty_file.clone(),
+                    in_title_brlan: empty_file.clone(),
+                    loop_press_brlan: empty_file.clone(),
+                    out_press_brlan: empty_file.clone(),
+                    brlyt: empty_file.clone(),
+                },
+            );
+            let mut in_buf = Cursor::new(Vec::new());
+            u8_fnt::write(&mut in_buf, &in_root)?;
+            in_buf.seek(SeekFrom::Start(0))?;
+
+            let error = convert_openingtitle_between_regions_in_place(
+                &mut in_buf,
+                &mut ReadOnlyWriter,
+                None,
+                RegionBitFlags::ALL,
+                &ConvertOpeningTitleBetweenRegionsConflictStrategies::default(),
+            )
+            .unwrap_err();
+
+            assert_eq!(error.to_string(), "I/O error while writing new FNT");
+            let source = std::error::Error::source(&error)
+                .and_then(|source| source.downcast_ref::<io::Error>())
+                .map(io::Error::kind);
+            assert_eq!(source, Some(io::ErrorKind::PermissionDenied));
+            Ok(())
+        }
+
+        #[test]
+        fn test_invalid_u8_file_error_chain() -> TestResult {
+            // Root folder containing one node with type 3
+            let mut in_buf = Cursor::new(
+                concat_bytes!(
+                    b"U\xaa8-\0\0\0 \0\0\0\x19\0\0\0@\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+                    b"\x01\0\0\0\0\0\0\0\0\0\0\x02\x03\0\0\x01\0\0\0\0\0\0\0\0\0a\0\0\0\0\0\0",
+                )
+                .to_vec(),
+            );
+            let mut out_buf = Cursor::new(Vec::new());
+
+            let error = convert_openingtitle_between_regions(
+                &mut in_buf,
+                &mut out_buf,
+                None,
+                RegionBitFlags::ALL,
+                &ConvertOpeningTitleBetweenRegionsConflictStrategies::default(),
+            )
+            .unwrap_err();
+
+            // Render the whole error chain, the way anyhow would
+            let mut rendered = error.to_string();
+            let mut source = std::error::Error::source(&error);
+            while let Some(inner) = source {
+                rendered.push_str(&format!(": {inner}"));
+                source = inner.source();
+            }
+
+            assert_eq!(rendered, "invalid U8 file: unexpected node type 3 at 0x2c");
+            Ok(())
+        }
     }
 }
@@ -0,0 +1,391 @@
+//! Support for merging two single-region openingTitle.arc files (e.g.
+//! one from a US copy of the game and one from a Japanese copy) into a
+//! single region-free arc.
+
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use log::{debug, trace};
+
+use crate::u8_fnt::{self, U8FileNode, U8Node};
+use crate::util;
+use crate::{
+    add_china_logo_fallback, add_new_filenames, lay_out_new_fat, remove_regional_files,
+    select_regional_files, ConflictStrategy, ConvertOpeningTitleBetweenRegionsError,
+    FileComparator, IoContext, Region, RegionBitFlags, FAT_COPY_BUFFER_SIZE,
+};
+
+/// Which of the two input arcs a piece of file data comes from.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+enum Source {
+    A,
+    B,
+}
+
+/// A piece of file data in one of the input arcs. The offset is
+/// relative to the start of that arc's FAT.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+struct SourceData {
+    source: Source,
+    offset: u32,
+    size: u32,
+}
+
+/// Replace the offset of every file in a FNT with an index into
+/// `data`, adding a new entry for each piece of data not seen before.
+/// This lets trees from both arcs be mixed freely.
+fn index_file_data(node: &mut U8Node, source: Source, data: &mut Vec<SourceData>) {
+    match node {
+        U8Node::File(U8FileNode { offset, size }) => {
+            let entry = SourceData {
+                source,
+                offset: *offset,
+                size: *size,
+            };
+            let index = data.iter().position(|x| *x == entry).unwrap_or_else(|| {
+                data.push(entry);
+                data.len() - 1
+            });
+            *offset = index.try_into().unwrap();
+        }
+        U8Node::Folder(_) => {
+            for (_, child) in node.iter_mut() {
+                index_file_data(child, source, data);
+            }
+        }
+    }
+}
+
+/// Add everything from `other` that `node` doesn't already have (by
+/// case-insensitive name), recursively. Files that exist in both are
+/// kept as they are in `node`.
+fn add_missing_nodes(node: &mut U8Node, other: &U8Node, path: &str) {
+    let (Some(folder), Some(other_folder)) = (node.as_mut_folder(), other.as_folder()) else {
+        return;
+    };
+    for (name, other_child) in other_folder {
+        let child_path = format!("{path}/{name}");
+        match folder.get_mut(name) {
+            Some(existing) => add_missing_nodes(existing, other_child, &child_path),
+            None => {
+                trace!("Adding {child_path:?} from the second arc");
+                folder.insert(name.clone(), other_child.clone());
+            }
+        }
+    }
+}
+
+/// Point every file in a FNT at `remapping[offset]` instead.
+fn remap_file_data(node: &mut U8Node, remapping: &[u32]) {
+    match node {
+        U8Node::File(U8FileNode { offset, .. }) => *offset = remapping[*offset as usize],
+        U8Node::Folder(children) => {
+            for child in children.values_mut() {
+                remap_file_data(child, remapping);
+            }
+        }
+    }
+}
+
+/// Merge two openingTitle.arc files for different regions into a
+/// single arc supporting `to_regions`, and write it to `out_file`.
+///
+/// `b_region` uses the regional files from `b`, and every other region
+/// in `to_regions` uses the ones from `a`. All other files (TPLs, etc.)
+/// are taken from `a`, plus any that only exist in `b`. File data that
+/// `comparator` considers identical is only stored once, even if it
+/// came from both arcs, so a comparator that can give false positives
+/// (like `HashFileComparator`) can make one file's data replace
+/// another's; use `ByteFileComparator` to rule that out.
+pub fn merge_arcs_to_region_free<SR1, SR2, SW, C>(
+    mut a: SR1,
+    a_region: Region,
+    mut b: SR2,
+    b_region: Region,
+    mut out_file: SW,
+    to_regions: RegionBitFlags,
+    comparator: &C,
+) -> Result<(), ConvertOpeningTitleBetweenRegionsError>
+where
+    SR1: Seek + Read,
+    SR2: Seek + Read,
+    SW: Seek + Write,
+    C: FileComparator,
+{
+    if a_region == b_region {
+        return Err(ConvertOpeningTitleBetweenRegionsError::DuplicateSourceRegion(a_region));
+    }
+
+    let (mut fnt, a_data_table_offs) = u8_fnt::read(&mut a)?;
+    let (mut b_fnt, b_data_table_offs) = u8_fnt::read(&mut b)?;
+
+    let mut data = Vec::new();
+    index_file_data(&mut fnt, Source::A, &mut data);
+    index_file_data(&mut b_fnt, Source::B, &mut data);
+
+    let a_files = remove_regional_files(&mut fnt, RegionBitFlags::ALL)?;
+    let b_files = remove_regional_files(&mut b_fnt, RegionBitFlags::ALL)?;
+    let (a_files, _) = select_regional_files(&a_files, &[a_region], None)?;
+    let (b_files, _) = select_regional_files(&b_files, &[b_region], None)?;
+
+    add_missing_nodes(&mut fnt, &b_fnt, "");
+    let identity = &str::to_owned;
+    let mut a_to_regions = to_regions;
+    a_to_regions.remove(b_region);
+    add_new_filenames(
+        &mut fnt,
+        &a_files,
+        a_to_regions,
+        ConflictStrategy::Fail,
+        None,
+        identity,
+    )?;
+    if to_regions.contains(b_region) {
+        add_new_filenames(
+            &mut fnt,
+            &b_files,
+            b_region.into(),
+            ConflictStrategy::Fail,
+            None,
+            identity,
+        )?;
+    }
+    add_china_logo_fallback(&mut fnt, to_regions);
+
+    // Point files with identical data at the same entry, so that
+    // `lay_out_new_fat()` only copies it once. (Digests are only
+    // comparable for equality, so this is a linear search, but there
+    // are only a few dozen files.)
+    let mut unique: Vec<(u32, C::Digest, usize)> = Vec::new();
+    let mut remapping = Vec::with_capacity(data.len());
+    for (i, entry) in data.iter().enumerate() {
+        let digest = match entry.source {
+            Source::A => comparator.digest(
+                &mut a,
+                u64::from(a_data_table_offs) + u64::from(entry.offset),
+                entry.size as usize,
+            ),
+            Source::B => comparator.digest(
+                &mut b,
+                u64::from(b_data_table_offs) + u64::from(entry.offset),
+                entry.size as usize,
+            ),
+        }
+        .io_context("comparing file data")?;
+        let existing = unique
+            .iter()
+            .find(|(size, other, _)| *size == entry.size && *other == digest);
+        let index = match existing {
+            Some(&(_, _, index)) => index,
+            None => {
+                unique.push((entry.size, digest, i));
+                i
+            }
+        };
+        remapping.push(index.try_into().unwrap());
+    }
+    remap_file_data(&mut fnt, &remapping);
+    debug!(
+        "Merging {} pieces of file data ({} unique)",
+        data.len(),
+        unique.len()
+    );
+
+    let copies = lay_out_new_fat(&mut fnt)?;
+    u8_fnt::write(&mut out_file, &fnt).io_context("writing FNT")?;
+
+    let fat_start = out_file.stream_position().io_context("writing FAT")?;
+    let mut buffer = vec![0; FAT_COPY_BUFFER_SIZE];
+    for copy in copies {
+        util::write_zeros_to_align_to(&mut out_file, 0x20, fat_start).io_context("writing FAT")?;
+        let entry = data[copy.old_offset as usize];
+        let result = match entry.source {
+            Source::A => a
+                .seek(SeekFrom::Start(
+                    u64::from(a_data_table_offs) + u64::from(entry.offset),
+                ))
+                .and_then(|_| util::copy_n(&mut a, &mut out_file, copy.size.into(), &mut buffer)),
+            Source::B => b
+                .seek(SeekFrom::Start(
+                    u64::from(b_data_table_offs) + u64::from(entry.offset),
+                ))
+                .and_then(|_| util::copy_n(&mut b, &mut out_file, copy.size.into(), &mut buffer)),
+        };
+        result.io_context("writing FAT")?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::unnecessary_wraps)]
+mod tests {
+    use super::*;
+
+    use std::io::Cursor;
+
+    use crate::test_util;
+    use crate::{
+        ByteFileComparator, HashFileComparator, ANIM_FOLDER_PATH, BLYT_FOLDER_PATH,
+        TIMG_FOLDER_PATH,
+    };
+
+    type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+    /// Build a single-region arc, where every regional file's data is
+    /// `prefix` followed by its index, and there's one TPL with the
+    /// given name and data.
+    fn make_region_arc(region: Region, prefix: &[u8], tpl: (&str, &[u8])) -> Cursor<Vec<u8>> {
+        let filenames = region.filenames();
+        let mut files = vec![
+            (
+                format!("{ANIM_FOLDER_PATH}/{}", filenames.in_press_brlan),
+                [prefix, b"0"].concat(),
+            ),
+            (
+                format!("{ANIM_FOLDER_PATH}/{}", filenames.in_title_brlan),
+                [prefix, b"1"].concat(),
+            ),
+            (
+                format!("{ANIM_FOLDER_PATH}/{}", filenames.loop_press_brlan),
+                [prefix, b"2"].concat(),
+            ),
+            (
+                format!("{ANIM_FOLDER_PATH}/{}", filenames.out_press_brlan),
+                [prefix, b"3"].concat(),
+            ),
+            (
+                format!("{BLYT_FOLDER_PATH}/{}", filenames.brlyt),
+                [prefix, b"4"].concat(),
+            ),
+        ];
+        files.push((format!("{TIMG_FOLDER_PATH}/{}", tpl.0), tpl.1.to_vec()));
+
+        test_util::make_arc(
+            &files
+                .iter()
+                .map(|(path, data)| (path, data.as_slice()))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    /// Read a file's data out of an arc, by path.
+    fn read_file(
+        arc: &mut Cursor<Vec<u8>>,
+        path: &str,
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        arc.set_position(0);
+        let (root, data_table_offs) = u8_fnt::read(arc)?;
+        let node = root
+            .get(path)
+            .and_then(U8Node::as_file)
+            .ok_or(path.to_owned())?;
+        Ok(util::read_file_slice(
+            arc,
+            u64::from(data_table_offs) + u64::from(node.offset),
+            node.size as usize,
+        )?)
+    }
+
+    #[test]
+    fn test_merge() -> TestResult {
+        // The TPL is the same in both arcs, so it should only be stored
+        // once
+        let a = make_region_arc(
+            Region::E,
+            b"US",
+            ("wiiMario_Title_logo_local_00.tpl", b"logo"),
+        );
+        let b = make_region_arc(Region::J, b"JP", ("wiiMario_Title_logo_00.tpl", b"logo"));
+        let mut out = Cursor::new(Vec::new());
+        merge_arcs_to_region_free(
+            a,
+            Region::E,
+            b,
+            Region::J,
+            &mut out,
+            RegionBitFlags::ALL,
+            &ByteFileComparator,
+        )?;
+
+        for region in RegionBitFlags::ALL {
+            let filenames = region.filenames();
+            let expected: &[u8] = if region == Region::J { b"JP" } else { b"US" };
+            assert_eq!(
+                read_file(&mut out, &format!("/arc/anim/{}", filenames.in_press_brlan))?,
+                [expected, b"0"].concat()
+            );
+            assert_eq!(
+                read_file(&mut out, &format!("/arc/blyt/{}", filenames.brlyt))?,
+                [expected, b"4"].concat()
+            );
+        }
+        assert_eq!(
+            read_file(&mut out, "/arc/timg/wiiMario_Title_logo_00.tpl")?,
+            b"logo"
+        );
+
+        out.set_position(0);
+        let (root, _) = u8_fnt::read(&mut out)?;
+        let tpl_offset = |name: &str| {
+            root.get(&format!("/arc/timg/{name}"))
+                .and_then(U8Node::as_file)
+                .map(|node| node.offset)
+        };
+        assert!(tpl_offset("wiiMario_Title_logo_local_00.tpl").is_some());
+        assert_eq!(
+            tpl_offset("wiiMario_Title_logo_local_00.tpl"),
+            tpl_offset("wiiMario_Title_logo_00.tpl")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_different_data_same_size() -> TestResult {
+        // Same-sized TPLs with different data have to be kept apart
+        let a = make_region_arc(Region::E, b"US", ("a.tpl", b"logo"));
+        let b = make_region_arc(Region::J, b"JP", ("b.tpl", b"LOGO"));
+        let mut out = Cursor::new(Vec::new());
+        merge_arcs_to_region_free(
+            a,
+            Region::E,
+            b,
+            Region::J,
+            &mut out,
+            RegionBitFlags::ALL,
+            &ByteFileComparator,
+        )?;
+        assert_eq!(read_file(&mut out, "/arc/timg/a.tpl")?, b"logo");
+        assert_eq!(read_file(&mut out, "/arc/timg/b.tpl")?, b"LOGO");
+        Ok(())
+    }
+
+    #[test]
+    fn test_errors() -> TestResult {
+        let make = || make_region_arc(Region::E, b"US", ("a.tpl", b"logo"));
+        assert!(matches!(
+            merge_arcs_to_region_free(
+                make(),
+                Region::E,
+                make(),
+                Region::E,
+                Cursor::new(Vec::new()),
+                RegionBitFlags::ALL,
+                &HashFileComparator
+            ),
+            Err(ConvertOpeningTitleBetweenRegionsError::DuplicateSourceRegion(Region::E))
+        ));
+        assert!(matches!(
+            merge_arcs_to_region_free(
+                make(),
+                Region::E,
+                make(),
+                Region::J,
+                Cursor::new(Vec::new()),
+                RegionBitFlags::ALL,
+                &HashFileComparator
+            ),
+            Err(ConvertOpeningTitleBetweenRegionsError::MissingFiles(_))
+        ));
+        Ok(())
+    }
+}
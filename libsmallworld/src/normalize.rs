@@ -0,0 +1,307 @@
+//! Support for renaming regional files with slightly-off names (as
+//! produced by some buggy tools) to the canonical names smallworld
+//! expects, so that they can be converted like any other regional file.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{Read, Seek};
+
+use log::warn;
+
+use crate::u8_fnt::{self, U8FolderNode, U8Node};
+use crate::{
+    get_anim_folder, get_blyt_folder, get_mut_anim_folder, get_mut_blyt_folder,
+    ConvertOpeningTitleBetweenRegionsError, Region, ANIM_FOLDER_PATH, BLYT_FOLDER_PATH,
+};
+
+/// Everything (other than the region tag) that identifies which
+/// regional-file slot a filename belongs to: the extension, plus a
+/// keyword for brlans.
+struct SlotPattern {
+    keyword: Option<&'static str>,
+    extension: &'static str,
+}
+
+const IN_PRESS_BRLAN: SlotPattern = SlotPattern {
+    keyword: Some("inpress"),
+    extension: ".brlan",
+};
+const IN_TITLE_BRLAN: SlotPattern = SlotPattern {
+    keyword: Some("intitle"),
+    extension: ".brlan",
+};
+const LOOP_PRESS_BRLAN: SlotPattern = SlotPattern {
+    keyword: Some("looppress"),
+    extension: ".brlan",
+};
+const OUT_PRESS_BRLAN: SlotPattern = SlotPattern {
+    keyword: Some("outpress"),
+    extension: ".brlan",
+};
+const BRLYT: SlotPattern = SlotPattern {
+    keyword: None,
+    extension: ".brlyt",
+};
+
+/// A regional file that was (or would be) renamed to its canonical
+/// name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FilenameNormalization {
+    /// Path of the folder containing the file.
+    pub folder: &'static str,
+    /// The file's original name.
+    pub from: String,
+    /// The canonical name it was renamed to.
+    pub to: &'static str,
+}
+
+impl fmt::Display for FilenameNormalization {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{0}/{1} -> {0}/{2}", self.folder, self.from, self.to)
+    }
+}
+
+/// Get the prefix that all of a region's regional filenames start with
+/// (e.g. "openingtitle_eu" for P), in lowercase.
+pub(crate) fn region_prefix(region: Region) -> String {
+    let brlyt = region.filenames().brlyt.to_lowercase();
+    let tag_end = brlyt["openingtitle_".len()..]
+        .find(['_', '.'])
+        .map_or(brlyt.len(), |i| i + "openingtitle_".len());
+    brlyt[..tag_end].to_owned()
+}
+
+/// Check whether a (non-canonical) filename clearly corresponds to a
+/// particular region and slot.
+fn matches_pattern(filename: &str, prefix: &str, pattern: &SlotPattern) -> bool {
+    let filename = filename.to_lowercase();
+
+    if !filename.starts_with(prefix) || !filename.contains(pattern.extension) {
+        return false;
+    }
+    match pattern.keyword {
+        Some(keyword) => filename.contains(keyword),
+        // The brlyt shouldn't look like any of the brlans
+        None => [
+            IN_PRESS_BRLAN,
+            IN_TITLE_BRLAN,
+            LOOP_PRESS_BRLAN,
+            OUT_PRESS_BRLAN,
+        ]
+        .iter()
+        .all(|brlan| !filename.contains(brlan.keyword.unwrap())),
+    }
+}
+
+/// Find the files in a folder that should be renamed to canonical
+/// names, given the canonical names expected in that folder (along
+/// with their regions and slot patterns).
+///
+/// A file is only renamed if it corresponds to exactly one canonical
+/// name, that canonical name isn't already present, and no other file
+/// also corresponds to it.
+fn find_in_folder(
+    folder: &U8FolderNode,
+    folder_path: &'static str,
+    expected: &[(Region, &'static str, &SlotPattern)],
+) -> Vec<FilenameNormalization> {
+    let canonical_names: Vec<&str> = expected.iter().map(|(_, name, _)| *name).collect();
+
+    // {canonical name: [nonstandard names that match it]}
+    let mut candidates: HashMap<&'static str, Vec<&String>> = HashMap::new();
+
+    for (filename, node) in folder {
+        if !matches!(node, U8Node::File(_)) || canonical_names.contains(&filename.as_str()) {
+            continue;
+        }
+
+        let matches: Vec<&'static str> = expected
+            .iter()
+            .filter(|(region, canonical, pattern)| {
+                // (a file whose name only differs from the canonical
+                // one in case is found by the lookup, but still needs
+                // renaming)
+                !folder
+                    .get_key_value(canonical)
+                    .is_some_and(|(name, _)| name == canonical)
+                    && matches_pattern(filename, &region_prefix(*region), pattern)
+            })
+            .map(|(_, canonical, _)| *canonical)
+            .collect();
+
+        match matches[..] {
+            [] => (),
+            [canonical] => candidates.entry(canonical).or_default().push(filename),
+            _ => warn!("{folder_path}/{filename} matches more than one regional filename"),
+        }
+    }
+
+    let mut normalizations: Vec<FilenameNormalization> = candidates
+        .into_iter()
+        .filter_map(|(canonical, filenames)| match filenames[..] {
+            [filename] => Some(FilenameNormalization {
+                folder: folder_path,
+                from: filename.clone(),
+                to: canonical,
+            }),
+            _ => {
+                warn!("More than one file in {folder_path} could be renamed to {canonical}");
+                None
+            }
+        })
+        .collect();
+    normalizations.sort_by_key(|normalization| normalization.from.to_lowercase());
+    normalizations
+}
+
+/// List the renames needed to give nonstandard regional filenames in
+/// a FNT root node their canonical names.
+fn find_normalizations(
+    fnt: &U8Node,
+) -> Result<Vec<FilenameNormalization>, ConvertOpeningTitleBetweenRegionsError> {
+    let mut expected_brlans = Vec::new();
+    let mut expected_brlyts = Vec::new();
+    for (region, filenames) in Region::all_filenames() {
+        expected_brlans.extend([
+            (region, filenames.in_press_brlan, &IN_PRESS_BRLAN),
+            (region, filenames.in_title_brlan, &IN_TITLE_BRLAN),
+            (region, filenames.loop_press_brlan, &LOOP_PRESS_BRLAN),
+            (region, filenames.out_press_brlan, &OUT_PRESS_BRLAN),
+        ]);
+        expected_brlyts.push((region, filenames.brlyt, &BRLYT));
+    }
+
+    let mut normalizations =
+        find_in_folder(get_anim_folder(fnt)?, ANIM_FOLDER_PATH, &expected_brlans);
+    normalizations.extend(find_in_folder(
+        get_blyt_folder(fnt)?,
+        BLYT_FOLDER_PATH,
+        &expected_brlyts,
+    ));
+    Ok(normalizations)
+}
+
+/// Rename nonstandard regional filenames in a FNT root node to their
+/// canonical names, and return the list of renames performed.
+pub(crate) fn normalize_regional_filenames(
+    fnt: &mut U8Node,
+) -> Result<Vec<FilenameNormalization>, ConvertOpeningTitleBetweenRegionsError> {
+    let normalizations = find_normalizations(fnt)?;
+
+    for normalization in &normalizations {
+        let folder = if normalization.folder == ANIM_FOLDER_PATH {
+            get_mut_anim_folder(fnt)?
+        } else {
+            get_mut_blyt_folder(fnt)?
+        };
+        if let Some(node) = folder.remove(&normalization.from) {
+            folder.insert(normalization.to.to_owned(), node);
+        }
+    }
+
+    Ok(normalizations)
+}
+
+/// Read openingTitle.arc from a `Seek+Read`, and list the files whose
+/// names look like regional filenames but aren't quite right, along
+/// with the canonical names that the `normalize_names` conversion
+/// option would rename them to. Nothing is modified.
+pub fn find_nonstandard_regional_filenames<SR: Seek + Read>(
+    mut in_file: SR,
+) -> Result<Vec<FilenameNormalization>, ConvertOpeningTitleBetweenRegionsError> {
+    let (fnt, _) = u8_fnt::read(&mut in_file)?;
+    find_normalizations(&fnt)
+}
+
+#[cfg(test)]
+#[allow(clippy::unnecessary_wraps)]
+mod tests {
+    use super::*;
+
+    use crate::empty_openingtitle_tree;
+    use crate::u8_fnt::U8FileNode;
+
+    type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+    /// Helper function to make an openingTitle FNT with the given anim
+    /// and blyt filenames (all pointing to empty files)
+    fn make_fnt(anim: &[&str], blyt: &[&str]) -> U8Node {
+        let mut fnt = empty_openingtitle_tree();
+        let anim_paths = anim.iter().map(|name| format!("{ANIM_FOLDER_PATH}/{name}"));
+        let blyt_paths = blyt.iter().map(|name| format!("{BLYT_FOLDER_PATH}/{name}"));
+        for path in anim_paths.chain(blyt_paths) {
+            fnt.insert_path(&path, U8Node::File(U8FileNode { offset: 0, size: 0 }))
+                .unwrap();
+        }
+        fnt
+    }
+
+    #[test]
+    fn test_region_prefix() -> TestResult {
+        assert_eq!(region_prefix(Region::P), "openingtitle_eu");
+        assert_eq!(region_prefix(Region::J), "openingtitle_13");
+        Ok(())
+    }
+
+    #[test]
+    fn test_normalize() -> TestResult {
+        let mut fnt = make_fnt(
+            &[
+                "openingTitle_EU_01_inPress.brlan",
+                "openingtitle_eu_00_intitle.brlan",
+                "openingTitle_EU_00_loopPress.brlan",
+                "openingTitle_EU_00_outPress_fixed.brlan",
+                "something else.brlan",
+            ],
+            &["openingTitle_13.brlyt.bak"],
+        );
+
+        let normalizations = normalize_regional_filenames(&mut fnt)?;
+        assert_eq!(
+            normalizations
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            [
+                "/arc/anim/openingtitle_eu_00_intitle.brlan -> \
+                 /arc/anim/openingTitle_EU_00_inTitle.brlan",
+                "/arc/anim/openingTitle_EU_00_outPress_fixed.brlan -> \
+                 /arc/anim/openingTitle_EU_00_outPress.brlan",
+                "/arc/anim/openingTitle_EU_01_inPress.brlan -> \
+                 /arc/anim/openingTitle_EU_00_inPress.brlan",
+                "/arc/blyt/openingTitle_13.brlyt.bak -> /arc/blyt/openingTitle_13.brlyt",
+            ]
+        );
+
+        assert_eq!(
+            fnt,
+            make_fnt(
+                &[
+                    "openingTitle_EU_00_inPress.brlan",
+                    "openingTitle_EU_00_inTitle.brlan",
+                    "openingTitle_EU_00_loopPress.brlan",
+                    "openingTitle_EU_00_outPress.brlan",
+                    "something else.brlan",
+                ],
+                &["openingTitle_13.brlyt"],
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_ambiguous() -> TestResult {
+        // Two candidates for the same canonical name, and one that
+        // would collide with an existing canonical name
+        let fnt = make_fnt(
+            &[
+                "openingTitle_US_01_inPress.brlan",
+                "openingTitle_US_02_inPress.brlan",
+            ],
+            &["openingTitle_KR_00.brlyt", "openingTitle_KR_01.brlyt"],
+        );
+
+        assert!(find_normalizations(&fnt)?.is_empty());
+        Ok(())
+    }
+}
@@ -5,6 +5,7 @@ use phf::phf_map;
 
 /// Container for all region-specific openingTitle.arc filenames for a
 /// particular region.
+#[derive(Debug)]
 pub struct OpeningTitleRegionFilenames<'a> {
     pub in_press_brlan: &'a str,
     pub in_title_brlan: &'a str,
@@ -73,8 +74,14 @@ const C_FILENAMES: OpeningTitleRegionFilenames<'static> = OpeningTitleRegionFile
     // (for reference) TPL: "wiiMario_Title_logo_CN.tpl"
 };
 
+/// The title logo TPL shared by the "P" and "E" regions.
+pub const SHARED_LOGO_TPL_FILENAME: &str = "wiiMario_Title_logo_local_00.tpl";
+
+/// The title logo TPL used by the "C" region.
+pub const CHINA_LOGO_TPL_FILENAME: &str = "wiiMario_Title_logo_CN.tpl";
+
 /// Map that contains the filenames for every region.
-pub const ALL_FILENAMES: phf::Map<&'static str, OpeningTitleRegionFilenames> = phf_map! {
+pub static ALL_FILENAMES: phf::Map<&'static str, OpeningTitleRegionFilenames> = phf_map! {
     "P" => P_FILENAMES,
     "E" => E_FILENAMES,
     "J" => J_FILENAMES,
@@ -0,0 +1,246 @@
+//! A simple binary patch format, for distributing a converted arc as a
+//! small delta against the original instead of as a whole file.
+//!
+//! Like bsdiff, a patch is mostly made of instructions to copy ranges
+//! of the original file, with literal bytes only where the modified
+//! file has data that can't be found in the original. Unlike bsdiff,
+//! there's no compression and no "approximate match" diffing, which
+//! keeps the implementation small. Converted arcs are mostly file data
+//! copied verbatim from the original (just moved around), so this
+//! still produces tiny patches in practice.
+//!
+//! Only available with the `bsdiff` feature.
+
+use std::collections::HashMap;
+
+use thiserror::Error;
+
+/// The magic bytes at the start of every patch.
+const PATCH_MAGIC: &[u8; 8] = b"SWPATCH1";
+
+/// Size of the blocks of the original file that are indexed for
+/// matching. Matches shorter than this are never found.
+const BLOCK_SIZE: usize = 16;
+
+/// Patch instruction: copy a range of the original file.
+const OP_COPY: u8 = 0;
+
+/// Patch instruction: insert literal bytes.
+const OP_INSERT: u8 = 1;
+
+/// All errors that can be encountered when applying a patch.
+#[non_exhaustive]
+#[derive(Error, Debug)]
+pub enum ApplyPatchError {
+    /// The patch doesn't start with the expected magic bytes.
+    #[error("not a smallworld patch")]
+    BadMagic,
+
+    /// The patch ended in the middle of an instruction.
+    #[error("patch is truncated")]
+    Truncated,
+
+    /// An unknown instruction was found at the given offset.
+    #[error("unknown patch instruction {0:#x} at {1:#x}")]
+    UnknownInstruction(u8, usize),
+
+    /// A copy instruction referred to data past the end of the original
+    /// file (which is probably the wrong file).
+    #[error("patch copies {offset:#x}-{end:#x} from the original, but it's only {len:#x} bytes")]
+    CopyOutOfRange { offset: u64, end: u64, len: usize },
+
+    /// The patched output had a different length than the patch says
+    /// it should.
+    #[error("patched output is {actual:#x} bytes, but should be {expected:#x}")]
+    LengthMismatch { expected: u64, actual: usize },
+}
+
+/// Create a patch that turns `original` into `modified`, for use with
+/// `apply_patch()`.
+pub fn make_patch(original: &[u8], modified: &[u8]) -> Vec<u8> {
+    // Index the start of every block of the original file. If a block
+    // appears more than once, the first copy is used.
+    let mut blocks: HashMap<&[u8], usize> = HashMap::new();
+    for (i, block) in original.chunks_exact(BLOCK_SIZE).enumerate() {
+        blocks.entry(block).or_insert(i * BLOCK_SIZE);
+    }
+
+    let mut patch = PATCH_MAGIC.to_vec();
+    patch.extend_from_slice(&(modified.len() as u64).to_be_bytes());
+
+    let mut literal_start = 0;
+    let mut pos = 0;
+    while pos + BLOCK_SIZE <= modified.len() {
+        let Some(&block_offset) = blocks.get(&modified[pos..pos + BLOCK_SIZE]) else {
+            pos += 1;
+            continue;
+        };
+
+        // Extend the match as far as it goes in both directions (but
+        // not backwards past data that's already been written)
+        let mut start = pos;
+        let mut original_start = block_offset;
+        while start > literal_start
+            && original_start > 0
+            && modified[start - 1] == original[original_start - 1]
+        {
+            start -= 1;
+            original_start -= 1;
+        }
+        let mut end = pos + BLOCK_SIZE;
+        let mut original_end = block_offset + BLOCK_SIZE;
+        while end < modified.len()
+            && original_end < original.len()
+            && modified[end] == original[original_end]
+        {
+            end += 1;
+            original_end += 1;
+        }
+
+        write_insert(&mut patch, &modified[literal_start..start]);
+        patch.push(OP_COPY);
+        patch.extend_from_slice(&(original_start as u64).to_be_bytes());
+        patch.extend_from_slice(&((end - start) as u64).to_be_bytes());
+
+        literal_start = end;
+        pos = end;
+    }
+    write_insert(&mut patch, &modified[literal_start..]);
+
+    patch
+}
+
+/// Helper function for `make_patch()` to write an insert instruction,
+/// if there's anything to insert.
+fn write_insert(patch: &mut Vec<u8>, data: &[u8]) {
+    if !data.is_empty() {
+        patch.push(OP_INSERT);
+        patch.extend_from_slice(&(data.len() as u64).to_be_bytes());
+        patch.extend_from_slice(data);
+    }
+}
+
+/// Apply a patch created by `make_patch()` to `original`, and return
+/// the modified data.
+pub fn apply_patch(original: &[u8], patch: &[u8]) -> Result<Vec<u8>, ApplyPatchError> {
+    let rest = patch
+        .strip_prefix(PATCH_MAGIC)
+        .ok_or(ApplyPatchError::BadMagic)?;
+    let mut reader = PatchReader {
+        patch,
+        pos: patch.len() - rest.len(),
+    };
+
+    let expected_len = reader.read_u64()?;
+    let mut output = Vec::new();
+
+    while reader.pos < patch.len() {
+        let op_pos = reader.pos;
+        match reader.read_bytes(1)?[0] {
+            OP_COPY => {
+                let offset = reader.read_u64()?;
+                let len = reader.read_u64()?;
+                let end = offset.saturating_add(len);
+                let range = usize::try_from(offset)
+                    .ok()
+                    .zip(usize::try_from(end).ok())
+                    .filter(|&(_, end)| end <= original.len())
+                    .ok_or(ApplyPatchError::CopyOutOfRange {
+                        offset,
+                        end,
+                        len: original.len(),
+                    })?;
+                output.extend_from_slice(&original[range.0..range.1]);
+            }
+            OP_INSERT => {
+                let len = reader.read_u64()?;
+                let len = usize::try_from(len).map_err(|_| ApplyPatchError::Truncated)?;
+                output.extend_from_slice(reader.read_bytes(len)?);
+            }
+            op => return Err(ApplyPatchError::UnknownInstruction(op, op_pos)),
+        }
+    }
+
+    if output.len() as u64 != expected_len {
+        return Err(ApplyPatchError::LengthMismatch {
+            expected: expected_len,
+            actual: output.len(),
+        });
+    }
+    Ok(output)
+}
+
+/// Helper for reading the fields of a patch in `apply_patch()`.
+struct PatchReader<'a> {
+    patch: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> PatchReader<'a> {
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], ApplyPatchError> {
+        let bytes = self
+            .pos
+            .checked_add(len)
+            .and_then(|end| self.patch.get(self.pos..end))
+            .ok_or(ApplyPatchError::Truncated)?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn read_u64(&mut self) -> Result<u64, ApplyPatchError> {
+        Ok(u64::from_be_bytes(self.read_bytes(8)?.try_into().unwrap()))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unnecessary_wraps)]
+mod tests {
+    use super::*;
+
+    type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+    #[test]
+    fn test_round_trip() -> TestResult {
+        let original: Vec<u8> = (0..0x1000).map(|i| (i * 7 % 251) as u8).collect();
+
+        // Move some data around, and add a bit of new data
+        let mut modified = original[0x800..].to_vec();
+        modified.extend_from_slice(b"something new");
+        modified.extend_from_slice(&original[..0x800]);
+
+        let patch = make_patch(&original, &modified);
+        assert!(patch.len() < 0x80);
+        assert_eq!(apply_patch(&original, &patch)?, modified);
+
+        // Edge cases
+        for (original, modified) in [
+            (&b""[..], &b""[..]),
+            (b"", b"only new data"),
+            (b"only old data", b""),
+        ] {
+            let patch = make_patch(original, modified);
+            assert_eq!(apply_patch(original, &patch)?, modified);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_errors() -> TestResult {
+        let original = [0x55; 0x40];
+        let patch = make_patch(&original, &[0x55; 0x20]);
+
+        assert!(matches!(
+            apply_patch(&original, b"not a patch"),
+            Err(ApplyPatchError::BadMagic)
+        ));
+        assert!(matches!(
+            apply_patch(&original, &patch[..patch.len() - 1]),
+            Err(ApplyPatchError::Truncated)
+        ));
+        assert!(matches!(
+            apply_patch(&original[..0x10], &patch),
+            Err(ApplyPatchError::CopyOutOfRange { .. })
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,181 @@
+//! Support for building a new openingTitle.arc from loose files, for
+//! people who no longer have an original to convert. smallworld
+//! doesn't read the files itself; instead, the caller provides them by
+//! path within the arc (e.g. `"/arc/anim/openingTitle_US_00_inPress.brlan"`).
+
+use std::io::{Read, Seek, Write};
+
+use itertools::Itertools;
+use log::{debug, warn};
+
+use crate::u8_fnt::{self, FntBuilder};
+use crate::util;
+use crate::{
+    ConvertOpeningTitleBetweenRegionsError, IoContext, RegionBitFlags, ANIM_FOLDER_PATH,
+    BLYT_FOLDER_PATH, FAT_COPY_BUFFER_SIZE, TIMG_FOLDER_PATH,
+};
+
+/// Build a complete openingTitle.arc containing the regional files for
+/// `to_regions`, plus the given TPL files (which go in the "timg"
+/// folder), and write it to `out_file`.
+///
+/// `open_file` is called with the path of each file in the new arc, and
+/// should return a reader for its data along with its size, or `None`
+/// if the file isn't available. Missing files are reported together in
+/// a single `MissingFiles` error, before anything is written.
+///
+/// smallworld doesn't know the TPL filenames, so those are up to the
+/// caller. A warning is logged if there aren't any, since the game
+/// won't be able to show the title logo without them.
+pub fn build_openingtitle_template<SW, R, F>(
+    mut out_file: SW,
+    to_regions: RegionBitFlags,
+    tpl_filenames: &[&str],
+    mut open_file: F,
+) -> Result<(), ConvertOpeningTitleBetweenRegionsError>
+where
+    SW: Seek + Write,
+    R: Read,
+    F: FnMut(&str) -> Result<Option<(R, u32)>, std::io::Error>,
+{
+    if tpl_filenames.is_empty() {
+        warn!("No TPL files were provided, so the title logo will be missing");
+    }
+
+    // Several regions can share the same filenames (e.g. P and E), so
+    // each path is only included once
+    let paths: Vec<String> = to_regions
+        .iter()
+        .flat_map(|region| {
+            let filenames = region.filenames();
+            [
+                format!("{ANIM_FOLDER_PATH}/{}", filenames.in_press_brlan),
+                format!("{ANIM_FOLDER_PATH}/{}", filenames.in_title_brlan),
+                format!("{ANIM_FOLDER_PATH}/{}", filenames.loop_press_brlan),
+                format!("{ANIM_FOLDER_PATH}/{}", filenames.out_press_brlan),
+                format!("{BLYT_FOLDER_PATH}/{}", filenames.brlyt),
+            ]
+        })
+        .chain(
+            tpl_filenames
+                .iter()
+                .map(|filename| format!("{TIMG_FOLDER_PATH}/{filename}")),
+        )
+        .unique()
+        .collect();
+
+    // Open everything up front, so that all missing files can be
+    // reported at once
+    let mut files = Vec::new();
+    let mut missing = Vec::new();
+    for path in &paths {
+        match open_file(path).io_context("opening template input file")? {
+            Some(file) => files.push((path, file)),
+            None => missing.push(path.as_str()),
+        }
+    }
+    if !missing.is_empty() {
+        return Err(ConvertOpeningTitleBetweenRegionsError::MissingFiles(
+            missing.join(", "),
+        ));
+    }
+
+    debug!("Building template FNT with {} files", files.len());
+    let mut builder = FntBuilder::new();
+    let mut offsets = Vec::new();
+    for (path, (_, size)) in &files {
+        offsets.push(builder.add_file(path, *size).map_err(|err| {
+            ConvertOpeningTitleBetweenRegionsError::InvalidOpeningTitleStructure(err.to_string())
+        })?);
+    }
+
+    u8_fnt::write(&mut out_file, &builder.finish()).io_context("writing FNT")?;
+    let fat_start = out_file.stream_position().io_context("writing FAT")?;
+
+    let mut buffer = vec![0; FAT_COPY_BUFFER_SIZE];
+    for ((_, (mut reader, size)), offset) in files.into_iter().zip(offsets) {
+        util::write_zeros_to_align_to(&mut out_file, 0x20, fat_start).io_context("writing FAT")?;
+        debug_assert_eq!(out_file.stream_position()? - fat_start, u64::from(offset));
+        util::copy_n(&mut reader, &mut out_file, size.into(), &mut buffer)
+            .io_context("writing FAT")?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::unnecessary_wraps)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+    use std::io::Cursor;
+
+    use crate::Region;
+
+    type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+    #[test]
+    fn test_build() -> TestResult {
+        let regions = RegionBitFlags::from_iter([Region::E, Region::J]);
+        let tpl = "/arc/timg/wiiMario_Title_logo_US.tpl";
+
+        // Give every file its own path as its data
+        let open_file = |path: &str| {
+            Ok(Some((
+                Cursor::new(path.as_bytes().to_vec()),
+                path.len().try_into().unwrap(),
+            )))
+        };
+        let mut out = Cursor::new(Vec::new());
+        build_openingtitle_template(
+            &mut out,
+            regions,
+            &["wiiMario_Title_logo_US.tpl"],
+            open_file,
+        )?;
+
+        let mut files = HashMap::new();
+        let (root, data_table_offs) = u8_fnt::read(&mut out)?;
+        for region in [Region::E, Region::J] {
+            let filenames = region.filenames();
+            for path in [
+                format!("/arc/anim/{}", filenames.in_press_brlan),
+                format!("/arc/blyt/{}", filenames.brlyt),
+            ] {
+                files.insert(path.clone(), root.get(&path).cloned());
+            }
+        }
+        files.insert(tpl.to_owned(), root.get(tpl).cloned());
+
+        for (path, node) in files {
+            let node = node.ok_or(format!("{path} missing"))?;
+            let node = node.as_file().unwrap();
+            let data = util::read_file_slice(
+                &mut out,
+                (data_table_offs + node.offset).into(),
+                node.size.try_into()?,
+            )?;
+            assert_eq!(data, path.as_bytes());
+        }
+        let korean_path = format!("/arc/anim/{}", Region::K.filenames().in_press_brlan);
+        assert!(root.get(&korean_path).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_files() -> TestResult {
+        let open_file =
+            |path: &str| Ok((!path.ends_with(".brlyt")).then(|| (Cursor::new(vec![0; 4]), 4)));
+        let result =
+            build_openingtitle_template(Cursor::new(Vec::new()), Region::J.into(), &[], open_file);
+
+        match result {
+            Err(ConvertOpeningTitleBetweenRegionsError::MissingFiles(files)) => {
+                assert_eq!(files, format!("/arc/blyt/{}", Region::J.filenames().brlyt));
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+        Ok(())
+    }
+}
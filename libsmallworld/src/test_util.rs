@@ -0,0 +1,27 @@
+//! Helper functions shared between the unit tests of several modules.
+
+use std::io::{Cursor, Write};
+
+use crate::u8_fnt::{self, FntBuilder};
+
+/// Build an arc containing the given files, by forward-slash-separated
+/// path (creating folders as needed). File data is laid out in the
+/// order the files are listed, and the returned cursor is positioned
+/// at the start of the arc.
+pub fn make_arc<P: AsRef<str>>(files: &[(P, &[u8])]) -> Cursor<Vec<u8>> {
+    let mut builder = FntBuilder::new();
+    let mut fat = Vec::new();
+    for (path, data) in files {
+        let offset = builder
+            .add_file(path.as_ref(), data.len().try_into().unwrap())
+            .unwrap();
+        fat.resize(offset as usize, 0);
+        fat.extend_from_slice(data);
+    }
+
+    let mut arc = Cursor::new(Vec::new());
+    u8_fnt::write(&mut arc, &builder.finish()).unwrap();
+    arc.write_all(&fat).unwrap();
+    arc.set_position(0);
+    arc
+}
@@ -0,0 +1,287 @@
+//! An in-memory U8 archive, for editing file data without dealing with
+//! FAT offsets and sizes directly.
+
+use std::io::{self, Read, Seek, Write};
+
+use crate::u8_fnt::{self, FntBuilderError, ParseU8Error, ParsedArc, U8FileNode, U8Node};
+use crate::util;
+
+/// A U8 archive held entirely in memory: its FNT, plus each file's
+/// data. File data is read and written by path, and offsets are only
+/// assigned when the archive is written.
+///
+/// Files that shared data in the original arc get separate copies, so
+/// that changing one doesn't affect the others.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct U8Archive {
+    /// The FNT, with each file node's offset replaced by an index into
+    /// `data`.
+    arc: ParsedArc,
+
+    /// The data for each file.
+    data: Vec<Vec<u8>>,
+}
+
+impl Default for U8Archive {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl U8Archive {
+    /// Create an empty archive.
+    pub fn new() -> Self {
+        Self {
+            arc: ParsedArc::new(U8Node::Folder(Default::default())),
+            data: Vec::new(),
+        }
+    }
+
+    /// Read a U8 file, including all of its file data.
+    pub fn read<SR: Seek + Read>(file: &mut SR) -> Result<Self, ParseU8Error> {
+        /// Helper function to read the files in a node, recursively
+        fn visit_node<SR: Seek + Read>(
+            node: &mut U8Node,
+            data_table_offs: u32,
+            file: &mut SR,
+            data: &mut Vec<Vec<u8>>,
+        ) -> Result<(), ParseU8Error> {
+            match node {
+                U8Node::File(U8FileNode { offset, size }) => {
+                    data.push(util::read_file_slice(
+                        file,
+                        u64::from(data_table_offs) + u64::from(*offset),
+                        (*size).try_into().unwrap(),
+                    )?);
+                    *offset = (data.len() - 1).try_into().unwrap();
+                }
+                U8Node::Folder(_) => {
+                    // (in FNT order, so that the indices are
+                    // deterministic)
+                    for (_, child) in node.iter_mut() {
+                        visit_node(child, data_table_offs, file, data)?;
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        let mut arc = u8_fnt::read_parsed_arc(file)?;
+        let mut data = Vec::new();
+        visit_node(&mut arc.root, arc.data_table_offs, file, &mut data)?;
+        Ok(Self { arc, data })
+    }
+
+    /// Get a file's data, by forward-slash-separated path (looked up
+    /// case-insensitively, like `U8Node::get()`). Returns `None` if
+    /// there's no such file.
+    pub fn get_file(&self, path: &str) -> Option<&[u8]> {
+        let node = self.arc.root.get(path)?.as_file()?;
+        Some(&self.data[node.offset as usize])
+    }
+
+    /// Set a file's data, by forward-slash-separated path. If the file
+    /// doesn't exist yet, it's created, along with any missing folders
+    /// leading to it.
+    ///
+    /// Fails with `NotAFolder` if a component of the path (or the root)
+    /// is a file, `AlreadyExists` if the path itself is a folder, and
+    /// `FatTooLarge` if the data is 4 GiB or larger.
+    pub fn set_file(&mut self, path: &str, data: Vec<u8>) -> Result<(), FntBuilderError> {
+        let size: u32 = data
+            .len()
+            .try_into()
+            .map_err(|_| FntBuilderError::FatTooLarge)?;
+
+        let root = self
+            .arc
+            .root
+            .as_mut_folder()
+            .ok_or_else(|| FntBuilderError::NotAFolder("/".to_owned()))?;
+        let (folder, filename) = u8_fnt::parent_folder_for_path(root, path)?;
+
+        match folder.get_mut(filename) {
+            Some(U8Node::File(node)) => {
+                self.data[node.offset as usize] = data;
+                node.size = size;
+            }
+            Some(U8Node::Folder(_)) => {
+                return Err(FntBuilderError::AlreadyExists(path.to_owned()));
+            }
+            None => {
+                let offset = self.data.len().try_into().unwrap();
+                self.data.push(data);
+                folder.insert(
+                    filename.to_owned(),
+                    U8Node::File(U8FileNode { offset, size }),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the archive as a U8 file. Each file's data is placed in
+    /// FNT order, aligned to 0x20.
+    pub fn write<SW: Seek + Write>(&self, file: &mut SW) -> Result<(), io::Error> {
+        /// Helper function to assign FAT offsets to the files in a
+        /// node, recursively, in the same order `u8_fnt::write()`
+        /// visits them. Also collects the data indices in that order.
+        fn visit_node(
+            node: &mut U8Node,
+            fat_size: &mut u64,
+            order: &mut Vec<usize>,
+        ) -> Result<(), io::Error> {
+            match node {
+                U8Node::File(U8FileNode { offset, size }) => {
+                    let new_offset = (*fat_size + 0x1f) & !0x1f;
+                    *fat_size = new_offset + u64::from(*size);
+                    if *fat_size > u32::MAX.into() {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            FntBuilderError::FatTooLarge,
+                        ));
+                    }
+                    order.push(*offset as usize);
+                    *offset = new_offset as u32;
+                }
+                U8Node::Folder(_) => {
+                    for (_, child) in node.iter_mut() {
+                        visit_node(child, fat_size, order)?;
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        let mut arc = self.arc.clone();
+        let mut order = Vec::new();
+        visit_node(&mut arc.root, &mut 0, &mut order)?;
+
+        u8_fnt::write_parsed_arc(file, &arc)?;
+        let fat_start = file.stream_position()?;
+        for index in order {
+            util::write_zeros_to_align_to(file, 0x20, fat_start)?;
+            file.write_all(&self.data[index])?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unnecessary_wraps)]
+mod tests {
+    use super::*;
+
+    use std::io::Cursor;
+
+    use crate::u8_fnt::U8FolderNode;
+
+    type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+    /// Write an archive, and read it back in.
+    fn round_trip(archive: &U8Archive) -> Result<U8Archive, Box<dyn std::error::Error>> {
+        let mut cursor = Cursor::new(Vec::new());
+        archive.write(&mut cursor)?;
+        cursor.set_position(0);
+        Ok(U8Archive::read(&mut cursor)?)
+    }
+
+    #[test]
+    fn test_read() -> TestResult {
+        // Two files sharing the same data, and one more
+        let root = U8Node::Folder(U8FolderNode::from([(
+            "arc".to_owned(),
+            U8Node::Folder(U8FolderNode::from([
+                (
+                    "a".to_owned(),
+                    U8Node::File(U8FileNode { offset: 0, size: 3 }),
+                ),
+                (
+                    "b".to_owned(),
+                    U8Node::File(U8FileNode { offset: 0, size: 3 }),
+                ),
+                (
+                    "c".to_owned(),
+                    U8Node::File(U8FileNode {
+                        offset: 0x20,
+                        size: 2,
+                    }),
+                ),
+            ])),
+        )]));
+        let mut cursor = Cursor::new(Vec::new());
+        u8_fnt::write(&mut cursor, &root)?;
+        cursor.write_all(&[b'A'; 0x20])?;
+        cursor.write_all(b"CC")?;
+        cursor.set_position(0);
+
+        let mut archive = U8Archive::read(&mut cursor)?;
+        assert_eq!(archive.get_file("/arc/a"), Some(&b"AAA"[..]));
+        assert_eq!(archive.get_file("/ARC/C"), Some(&b"CC"[..]));
+        assert_eq!(archive.get_file("/arc"), None);
+        assert_eq!(archive.get_file("/arc/d"), None);
+
+        // Shared data is copied, not aliased
+        archive.set_file("/arc/a", b"new".to_vec())?;
+        assert_eq!(archive.get_file("/arc/b"), Some(&b"AAA"[..]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_replace_grow_shrink() -> TestResult {
+        let mut archive = U8Archive::new();
+        archive.set_file("/arc/a", b"1234".to_vec())?;
+        archive.set_file("/arc/b", b"5678".to_vec())?;
+
+        // Replace (same size)
+        archive.set_file("/arc/a", b"abcd".to_vec())?;
+        let archive_2 = round_trip(&archive)?;
+        assert_eq!(archive_2.get_file("/arc/a"), Some(&b"abcd"[..]));
+        assert_eq!(archive_2.get_file("/arc/b"), Some(&b"5678"[..]));
+
+        // Grow past the alignment boundary, so that the next file has
+        // to move
+        archive.set_file("/arc/a", vec![b'x'; 0x30])?;
+        let archive_2 = round_trip(&archive)?;
+        assert_eq!(archive_2.get_file("/arc/a"), Some(&[b'x'; 0x30][..]));
+        assert_eq!(archive_2.get_file("/arc/b"), Some(&b"5678"[..]));
+
+        // Shrink (to nothing)
+        archive.set_file("/arc/a", Vec::new())?;
+        let archive_2 = round_trip(&archive)?;
+        assert_eq!(archive_2.get_file("/arc/a"), Some(&b""[..]));
+        assert_eq!(archive_2.get_file("/arc/b"), Some(&b"5678"[..]));
+        assert_eq!(archive_2, round_trip(&archive_2)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_file_errors() -> TestResult {
+        let mut archive = U8Archive::new();
+        archive.set_file("/arc/a", b"1".to_vec())?;
+
+        assert_eq!(
+            archive.set_file("/arc/a/b", Vec::new()),
+            Err(FntBuilderError::NotAFolder("arc/a".to_owned()))
+        );
+        assert_eq!(
+            archive.set_file("/arc", Vec::new()),
+            Err(FntBuilderError::AlreadyExists("/arc".to_owned()))
+        );
+        assert_eq!(
+            archive.set_file("/", Vec::new()),
+            Err(FntBuilderError::InvalidPath("/".to_owned()))
+        );
+
+        // (The reader accepts an arc whose root is a file)
+        let mut archive = U8Archive {
+            arc: ParsedArc::new(U8Node::File(U8FileNode { offset: 0, size: 0 })),
+            data: vec![Vec::new()],
+        };
+        assert_eq!(
+            archive.set_file("x", Vec::new()),
+            Err(FntBuilderError::NotAFolder("/".to_owned()))
+        );
+        Ok(())
+    }
+}
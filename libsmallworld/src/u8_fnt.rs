@@ -1,15 +1,16 @@
 //! Functions for reading and writing U8 archive filename tables (FNTs).
 
-use std::collections::HashMap;
 use std::fmt;
-use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::io::{self, Cursor, Read, Seek, SeekFrom, Write};
 
-use binread::{BinReaderExt, NullString};
+use binread::{BinRead, BinReaderExt, NullString};
 use itertools::Itertools;
 use log::{debug, trace, warn};
 use thiserror::Error;
+use unicode_width::UnicodeWidthStr;
 
 use crate::util;
+use crate::{CaseCollision, CaseInsensitiveMap};
 
 /// The "magic" identifier at the beginning of every U8 file. Often
 /// written as `b"U\xaa8-"` (the ASCII characters of which lend "U8"
@@ -24,9 +25,41 @@ pub enum ParseU8Error {
     #[error("file is not a U8 archive (magic: {0:#x})")]
     BadMagic(u32),
 
-    /// A node type other than 0 or 1 was found.
-    #[error("unexpected node type: {0:?}")]
-    UnexpectedNodeType(u8),
+    /// A node type other than 0 or 1 was found. `offset` is the
+    /// absolute offset of the node.
+    #[error("unexpected node type {node_type} at {offset:#x}")]
+    UnexpectedNodeType { node_type: u8, offset: u32 },
+
+    /// The header's data table offset doesn't match the end of the
+    /// FNT (aligned to 0x20). Only returned by
+    /// `check_data_table_offset()`.
+    #[error(
+        "header says the data table starts at {claimed:#x}, but the FNT implies {computed:#x}"
+    )]
+    DataTableOffsetMismatch { claimed: u32, computed: u32 },
+
+    /// The FNT's structure is inconsistent (e.g. its node table is
+    /// larger than the whole file), so reading any further would mean
+    /// treating unrelated data as nodes.
+    #[error("malformed U8 archive: {0}")]
+    Malformed(String),
+
+    /// The file ends before the end of the file data its FNT points to
+    /// (by more than just alignment padding), so it was probably cut
+    /// short.
+    #[error("file is {actual:#x} bytes, but its FNT implies at least {implied:#x} (truncated?)")]
+    Truncated { implied: u64, actual: u64 },
+
+    /// A value couldn't be read from the file (usually because it ends
+    /// too early). `what` describes the value, and `offset` is the
+    /// absolute offset it was being read from.
+    #[error("failed to read {what} at {offset:#x}")]
+    ReadFailed {
+        what: &'static str,
+        offset: u64,
+        #[source]
+        source: io::Error,
+    },
 
     /// All other errors.
     #[error("I/O error")]
@@ -35,12 +68,40 @@ pub enum ParseU8Error {
 
 impl From<binread::Error> for ParseU8Error {
     fn from(error: binread::Error) -> ParseU8Error {
-        ParseU8Error::IoError(io::Error::new(io::ErrorKind::Other, error))
+        ParseU8Error::IoError(binread_error_into_io_error(error))
     }
 }
 
-/// A struct representing a file node in a U8 filename table.
+/// Unwrap a binread I/O error, or wrap any other kind of binread error
+/// in one.
+fn binread_error_into_io_error(error: binread::Error) -> io::Error {
+    match error {
+        binread::Error::Io(error) => error,
+        other => io::Error::other(other),
+    }
+}
+
+/// Read a big-endian value from the current position, returning
+/// `ParseU8Error::ReadFailed` (with `what` and the position) on
+/// failure.
+fn read_be_value<T, SR>(file: &mut SR, what: &'static str) -> Result<T, ParseU8Error>
+where
+    T: BinRead<Args = ()>,
+    SR: Seek + Read,
+{
+    let offset = file.stream_position()?;
+    file.read_be().map_err(|error| ParseU8Error::ReadFailed {
+        what,
+        offset,
+        source: binread_error_into_io_error(error),
+    })
+}
+
+/// A struct representing a file node in a U8 filename table. With the
+/// `serde` feature enabled, this can be serialized, e.g. for storing
+/// file metadata in an external database.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct U8FileNode {
     /// The offset to the start of the file data. For convenience when
     /// writing U8 file data, this is always relative to the start of
@@ -52,8 +113,50 @@ pub struct U8FileNode {
     pub size: u32,
 }
 
-/// A type representing a folder node in a U8 filename table.
-pub type U8FolderNode = HashMap<String, U8Node>;
+/// A type representing a folder node in a U8 filename table. Names are
+/// looked up case-insensitively, like the game does.
+pub type U8FolderNode = CaseInsensitiveMap<U8Node>;
+
+/// Get the key that folder contents are sorted by in U8 archives (and
+/// by `U8Node::iter()`): the name with ASCII letters case-folded, and
+/// everything else (including non-ASCII characters) compared
+/// byte-for-byte. This is plain ASCII case-folding, matching what
+/// Nintendo's loader expects, so tools that build their own arcs can
+/// use it to reproduce the same order.
+///
+/// The key borrows the name rather than allocating a lowercase copy.
+pub fn fnt_sort_key(name: &str) -> impl Ord + '_ {
+    FntSortKey(name)
+}
+
+/// The key returned by `fnt_sort_key()`.
+struct FntSortKey<'a>(&'a str);
+
+impl FntSortKey<'_> {
+    fn folded_bytes(&self) -> impl Iterator<Item = u8> + '_ {
+        self.0.bytes().map(|b| b.to_ascii_lowercase())
+    }
+}
+
+impl Ord for FntSortKey<'_> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.folded_bytes().cmp(other.folded_bytes())
+    }
+}
+
+impl PartialOrd for FntSortKey<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for FntSortKey<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.eq_ignore_ascii_case(other.0)
+    }
+}
+
+impl Eq for FntSortKey<'_> {}
 
 /// An enum representing a U8 filename-table node. It can be either a
 /// file (with offset and size), or a folder containing more `U8Node`s
@@ -92,7 +195,10 @@ impl U8Node {
         match self {
             Self::File(U8FileNode { offset, size }) => {
                 write!(f, "{}{}", " ".repeat(indent), name)?;
-                let mut amount_written = indent + name.len();
+                // (measure the name by its display width rather than
+                // its length in bytes, so that non-ASCII names don't
+                // throw off the alignment)
+                let mut amount_written = indent + name.width();
                 // extra spaces in these format strings to ensure
                 // there'll always be at least one before each number
                 util::write_right_aligned_str(
@@ -162,13 +268,22 @@ impl U8Node {
     }
 
     /// Iterate over a `U8Node::Folder`'s immediate descendants in the
-    /// correct (i.e. case-insensitive alphabetical) order.
+    /// correct (i.e. case-insensitive alphabetical; see
+    /// `fnt_sort_key()`) order. Names that differ only in case are
+    /// ordered by their exact bytes.
     /// For a `File`, just create an empty iterator.
+    ///
+    /// `U8FolderNode` is hash-based, so iterating over it directly
+    /// visits children in an arbitrary order that can change from run
+    /// to run. Anything whose output depends on the visiting order
+    /// (FNT node order, FAT offsets, which error gets reported first,
+    /// and so on) must go through this method or `.iter_mut()`, so
+    /// that the same input always gives byte-identical output.
     #[allow(dead_code)]
     pub fn iter(&self) -> std::vec::IntoIter<(&String, &Self)> {
         match self {
             U8Node::File(_) => Vec::new().into_iter(),
-            U8Node::Folder(children) => children.iter().sorted_by_key(|x| x.0.to_lowercase()),
+            U8Node::Folder(children) => children.iter().sorted_by_key(|x| (fnt_sort_key(x.0), x.0)),
         }
     }
 
@@ -177,7 +292,9 @@ impl U8Node {
     pub fn iter_mut(&mut self) -> std::vec::IntoIter<(&String, &mut Self)> {
         match self {
             U8Node::File(_) => Vec::new().into_iter(),
-            U8Node::Folder(children) => children.iter_mut().sorted_by_key(|x| x.0.to_lowercase()),
+            U8Node::Folder(children) => children
+                .iter_mut()
+                .sorted_by_key(|x| (fnt_sort_key(x.0), x.0)),
         }
     }
 
@@ -185,33 +302,21 @@ impl U8Node {
     /// provided (case-insensitively, the same way Nintendo does it).
     #[allow(dead_code)]
     pub fn child(&self, name: &str) -> Option<&Self> {
-        if let U8Node::Folder(_) = self {
-            let name = name.to_lowercase();
-            for (child_name, child) in self.iter() {
-                if child_name.to_lowercase() == name {
-                    return Some(child);
-                }
-            }
-        }
-        None
+        self.as_folder()?.get(name)
     }
 
     /// Mutable version of `.child()`.
     #[allow(dead_code)]
     pub fn child_mut(&mut self, name: &str) -> Option<&mut Self> {
-        if let U8Node::Folder(_) = self {
-            let name = name.to_lowercase();
-            for (child_name, child) in self.iter_mut() {
-                if child_name.to_lowercase() == name {
-                    return Some(child);
-                }
-            }
-        }
-        None
+        self.as_mut_folder()?.get_mut(name)
     }
 
     /// Get a descendant of a `U8Node::Folder`, following a path string
     /// separated by forward-slashes.
+    ///
+    /// The U8 format itself has no path separator, so a node's name can
+    /// technically contain a forward-slash. Such nodes can't be reached
+    /// by path; use `.child()` (or the `U8FolderNode` directly) instead.
     #[allow(dead_code)]
     pub fn get(&self, path: &str) -> Option<&Self> {
         let mut current = self;
@@ -220,10 +325,7 @@ impl U8Node {
             if component.is_empty() {
                 continue;
             }
-            current = match current.child(component) {
-                Some(child) => child,
-                None => return None,
-            }
+            current = current.child(component)?;
         }
         Some(current)
     }
@@ -236,13 +338,233 @@ impl U8Node {
             if component.is_empty() {
                 continue;
             }
-            current = match current.child_mut(component) {
-                Some(child) => child,
-                None => return None,
-            }
+            current = current.child_mut(component)?;
         }
         Some(current)
     }
+
+    /// Insert a node into a `U8Node::Folder` at a forward-slash-separated
+    /// path, creating any missing folders leading to it.
+    ///
+    /// Fails with `InvalidPath` if the path has no components,
+    /// `NotAFolder` if a component of the path (or this node itself) is
+    /// a file, and `AlreadyExists` if something (case-insensitively)
+    /// already exists at the path itself.
+    pub fn insert_path(&mut self, path: &str, node: U8Node) -> Result<(), FntBuilderError> {
+        let root = self
+            .as_mut_folder()
+            .ok_or_else(|| FntBuilderError::NotAFolder("/".to_owned()))?;
+        let (folder, name) = parent_folder_for_path(root, path)?;
+        if folder.contains_key(name) {
+            return Err(FntBuilderError::AlreadyExists(path.to_owned()));
+        }
+        folder.insert(name.to_owned(), node);
+        Ok(())
+    }
+
+    /// Get how deeply folders are nested below this node: 0 for a file
+    /// or a folder with no subfolders, 1 for a folder whose subfolders
+    /// have no subfolders of their own, and so on. This is on the same
+    /// scale as `MAX_DEPTH`.
+    pub fn depth(&self) -> usize {
+        match self {
+            Self::File(_) => 0,
+            Self::Folder(children) => children
+                .values()
+                .filter(|child| matches!(child, Self::Folder(_)))
+                .map(|child| child.depth() + 1)
+                .max()
+                .unwrap_or(0),
+        }
+    }
+
+    /// Count the file nodes in this node's subtree (including this node
+    /// itself, if it's a file).
+    pub fn count_files(&self) -> usize {
+        match self {
+            Self::File(_) => 1,
+            Self::Folder(children) => children.values().map(Self::count_files).sum(),
+        }
+    }
+
+    /// Count the folder nodes in this node's subtree (including this
+    /// node itself, if it's a folder). For a root node, `count_files()
+    /// + count_folders()` is the total number of nodes in the FNT.
+    pub fn count_folders(&self) -> usize {
+        match self {
+            Self::File(_) => 0,
+            Self::Folder(children) => 1 + children.values().map(Self::count_folders).sum::<usize>(),
+        }
+    }
+
+    /// Get the end offset of the file data that extends furthest into
+    /// the FAT, relative to its start (0 if there are no files).
+    pub fn data_end(&self) -> u64 {
+        match self {
+            Self::File(node) => u64::from(node.offset) + u64::from(node.size),
+            Self::Folder(children) => children.values().map(Self::data_end).max().unwrap_or(0),
+        }
+    }
+
+    /// The deepest folder nesting that `.validate()` will accept. Real
+    /// U8 archives are never anywhere near this deep, so anything past
+    /// it is almost certainly a mistake (and `write()` is recursive, so
+    /// it's best not to push our luck with the stack).
+    pub const MAX_DEPTH: usize = 0x100;
+
+    /// The largest string table a U8 file can have, since node name
+    /// offsets are only 24 bits wide.
+    const MAX_STRING_TABLE_SIZE: usize = 0x1000000;
+
+    /// Calculate how many bytes `write()` would write for this root
+    /// node (header, nodes table, strings table, and padding), without
+    /// actually serializing it.
+    pub fn serialized_fnt_len(&self) -> usize {
+        self.serialized_fnt_len_with_root_name("")
+    }
+
+    /// Version of `serialized_fnt_len()` for a root node with a name.
+    fn serialized_fnt_len_with_root_name(&self, root_name: &str) -> usize {
+        /// Helper function to count nodes and string table bytes
+        fn visit_node(node: &U8Node, name: &str, num_nodes: &mut usize, strings_len: &mut usize) {
+            *num_nodes += 1;
+            *strings_len += name.len() + 1;
+            if let U8Node::Folder(children) = node {
+                for (child_name, child) in children {
+                    visit_node(child, child_name, num_nodes, strings_len);
+                }
+            }
+        }
+
+        let mut num_nodes = 0;
+        let mut strings_len = 0;
+        visit_node(self, root_name, &mut num_nodes, &mut strings_len);
+        (0x20 + 12 * num_nodes + strings_len).next_multiple_of(0x20)
+    }
+
+    /// Check the tree for structural problems that would cause `write()`
+    /// to produce a broken (or subtly wrong) U8 file, and return all of
+    /// them. An empty `Vec` means the tree is OK to serialize.
+    ///
+    /// The root has to be a folder, since readers take its size field
+    /// to be the total node count: a root file (typically with size 0)
+    /// would make the arc look empty or truncated. Below the root,
+    /// whether a node is a file or a folder is explicit in the tree, so
+    /// there's nothing else to check there.
+    ///
+    /// Child ordering isn't checked either, since a `U8FolderNode` has
+    /// no order of its own: `write()` decides the order (see
+    /// `FntOrder`) as it goes.
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+        // (+1 for the root node's empty name)
+        let mut string_table_size = 1;
+
+        if let Self::File(U8FileNode { size, .. }) = self {
+            issues.push(ValidationIssue {
+                path: "/".to_owned(),
+                message: format!("root node is a file (size {size:#x}), but needs to be a folder"),
+            });
+        }
+
+        self.validate_recursive("/", 0, &mut issues, &mut string_table_size);
+
+        if string_table_size > Self::MAX_STRING_TABLE_SIZE {
+            issues.push(ValidationIssue {
+                path: "/".to_owned(),
+                message: format!(
+                    "string table would be {string_table_size:#x} bytes long (max {:#x})",
+                    Self::MAX_STRING_TABLE_SIZE
+                ),
+            });
+        }
+
+        issues
+    }
+
+    /// Recursive helper for `.validate()`.
+    fn validate_recursive(
+        &self,
+        path: &str,
+        depth: usize,
+        issues: &mut Vec<ValidationIssue>,
+        string_table_size: &mut usize,
+    ) {
+        match self {
+            Self::File(U8FileNode { offset, size }) => {
+                if offset.checked_add(*size).is_none() {
+                    issues.push(ValidationIssue {
+                        path: path.to_owned(),
+                        message: format!("data range {offset:#x}+{size:#x} overflows 32 bits"),
+                    });
+                }
+            }
+            Self::Folder(_) => {
+                if depth > Self::MAX_DEPTH {
+                    issues.push(ValidationIssue {
+                        path: path.to_owned(),
+                        message: format!("nested more than {} folders deep", Self::MAX_DEPTH),
+                    });
+                    return;
+                }
+
+                // Names are compared case-insensitively when looking
+                // files up, so two names differing only by case would
+                // make one of them unreachable
+                for ((name_a, _), (name_b, _)) in self.iter().tuple_windows() {
+                    if name_a.eq_ignore_ascii_case(name_b) {
+                        issues.push(ValidationIssue {
+                            path: path.to_owned(),
+                            message: format!(
+                                "{name_a:?} and {name_b:?} have the same name (case-insensitively)"
+                            ),
+                        });
+                    }
+                }
+
+                for (name, child) in self.iter() {
+                    let child_path = if path == "/" {
+                        format!("/{name}")
+                    } else {
+                        format!("{path}/{name}")
+                    };
+
+                    *string_table_size += name.len() + 1;
+
+                    if name.is_empty() {
+                        issues.push(ValidationIssue {
+                            path: child_path.clone(),
+                            message: "name is empty".to_owned(),
+                        });
+                    }
+                    if name.contains('\0') {
+                        issues.push(ValidationIssue {
+                            path: child_path.clone(),
+                            message: "name contains a null character".to_owned(),
+                        });
+                    }
+
+                    child.validate_recursive(&child_path, depth + 1, issues, string_table_size);
+                }
+            }
+        }
+    }
+}
+
+/// A structural problem found by `U8Node::validate()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidationIssue {
+    /// Forward-slash-separated path to the node with the problem.
+    pub path: String,
+
+    /// Description of the problem.
+    pub message: String,
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
 }
 
 impl fmt::Display for U8Node {
@@ -258,57 +580,514 @@ impl fmt::Display for U8Node {
         util::write_right_aligned_str(
             f,
             &mut amount_written,
-            Self::DISPLAY_SIZE_RIGHT_EDGE + 1,
-            "SIZE\n",
+            Self::DISPLAY_SIZE_RIGHT_EDGE,
+            "SIZE",
         )?;
+        writeln!(f)?;
         self.fmt_with_indent(f, "", 0)
     }
 }
 
+/// Builds a folder node. As with `U8FolderNode::insert()`, a later entry
+/// only replaces an earlier one with exactly the same name; names that
+/// only differ in case are both kept (see `U8Node::validate()`).
+impl FromIterator<(String, U8Node)> for U8Node {
+    fn from_iter<I: IntoIterator<Item = (String, U8Node)>>(iter: I) -> Self {
+        U8Node::Folder(iter.into_iter().collect())
+    }
+}
+
+/// Builds a folder node, like `U8Node::from_iter()`.
+impl<const N: usize> From<[(String, U8Node); N]> for U8Node {
+    fn from(entries: [(String, U8Node); N]) -> Self {
+        entries.into_iter().collect()
+    }
+}
+
+/// All errors that can be encountered when building a FNT with
+/// `FntBuilder`.
+#[non_exhaustive]
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum FntBuilderError {
+    /// The path has no components.
+    #[error("invalid path {0:?}")]
+    InvalidPath(String),
+
+    /// A node with the same name (case-insensitively) already exists.
+    #[error("{0:?} already exists")]
+    AlreadyExists(String),
+
+    /// A path component that needs to be a folder is already a file.
+    #[error("{0:?} is a file, not a folder")]
+    NotAFolder(String),
+
+    /// The total size of the file data would overflow 32 bits.
+    #[error("file data would exceed 4 GiB")]
+    FatTooLarge,
+}
+
+/// Find the folder that a node at a forward-slash-separated path
+/// (relative to `root`) belongs in, creating any missing folders
+/// leading to it, and return it along with the node's name.
+///
+/// Fails with `InvalidPath` if the path has no components, or
+/// `NotAFolder` if a component of the path (other than the last) is a
+/// file.
+pub(crate) fn parent_folder_for_path<'a, 'p>(
+    root: &'a mut U8FolderNode,
+    path: &'p str,
+) -> Result<(&'a mut U8FolderNode, &'p str), FntBuilderError> {
+    let components: Vec<&str> = path.split('/').filter(|c| !c.is_empty()).collect();
+    let Some((&name, folder_names)) = components.split_last() else {
+        return Err(FntBuilderError::InvalidPath(path.to_owned()));
+    };
+
+    let mut folder = root;
+    for (i, folder_name) in folder_names.iter().enumerate() {
+        if !folder.contains_key(folder_name) {
+            folder.insert(
+                (*folder_name).to_owned(),
+                U8Node::Folder(U8FolderNode::new()),
+            );
+        }
+        folder = folder
+            .get_mut(folder_name)
+            .unwrap()
+            .as_mut_folder()
+            .ok_or_else(|| FntBuilderError::NotAFolder(components[..=i].join("/")))?;
+    }
+
+    Ok((folder, name))
+}
+
+/// A push-based alternative to constructing nested `U8FolderNode`s by
+/// hand. Each file is given the next available offset in the FAT
+/// (aligned to 0x20), so that file data can be written in the same
+/// order the files were added.
+#[derive(Clone, Debug, Default)]
+pub struct FntBuilder {
+    root: U8FolderNode,
+    fat_size: u32,
+}
+
+impl FntBuilder {
+    /// Create a builder for an empty FNT.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a file at a forward-slash-separated path, creating any
+    /// folders along the way that don't exist yet. Returns the offset
+    /// assigned to the file's data, relative to the start of the FAT.
+    ///
+    /// Like `U8Node::get()`, this can't create nodes whose names contain
+    /// forward-slashes.
+    pub fn add_file(&mut self, path: &str, size: u32) -> Result<u32, FntBuilderError> {
+        let (folder, filename) = parent_folder_for_path(&mut self.root, path)?;
+        if folder.contains_key(filename) {
+            return Err(FntBuilderError::AlreadyExists(path.to_owned()));
+        }
+
+        let offset = self
+            .fat_size
+            .checked_add(0x1f)
+            .ok_or(FntBuilderError::FatTooLarge)?
+            & !0x1f;
+        self.fat_size = offset
+            .checked_add(size)
+            .ok_or(FntBuilderError::FatTooLarge)?;
+
+        folder.insert(
+            filename.to_owned(),
+            U8Node::File(U8FileNode { offset, size }),
+        );
+        Ok(offset)
+    }
+
+    /// The total size of the FAT for the files added so far.
+    pub fn fat_size(&self) -> u32 {
+        self.fat_size
+    }
+
+    /// Finish building, and return the FNT's root node.
+    pub fn finish(self) -> U8Node {
+        U8Node::Folder(self.root)
+    }
+}
+
+/// A U8 file's FNT, along with the header values that can't be
+/// derived from it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParsedArc {
+    /// The root node.
+    pub root: U8Node,
+
+    /// The offset to the start of the data table (which all of the
+    /// "offset" values in the FNT are relative to). Ignored when
+    /// writing, since it's recalculated then.
+    pub data_table_offs: u32,
+
+    /// The 16 reserved bytes at the end of the header. These are
+    /// normally all zero, but some arcs stash data there.
+    pub reserved: [u8; 16],
+
+    /// The root node's name. This is normally empty, but a few tools
+    /// give it one.
+    pub root_name: String,
+
+    /// The header exactly as it was read, including the values that
+    /// are normally derived from the FNT (so that tools can check them,
+    /// or reproduce them exactly). Ignored when writing, since those
+    /// values are recalculated then. All zeros if the arc wasn't read
+    /// from a file.
+    pub header: U8Header,
+}
+
+impl ParsedArc {
+    /// Wrap a root node for writing, with all-zero reserved bytes.
+    pub fn new(root: U8Node) -> Self {
+        Self {
+            root,
+            data_table_offs: 0,
+            reserved: [0; 16],
+            root_name: String::new(),
+            header: U8Header::default(),
+        }
+    }
+
+    /// Build a `ParsedArc` from a root node and the data table offset
+    /// its file offsets are relative to, with all-zero reserved bytes
+    /// and an empty root name.
+    pub fn from_parts(root: U8Node, data_table_offs: u32) -> Self {
+        Self {
+            root,
+            data_table_offs,
+            ..Self::new(U8Node::Folder(U8FolderNode::new()))
+        }
+    }
+
+    /// Calculate how many bytes `write_parsed_arc()` would write for
+    /// this arc, without actually serializing it.
+    pub fn serialized_fnt_len(&self) -> usize {
+        self.root.serialized_fnt_len_with_root_name(&self.root_name)
+    }
+
+    /// Split into the root node and data table offset, discarding the
+    /// reserved bytes and root name. This is the same pair `read()`
+    /// returns.
+    pub fn into_parts(self) -> (U8Node, u32) {
+        (self.root, self.data_table_offs)
+    }
+
+    /// The smallest size the file this arc came from can have, going by
+    /// the data table offset and the file data that extends furthest
+    /// past it.
+    pub fn implied_size(&self) -> u64 {
+        u64::from(self.data_table_offs) + self.root.data_end()
+    }
+}
+
 /// Read a U8 file's FNT.
 ///
 /// Returns the new root node, and the offset to the start of the data
 /// table (which all of the "offset" values in the FNT will be relative
-/// to).
+/// to). Use `read_parsed_arc()` to also get the other header values.
 ///
 /// Assuming an `Ok` return value, the file will be left seeked to the
 /// start of the FAT.
 pub fn read<SR: Seek + Read>(file: &mut SR) -> Result<(U8Node, u32), ParseU8Error> {
+    Ok(read_parsed_arc(file)?.into_parts())
+}
+
+/// Same as `read()`, but for readers that can't seek (e.g. network
+/// streams, or the output of a decompressor). The whole stream is read
+/// into memory first, and returned alongside the FNT as a `Cursor`, so
+/// that the file data can still be read afterwards (and passed to
+/// functions that need `Seek + Read`).
+pub fn read_from_reader<R: Read>(
+    mut reader: R,
+) -> Result<(U8Node, u32, Cursor<Vec<u8>>), ParseU8Error> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    let mut cursor = Cursor::new(data);
+    let (root, data_table_offs) = read(&mut cursor)?;
+    Ok((root, data_table_offs, cursor))
+}
+
+/// Same as `read_from_reader()`, but returns a `ParsedArc` (so that the
+/// other header values are available too) and the raw bytes of the
+/// whole stream, which are also buffered in full.
+pub fn read_parsed_arc_from_reader<R: Read>(
+    mut reader: R,
+) -> Result<(ParsedArc, Vec<u8>), ParseU8Error> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    let arc = read_parsed_arc(&mut Cursor::new(&data))?;
+    Ok((arc, data))
+}
+
+/// Read a U8 file's FNT from a reader that can't seek, given the
+/// length of the whole file up front.
+///
+/// Unlike `read_from_reader()`, only the header and FNT (everything
+/// before the data table) are read into memory, and the reader is left
+/// at the start of the data table, so the file data can be streamed
+/// from there. Offsets are checked against `total_len` before anything
+/// past the header is read: a data table offset past the end is
+/// `Malformed`, and files whose data would end past it are `Truncated`
+/// (as with `read()`).
+///
+/// Only the FNT is read this way. Reading file data out of order, and
+/// converting the arc, still need a real `Seek + Read`.
+pub fn read_with_length<R: Read>(
+    reader: &mut R,
+    total_len: u64,
+) -> Result<(U8Node, u32), ParseU8Error> {
+    // The data table offset is the last field that's needed to know how
+    // much of the file the FNT takes up
+    let mut prefix = vec![0; 0x10];
+    reader
+        .read_exact(&mut prefix)
+        .map_err(|source| ParseU8Error::ReadFailed {
+            what: "header",
+            offset: 0,
+            source,
+        })?;
+    let magic = u32::from_be_bytes(prefix[0..4].try_into().unwrap());
+    if magic != U8_MAGIC {
+        return Err(ParseU8Error::BadMagic(magic));
+    }
+    let data_table_offs = u32::from_be_bytes(prefix[12..16].try_into().unwrap());
+    if u64::from(data_table_offs) > total_len {
+        return Err(ParseU8Error::Malformed(format!(
+            "data table offset ({data_table_offs:#x}) is past the end of the file \
+             ({total_len:#x})"
+        )));
+    }
+
+    reader
+        .take(u64::from(data_table_offs).saturating_sub(0x10))
+        .read_to_end(&mut prefix)?;
+    if prefix.len() < data_table_offs as usize {
+        return Err(ParseU8Error::ReadFailed {
+            what: "FNT",
+            offset: prefix.len() as u64,
+            source: io::ErrorKind::UnexpectedEof.into(),
+        });
+    }
+    let (arc, _) = read_impl(
+        &mut Cursor::new(prefix),
+        &ReadOptions::default(),
+        Some(total_len),
+    )?;
+    Ok(arc.into_parts())
+}
+
+/// Options for `read_parsed_arc_with_options()`. The defaults match
+/// the behavior of `read_parsed_arc()`.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct ReadOptions {
+    /// Also accept files whose magic only matches `U8_MAGIC` in its
+    /// first three bytes, which some tools get wrong. A warning with
+    /// the actual magic is logged when this happens.
+    pub lenient_magic: bool,
+
+    /// Stop reading each name at the data table offset if no null
+    /// terminator is found before it, instead of reading on into the
+    /// file data. Some tools leave the terminator off of the last name.
+    /// A warning is logged for each name this happens to.
+    pub lenient_strings: bool,
+
+    /// Accept files that end before their last file's data does,
+    /// logging a warning instead of returning `Truncated`. Arcs that
+    /// are only missing the last file's alignment padding (less than
+    /// 0x20 bytes) are always accepted this way.
+    pub allow_truncated: bool,
+}
+
+/// The raw values from a U8 file's header, plus the node count (from
+/// the root node). See `read_header()`.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct U8Header {
+    /// The magic number, which should be `U8_MAGIC`.
+    pub magic: u32,
+    /// Offset to the root node (normally 0x20).
+    pub root_node_offs: u32,
+    /// Total size of the node and string tables. Added to
+    /// `root_node_offs`, this is where the header ends.
+    pub header_size: u32,
+    /// Offset to the start of the data table.
+    pub data_table_offs: u32,
+    /// Total number of nodes, including the root node (from the root
+    /// node's "size" field).
+    pub node_count: u32,
+    /// The reserved bytes at the end of the header, which are normally
+    /// all zero.
+    pub reserved: [u8; 16],
+}
+
+impl U8Header {
+    /// The offset where the header (including the node and string
+    /// tables) ends, according to its `header_size` field.
+    pub fn end_of_header(&self) -> u64 {
+        u64::from(self.root_node_offs) + u64::from(self.header_size)
+    }
+}
+
+/// Read just the header of a U8 file, and the node count from its root
+/// node, without walking the rest of the FNT. No validation is done,
+/// not even of the magic, so this also works on files that `read()`
+/// rejects.
+pub fn read_header<SR: Seek + Read>(file: &mut SR) -> Result<U8Header, ParseU8Error> {
+    file.seek(SeekFrom::Start(0))?;
+    let magic: u32 = read_be_value(file, "magic")?;
+    let root_node_offs: u32 = read_be_value(file, "root node offset")?;
+    let header_size: u32 = read_be_value(file, "header size")?;
+    let data_table_offs: u32 = read_be_value(file, "data table offset")?;
+    let reserved: [u8; 16] = read_be_value(file, "reserved header bytes")?;
+
+    file.seek(SeekFrom::Start(u64::from(root_node_offs) + 8))?;
+    let node_count: u32 = read_be_value(file, "node count")?;
+
+    Ok(U8Header {
+        magic,
+        root_node_offs,
+        header_size,
+        data_table_offs,
+        node_count,
+        reserved,
+    })
+}
+
+/// Read a U8 file's FNT and the header values that go with it.
+///
+/// Assuming an `Ok` return value, the file will be left seeked to the
+/// start of the FAT.
+pub fn read_parsed_arc<SR: Seek + Read>(file: &mut SR) -> Result<ParsedArc, ParseU8Error> {
+    read_parsed_arc_with_options(file, &ReadOptions::default())
+}
+
+/// Same as `read_parsed_arc()`, but with additional options.
+pub fn read_parsed_arc_with_options<SR: Seek + Read>(
+    file: &mut SR,
+    options: &ReadOptions,
+) -> Result<ParsedArc, ParseU8Error> {
+    let (arc, computed_data_table_offs) = read_impl(file, options, None)?;
+    if arc.data_table_offs != computed_data_table_offs {
+        warn!(
+            "Header says the data table starts at {:#x}, but the FNT implies {computed_data_table_offs:#x}",
+            arc.data_table_offs
+        );
+    }
+    Ok(arc)
+}
+
+/// Read a U8 file's FNT, and check that the data table offset in its
+/// header matches where the node and string tables actually end
+/// (aligned to 0x20). Some editors forget to update the header after
+/// changing names, which leaves the two out of sync.
+///
+/// Returns `ParseU8Error::DataTableOffsetMismatch` if they disagree.
+pub fn check_data_table_offset<SR: Seek + Read>(file: &mut SR) -> Result<(), ParseU8Error> {
+    let (arc, computed) = read_impl(file, &ReadOptions::default(), None)?;
+    if arc.data_table_offs == computed {
+        Ok(())
+    } else {
+        Err(ParseU8Error::DataTableOffsetMismatch {
+            claimed: arc.data_table_offs,
+            computed,
+        })
+    }
+}
+
+/// Shared implementation of `read_parsed_arc_with_options()`,
+/// `check_data_table_offset()` and `read_with_length()`. Also returns
+/// the data table offset implied by the end of the string table.
+///
+/// `file_len` is the length of the whole file, if `file` only contains
+/// the start of it; otherwise, it's found by seeking to the end.
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+fn read_impl<SR: Seek + Read>(
+    file: &mut SR,
+    options: &ReadOptions,
+    file_len: Option<u64>,
+) -> Result<(ParsedArc, u32), ParseU8Error> {
     debug!("Reading U8 FNT");
 
-    // Check magic, just to be sure the file looks sane
+    // Check magic first, just to be sure the file looks sane (and so
+    // that files too short to have a full header are still reported as
+    // having the wrong magic)
     file.seek(SeekFrom::Start(0))?;
-    let magic: u32 = file.read_be()?;
+    let magic: u32 = read_be_value(file, "magic")?;
     if magic != U8_MAGIC {
-        return Err(ParseU8Error::BadMagic(magic));
+        if options.lenient_magic && magic >> 8 == U8_MAGIC >> 8 {
+            warn!("Nonstandard U8 magic: {magic:#010x} (expected {U8_MAGIC:#010x})");
+        } else {
+            return Err(ParseU8Error::BadMagic(magic));
+        }
     }
 
-    // Read other header stuff
-    file.seek(SeekFrom::Start(4))?;
-    let root_node_offs: u32 = file.read_be()?;
+    let header = read_header(file)?;
+    let U8Header {
+        root_node_offs,
+        data_table_offs,
+        reserved,
+        node_count: root_node_size,
+        ..
+    } = header;
+
     if root_node_offs != 0x20 {
         warn!("Unusual root node offset: {root_node_offs:#x}");
     }
     trace!("root_node_offs={root_node_offs:#x}");
-    file.seek(SeekFrom::Current(4))?;
-    let data_table_offs: u32 = file.read_be()?;
     trace!("data_table_offs={data_table_offs:#x}");
+    if reserved != [0; 16] {
+        debug!("Reserved header bytes aren't empty: {reserved:02x?}");
+    }
 
     // "Size" field of the root node tells us the total number of nodes;
     // this is how we calculate the offset of the string table
-    file.seek(SeekFrom::Start((root_node_offs + 8).into()))?;
-    let root_node_size: u32 = file.read_be()?;
     trace!("root_node_size={root_node_size:#x}");
-    let string_table_offs = root_node_offs + 12 * root_node_size;
+    if root_node_size == 0 {
+        return Err(ParseU8Error::Malformed(
+            "root node says there are 0 nodes".to_owned(),
+        ));
+    }
+    // (Checked against the file length up front, since nothing else
+    // stops a bogus node count from sending us far past the real node
+    // table)
+    let file_len = match file_len {
+        Some(len) => len,
+        None => file.seek(SeekFrom::End(0))?,
+    };
+    let string_table_offs = u64::from(root_node_offs) + 12 * u64::from(root_node_size);
+    let string_table_offs = match u32::try_from(string_table_offs) {
+        Ok(offs) if u64::from(offs) <= file_len => offs,
+        _ => {
+            return Err(ParseU8Error::Malformed(format!(
+                "node table ({root_node_size} nodes at {root_node_offs:#x}) extends past the \
+                 end of the file ({file_len:#x})"
+            )))
+        }
+    };
     trace!("string_table_offs={string_table_offs:#x}");
 
     // Inner function for recursion
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", skip_all, fields(idx = *idx))
+    )]
+    #[allow(clippy::too_many_arguments)]
     fn visit_node<SR: Seek + Read>(
         idx: &mut u32,
+        depth: usize,
         file: &mut SR,
         root_node_offs: u32,
+        node_count: u32,
         string_table_offs: u32,
         data_table_offs: u32,
+        string_table_end: &mut u64,
+        lenient_strings: bool,
     ) -> Result<(String, U8Node), ParseU8Error> {
         let my_node_idx = *idx;
         let node_offs = root_node_offs + 12 * my_node_idx;
@@ -316,65 +1095,150 @@ pub fn read<SR: Seek + Read>(file: &mut SR) -> Result<(U8Node, u32), ParseU8Erro
 
         // Read node header stuff
         file.seek(SeekFrom::Start(node_offs.into()))?;
-        let first_u32: u32 = file.read_be()?;
+        let first_u32: u32 = read_be_value(file, "node type and name offset")?;
         let node_type: u8 = (first_u32 >> 24).try_into().unwrap();
         let name_offs = first_u32 & 0x00ffffff;
-        let data_offs: u32 = file.read_be()?;
-        let size: u32 = file.read_be()?;
+        let data_offs: u32 = read_be_value(file, "node data offset")?;
+        let size: u32 = read_be_value(file, "node size")?;
         trace!("Node {idx} header: ({node_type}, {name_offs:#x}, {data_offs:#x}, {size:#x})");
 
         // Read node name string
-        file.seek(SeekFrom::Start((string_table_offs + name_offs).into()))?;
-        let name = file.read_be::<NullString>()?.to_string();
+        file.seek(SeekFrom::Start(
+            u64::from(string_table_offs) + u64::from(name_offs),
+        ))?;
+        let name_bytes = if lenient_strings {
+            read_name_up_to(file, data_table_offs.into())?
+        } else {
+            read_be_value::<NullString, _>(file, "node name")?.into()
+        };
+        let name = match String::from_utf8(name_bytes) {
+            Ok(name) => name,
+            Err(err) => {
+                // Probably Shift-JIS or similar. Keep going with a
+                // lossy version of the name, rather than giving up
+                let name = String::from_utf8_lossy(err.as_bytes()).into_owned();
+                warn!("Node {idx} name isn't valid UTF-8: {name:?}");
+                name
+            }
+        };
         trace!("Node {idx} name: {name:?}");
+        *string_table_end = (*string_table_end).max(file.stream_position()?);
 
         match node_type {
             U8Node::FILE_TYPE => {
+                let Some(offset) = data_offs.checked_sub(data_table_offs) else {
+                    return Err(ParseU8Error::Malformed(format!(
+                        "file node {my_node_idx}'s data ({data_offs:#x}) starts before the data \
+                         table ({data_table_offs:#x})"
+                    )));
+                };
                 *idx += 1;
-                Ok((
-                    name,
-                    U8Node::File(U8FileNode {
-                        offset: data_offs - data_table_offs,
-                        size,
-                    }),
-                ))
+                Ok((name, U8Node::File(U8FileNode { offset, size })))
             }
             U8Node::FOLDER_TYPE => {
                 let mut folder: U8FolderNode = U8FolderNode::new();
 
+                // (This is read recursively, so a crafted file with
+                // very deeply nested folders could overflow the stack)
+                if depth > U8Node::MAX_DEPTH {
+                    return Err(ParseU8Error::Malformed(format!(
+                        "folders are nested more than {} deep",
+                        U8Node::MAX_DEPTH
+                    )));
+                }
+                if size > node_count {
+                    return Err(ParseU8Error::Malformed(format!(
+                        "folder node {my_node_idx} ends at node {size}, but there are only \
+                         {node_count} nodes"
+                    )));
+                }
+
                 trace!("Visiting children of node {my_node_idx}");
                 *idx += 1;
                 while *idx < size {
-                    match visit_node(
+                    let (child_name, child) = visit_node(
                         idx,
+                        depth + 1,
                         file,
                         root_node_offs,
+                        node_count,
                         string_table_offs,
                         data_table_offs,
-                    ) {
-                        Ok(res_tuple) => folder.insert(res_tuple.0, res_tuple.1),
-                        Err(why) => return Err(why),
-                    };
+                        string_table_end,
+                        lenient_strings,
+                    )?;
+                    // Both are kept (so no data is lost), and
+                    // `U8Node::validate()` reports them
+                    if let Err(CaseCollision {
+                        existing,
+                        name,
+                        value,
+                    }) = folder.try_insert(child_name, child)
+                    {
+                        warn!(
+                            "Folder node {my_node_idx} has both {existing:?} and {name:?}, \
+                             which are the same name to the game"
+                        );
+                        folder.insert(name, value);
+                    }
                 }
                 trace!("Returning to parent dir (node {my_node_idx})");
                 Ok((name, U8Node::Folder(folder)))
             }
-            _ => Err(ParseU8Error::UnexpectedNodeType(node_type)),
+            _ => Err(ParseU8Error::UnexpectedNodeType {
+                node_type,
+                offset: node_offs,
+            }),
         }
     }
 
     // Read starting at the root node, of course
-    let res = (
-        visit_node(
-            &mut 0,
-            file,
-            root_node_offs,
-            string_table_offs,
-            data_table_offs,
-        )?
-        .1,
+    let mut string_table_end = u64::from(string_table_offs);
+    let (root_name, root) = visit_node(
+        &mut 0,
+        0,
+        file,
+        root_node_offs,
+        root_node_size,
+        string_table_offs,
         data_table_offs,
-    );
+        &mut string_table_end,
+        options.lenient_strings,
+    )?;
+    if !root_name.is_empty() {
+        debug!("Root node has a name: {root_name:?}");
+    }
+    let res = ParsedArc {
+        root,
+        data_table_offs,
+        reserved,
+        root_name,
+        header,
+    };
+    // Padding after the last file is normal, and so is missing padding
+    // (some tools count it in the file's size), but anything beyond
+    // that means the file isn't the size its FNT says it should be
+    let implied_size = res.implied_size();
+    trace!("implied_size={implied_size:#x}");
+    if file_len < implied_size {
+        let shortfall = implied_size - file_len;
+        if !options.allow_truncated && shortfall >= util::EOF_LENIENCY_LIMIT as u64 {
+            return Err(ParseU8Error::Truncated {
+                implied: implied_size,
+                actual: file_len,
+            });
+        }
+        warn!("File is {shortfall:#x} bytes shorter than its FNT implies ({implied_size:#x})");
+    } else if file_len > (implied_size + 0x1f) & !0x1f {
+        warn!(
+            "File has {:#x} bytes of trailing data after the end of its last file ({implied_size:#x})",
+            file_len - implied_size
+        );
+    }
+
+    let computed_data_table_offs =
+        u32::try_from((string_table_end + 0x1f) & !0x1f).unwrap_or(u32::MAX);
+    trace!("computed_data_table_offs={computed_data_table_offs:#x}");
 
     // Leave the file seeked to the start of the FAT. Just makes
     // sense, since we've essentially read all of the file data up until
@@ -382,22 +1246,163 @@ pub fn read<SR: Seek + Read>(file: &mut SR) -> Result<(U8Node, u32), ParseU8Erro
     file.seek(SeekFrom::Start(data_table_offs.into()))?;
 
     trace!("Done reading U8 FNT");
-    Ok(res)
+    Ok((res, computed_data_table_offs))
+}
+
+/// Read a null-terminated name, stopping early (with a warning) if
+/// `limit` or the end of the file is reached first. The reader is left
+/// just past the terminator, or at wherever reading stopped.
+fn read_name_up_to<SR: Seek + Read>(file: &mut SR, limit: u64) -> Result<Vec<u8>, io::Error> {
+    let start = file.stream_position()?;
+    let mut name = Vec::new();
+    let mut byte = [0];
+    loop {
+        if start + name.len() as u64 >= limit || file.read(&mut byte)? == 0 {
+            warn!("Name at {start:#x} isn't null-terminated; truncating it to {name:?}");
+            return Ok(name);
+        }
+        if byte[0] == 0 {
+            return Ok(name);
+        }
+        name.push(byte[0]);
+    }
+}
+
+/// The order in which to write each folder's children. See
+/// `write_parsed_arc_with_order()`.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub enum FntOrder {
+    /// Case-insensitive alphabetical order, the same as `U8Node::iter()`
+    /// and Nintendo's own arcs. Use this for anything the game will
+    /// load.
+    #[default]
+    Nintendo,
+
+    /// The order each folder's children were added to it in (see
+    /// `CaseInsensitiveMap::iter_insertion_order()`). A tree read from a
+    /// U8 file keeps the order it was stored in, so this writes files
+    /// added by a conversion after the original ones.
+    Insertion,
+
+    /// Whatever order the folders happen to store their children in.
+    /// This skips sorting, but the order is arbitrary and can change
+    /// between runs, so it's only suitable for throwaway output.
+    Unsorted,
+}
+
+/// Options for `write_parsed_arc_with_options()`. The defaults match
+/// the behavior of `write_parsed_arc()`.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct WriteOptions {
+    /// The order to write each folder's children in.
+    pub order: FntOrder,
+
+    /// If set, pad the end of the FNT so that the data table starts at
+    /// this offset (relative to the initial file position), instead of
+    /// at the next multiple of 0x20. Ignored if the FNT doesn't fit
+    /// before it; compare against `ParsedArc::serialized_fnt_len()`
+    /// first to check.
+    pub data_table_offs: Option<u32>,
+
+    /// If set, the length of the data table that will be written after
+    /// the FNT. Every file node's data (`offset + size`) is checked to
+    /// fit within it, and an `InvalidInput` error is returned (before
+    /// anything is written) if one doesn't.
+    pub fat_len: Option<u32>,
 }
 
 /// Write a FNT to a U8 file.
 ///
 /// Assuming an `Ok` return value, the file will be left seeked to the
-/// start of the FAT.
-pub fn write<SW: Seek + Write>(file: &mut SW, root: &U8Node) -> Result<(), io::Error> {
+/// start of the FAT, and the offset of the FAT relative to the initial
+/// file position (i.e. the length of the FNT, including padding) will
+/// be returned.
+pub fn write<SW: Seek + Write>(file: &mut SW, root: &U8Node) -> Result<u32, io::Error> {
+    write_impl(file, root, "", &[0; 16], &WriteOptions::default())
+}
+
+/// Version of `write()` that also writes the reserved header bytes and
+/// root name from a `ParsedArc`.
+pub fn write_parsed_arc<SW: Seek + Write>(
+    file: &mut SW,
+    arc: &ParsedArc,
+) -> Result<u32, io::Error> {
+    write_parsed_arc_with_order(file, arc, FntOrder::Nintendo)
+}
+
+/// Same as `write_parsed_arc()`, but with control over the order each
+/// folder's children are written in.
+pub fn write_parsed_arc_with_order<SW: Seek + Write>(
+    file: &mut SW,
+    arc: &ParsedArc,
+    order: FntOrder,
+) -> Result<u32, io::Error> {
+    write_parsed_arc_with_options(
+        file,
+        arc,
+        &WriteOptions {
+            order,
+            ..Default::default()
+        },
+    )
+}
+
+/// Same as `write_parsed_arc()`, but with additional options.
+pub fn write_parsed_arc_with_options<SW: Seek + Write>(
+    file: &mut SW,
+    arc: &ParsedArc,
+    options: &WriteOptions,
+) -> Result<u32, io::Error> {
+    write_impl(file, &arc.root, &arc.root_name, &arc.reserved, options)
+}
+
+/// Check that every file node's data lies within a data table of the
+/// given length, for `WriteOptions::fat_len`.
+fn check_file_data_bounds(node: &U8Node, path: &str, fat_len: u32) -> Result<(), io::Error> {
+    match node {
+        U8Node::File(U8FileNode { offset, size }) => {
+            if u64::from(*offset) + u64::from(*size) > fat_len.into() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "data for {path:?} ({offset:#x}, size {size:#x}) extends past the end of \
+                         the data table ({fat_len:#x})"
+                    ),
+                ));
+            }
+        }
+        U8Node::Folder(_) => {
+            for (name, child) in node.iter() {
+                check_file_data_bounds(child, &format!("{path}/{name}"), fat_len)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Shared implementation of `write()` and
+/// `write_parsed_arc_with_options()`.
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+fn write_impl<SW: Seek + Write>(
+    file: &mut SW,
+    root: &U8Node,
+    root_name: &str,
+    reserved: &[u8; 16],
+    options: &WriteOptions,
+) -> Result<u32, io::Error> {
     debug!("Writing U8 FNT");
 
+    if let Some(fat_len) = options.fat_len {
+        check_file_data_bounds(root, "", fat_len)?;
+    }
+
     let initial_file_offset = file.stream_position()?;
     trace!("initial_file_offset={initial_file_offset:#x}");
 
     // Write the constant parts of the header, leaving values we don't
     // yet know empty
-    file.write_all(b"U\xaa8-\0\0\0\x20\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0")?;
+    file.write_all(b"U\xaa8-\0\0\0\x20\0\0\0\0\0\0\0\0")?;
+    file.write_all(reserved)?;
 
     // We're not sure yet where the strings table will go (depends on
     // the length of the nodes table), so build it separately for now.
@@ -411,14 +1416,20 @@ pub fn write<SW: Seek + Write>(file: &mut SW, root: &U8Node) -> Result<(), io::E
     let mut file_data_offsets_to_write: Vec<(u64, u32)> = Vec::new();
 
     // Inner function for recursion
+    #[allow(clippy::too_many_arguments)]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", skip_all, fields(idx = *idx, name))
+    )]
     fn visit_node<SW: Seek + Write>(
         node: &U8Node,
         name: &str,
         idx: &mut u32,
-        recursion_depth: i32,
+        parent_idx: u32,
         file: &mut SW,
         strings_table: &mut Vec<u8>,
         file_data_offsets_to_write: &mut Vec<(u64, u32)>,
+        order: FntOrder,
     ) -> Result<(), io::Error> {
         trace!("Visiting node {idx}: {name:?}");
 
@@ -452,22 +1463,31 @@ pub fn write<SW: Seek + Write>(file: &mut SW, root: &U8Node) -> Result<(), io::E
                 file_data_offsets_to_write.push((my_node_offs + 4, *offset));
                 (0_u32, *size)
             }
-            U8Node::Folder(_) => {
+            U8Node::Folder(children) => {
                 trace!("Visiting children of node {my_node_idx}");
-                for (child_name, child) in node.iter() {
+                let children = match order {
+                    FntOrder::Nintendo => node.iter(),
+                    FntOrder::Insertion => children.iter_insertion_order(),
+                    FntOrder::Unsorted => children.iter().collect_vec().into_iter(),
+                };
+                for (child_name, child) in children {
                     visit_node(
                         child,
                         child_name,
                         idx,
-                        recursion_depth + 1,
+                        my_node_idx,
                         file,
                         strings_table,
                         file_data_offsets_to_write,
+                        order,
                     )?;
                 }
                 trace!("Returning to parent dir (node {my_node_idx})");
-                // below: casting from i32 to u32 and saturating negative values to 0
-                (recursion_depth.try_into().unwrap_or(0), *idx)
+                // A folder's data offset is the index of its parent
+                // folder node (0 for the root, which has no parent),
+                // and its size is the index of the first node after
+                // its last descendant
+                (parent_idx, *idx)
             }
         };
 
@@ -496,12 +1516,13 @@ pub fn write<SW: Seek + Write>(file: &mut SW, root: &U8Node) -> Result<(), io::E
     // Do all the things!
     visit_node(
         root,
-        "",
+        root_name,
         &mut 0,
-        -1,
+        0,
         file,
         &mut strings_table,
         &mut file_data_offsets_to_write,
+        options.order,
     )?;
 
     // Append the strings table, and make a note of the current length
@@ -515,9 +1536,17 @@ pub fn write<SW: Seek + Write>(file: &mut SW, root: &U8Node) -> Result<(), io::E
 
     // Align to 0x20 and make another note of the current length
     util::write_zeros_to_align_to(file, 0x20, initial_file_offset)?;
-    let data_table_offset: u32 = (file.stream_position()? - initial_file_offset)
+    let mut data_table_offset: u32 = (file.stream_position()? - initial_file_offset)
         .try_into()
         .unwrap();
+    if let Some(pinned_offset) = options.data_table_offs {
+        if pinned_offset >= data_table_offset {
+            file.write_all(&vec![0; (pinned_offset - data_table_offset) as usize])?;
+            data_table_offset = pinned_offset;
+        } else {
+            debug!("FNT is too long to pin the data table at {pinned_offset:#x}");
+        }
+    }
     trace!("data_table_offset={data_table_offset:#x}");
 
     // Write the real data offsets for all of the file nodes, now that
@@ -527,7 +1556,7 @@ pub fn write<SW: Seek + Write>(file: &mut SW, root: &U8Node) -> Result<(), io::E
         file_data_offsets_to_write.len()
     );
     for (offs, relative_value) in file_data_offsets_to_write {
-        file.seek(SeekFrom::Start(offs as u64))?;
+        file.seek(SeekFrom::Start(offs))?;
         file.write_all(&(data_table_offset + relative_value).to_be_bytes())?;
     }
 
@@ -541,7 +1570,7 @@ pub fn write<SW: Seek + Write>(file: &mut SW, root: &U8Node) -> Result<(), io::E
         initial_file_offset + u64::from(data_table_offset),
     ))?;
     trace!("Done writing U8 FNT");
-    Ok(())
+    Ok(data_table_offset)
 }
 
 #[cfg(test)]
@@ -618,25 +1647,392 @@ FILENAME                                              OFFSET      SIZE
             );
             Ok(())
         }
-    }
-
-    mod read {
-        use super::*;
 
         #[test]
-        fn test_empty_fnt() -> TestResult {
+        fn test_non_utf8_name() -> TestResult {
+            // A file named "\x82\xa0" (Shift-JIS), which isn't valid
+            // UTF-8
             let mut cursor = Cursor::new(
                 concat_bytes!(
-                    b"U\xaa8-\0\0\0 \0\0\0\r\0\0\0@\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
-                    b"\x01\0\0\0\0\0\0\0\0\0\0\x01\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+                    b"U\xaa8-\0\0\0 \0\0\0\x1c\0\0\0@\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+                    b"\x01\0\0\0\0\0\0\0\0\0\0\x02\0\0\0\x01\0\0\0@\0\0\0\x10",
+                    b"\0\x82\xa0\0\0\0\0\0",
                 )
                 .to_vec(),
             );
-            let (root, data_table_offs) = read(&mut cursor)?;
-            assert_eq!(root, U8Node::Folder(U8FolderNode::new()));
-            assert_eq!(data_table_offs, 0x40);
-            Ok(())
-        }
+            let (root, _) = read(&mut cursor)?;
+
+            assert_eq!(
+                format!("\n{}", root),
+                "
+FILENAME                                              OFFSET      SIZE
+/
+  \u{fffd}\u{fffd}                                                     0x0      0x10"
+            );
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    mod u8filenode_serde {
+        use super::*;
+
+        #[test]
+        fn test_round_trip() -> TestResult {
+            let node = U8FileNode {
+                offset: 0x1234,
+                size: 0xfedcba98,
+            };
+
+            let json = serde_json::to_string(&node)?;
+            assert_eq!(json, r#"{"offset":4660,"size":4275878552}"#);
+            assert_eq!(serde_json::from_str::<U8FileNode>(&json)?, node);
+            Ok(())
+        }
+    }
+
+    mod u8node_validate {
+        use super::*;
+
+        #[test]
+        fn test_valid() -> TestResult {
+            let root = U8Node::Folder(U8FolderNode::from([
+                (
+                    "a".to_owned(),
+                    U8Node::File(U8FileNode { offset: 1, size: 3 }),
+                ),
+                (
+                    "bb".to_owned(),
+                    U8Node::Folder(U8FolderNode::from([(
+                        "ccc".to_owned(),
+                        U8Node::File(U8FileNode { offset: 5, size: 7 }),
+                    )])),
+                ),
+            ]));
+            assert_eq!(root.validate(), Vec::new());
+            Ok(())
+        }
+
+        #[test]
+        fn test_problems() -> TestResult {
+            let root = U8Node::Folder(U8FolderNode::from([
+                (
+                    "a".to_owned(),
+                    U8Node::File(U8FileNode { offset: 1, size: 3 }),
+                ),
+                (
+                    "A".to_owned(),
+                    U8Node::File(U8FileNode { offset: 1, size: 3 }),
+                ),
+                (
+                    "bb".to_owned(),
+                    U8Node::Folder(U8FolderNode::from([
+                        (
+                            "".to_owned(),
+                            U8Node::File(U8FileNode { offset: 5, size: 7 }),
+                        ),
+                        (
+                            "c\0c".to_owned(),
+                            U8Node::File(U8FileNode {
+                                offset: 0xffffffff,
+                                size: 1,
+                            }),
+                        ),
+                    ])),
+                ),
+            ]));
+
+            let issues = root.validate();
+            assert_eq!(issues.len(), 4);
+            assert!(issues.iter().any(|i| i.path == "/"));
+            assert!(issues
+                .iter()
+                .any(|i| i.path == "/bb/" && i.message == "name is empty"));
+            assert_eq!(issues.iter().filter(|i| i.path == "/bb/c\0c").count(), 2);
+            Ok(())
+        }
+
+        #[test]
+        fn test_too_deep() -> TestResult {
+            let mut root = U8Node::Folder(U8FolderNode::new());
+            for _ in 0..=U8Node::MAX_DEPTH + 1 {
+                root = U8Node::Folder(U8FolderNode::from([("x".to_owned(), root)]));
+            }
+            assert_eq!(root.validate().len(), 1);
+            Ok(())
+        }
+
+        #[test]
+        fn test_root_file() -> TestResult {
+            let root = U8Node::File(U8FileNode { offset: 0, size: 0 });
+            assert_eq!(
+                root.validate(),
+                [ValidationIssue {
+                    path: "/".to_owned(),
+                    message: "root node is a file (size 0x0), but needs to be a folder".to_owned(),
+                }]
+            );
+            Ok(())
+        }
+    }
+
+    mod u8node_from_iter {
+        use super::*;
+
+        #[test]
+        fn test_simple() -> TestResult {
+            let file = U8Node::File(U8FileNode { offset: 0, size: 4 });
+            let root = U8Node::from([(
+                "arc".to_owned(),
+                U8Node::from([("a".to_owned(), file.clone())]),
+            )]);
+            assert_eq!(
+                root,
+                U8Node::Folder(U8FolderNode::from([(
+                    "arc".to_owned(),
+                    U8Node::Folder(U8FolderNode::from([("a".to_owned(), file.clone())])),
+                )]))
+            );
+
+            let folder: U8Node = ["a", "b", "A"]
+                .into_iter()
+                .map(|name| (name.to_owned(), file.clone()))
+                .collect();
+            assert_eq!(folder.as_folder().map(U8FolderNode::len), Some(3));
+            assert_eq!(folder.validate().len(), 1);
+            assert_eq!(U8Node::from([]), U8Node::Folder(U8FolderNode::new()));
+            Ok(())
+        }
+    }
+
+    mod fnt_sort_key {
+        use super::*;
+
+        #[test]
+        fn test_order() -> TestResult {
+            let names = ["b", "A", "_", "C", "a_", "a0"]
+                .into_iter()
+                .sorted_by_key(|name| fnt_sort_key(name))
+                .collect_vec();
+            assert_eq!(names, ["_", "A", "a0", "a_", "b", "C"]);
+
+            assert!(fnt_sort_key("Title.BRLYT") == fnt_sort_key("title.brlyt"));
+            // Only ASCII letters are case-folded
+            assert!(fnt_sort_key("\u{c9}") != fnt_sort_key("\u{e9}"));
+            Ok(())
+        }
+    }
+
+    mod u8node_insert_path {
+        use super::*;
+
+        #[test]
+        fn test_simple() -> TestResult {
+            let file = || U8Node::File(U8FileNode { offset: 0, size: 1 });
+            let mut root = U8Node::Folder(U8FolderNode::new());
+            root.insert_path("/arc/anim/a.brlan", file())?;
+            root.insert_path("ARC/b", file())?;
+
+            assert_eq!(
+                root,
+                U8Node::Folder(U8FolderNode::from([(
+                    "arc".to_owned(),
+                    U8Node::Folder(U8FolderNode::from([
+                        (
+                            "anim".to_owned(),
+                            U8Node::Folder(U8FolderNode::from([("a.brlan".to_owned(), file())])),
+                        ),
+                        ("b".to_owned(), file()),
+                    ])),
+                )]))
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn test_errors() -> TestResult {
+            let file = || U8Node::File(U8FileNode { offset: 0, size: 1 });
+            let mut root = U8Node::Folder(U8FolderNode::new());
+            root.insert_path("/arc/a", file())?;
+
+            assert_eq!(
+                root.insert_path("/arc/A", file()),
+                Err(FntBuilderError::AlreadyExists("/arc/A".to_owned()))
+            );
+            assert_eq!(
+                root.insert_path("/arc/a/b/c", file()),
+                Err(FntBuilderError::NotAFolder("arc/a".to_owned()))
+            );
+            assert_eq!(
+                root.insert_path("/", file()),
+                Err(FntBuilderError::InvalidPath("/".to_owned()))
+            );
+            assert_eq!(
+                file().insert_path("a", file()),
+                Err(FntBuilderError::NotAFolder("/".to_owned()))
+            );
+            Ok(())
+        }
+    }
+
+    mod u8node_counts {
+        use super::*;
+
+        #[test]
+        fn test_empty_folder() -> TestResult {
+            let root = U8Node::Folder(U8FolderNode::new());
+            assert_eq!(root.depth(), 0);
+            assert_eq!(root.count_files(), 0);
+            assert_eq!(root.count_folders(), 1);
+            Ok(())
+        }
+
+        #[test]
+        fn test_single_file() -> TestResult {
+            let file = U8Node::File(U8FileNode { offset: 0, size: 1 });
+            assert_eq!(file.depth(), 0);
+            assert_eq!(file.count_files(), 1);
+            assert_eq!(file.count_folders(), 0);
+            Ok(())
+        }
+
+        #[test]
+        fn test_tree() -> TestResult {
+            // {
+            //     "a": File,
+            //     "b": {"c": File, "d": {}},
+            //     "e": {}}
+            let file = || U8Node::File(U8FileNode { offset: 0, size: 1 });
+            let root = U8Node::Folder(U8FolderNode::from([
+                ("a".to_owned(), file()),
+                (
+                    "b".to_owned(),
+                    U8Node::Folder(U8FolderNode::from([
+                        ("c".to_owned(), file()),
+                        ("d".to_owned(), U8Node::Folder(U8FolderNode::new())),
+                    ])),
+                ),
+                ("e".to_owned(), U8Node::Folder(U8FolderNode::new())),
+            ]));
+            assert_eq!(root.depth(), 2);
+            assert_eq!(root.count_files(), 2);
+            assert_eq!(root.count_folders(), 4);
+
+            // This should agree with the node count in the written FNT
+            let mut out = Cursor::new(Vec::new());
+            write(&mut out, &root)?;
+            let header = read_header(&mut out)?;
+            assert_eq!(
+                header.node_count as usize,
+                root.count_files() + root.count_folders()
+            );
+            Ok(())
+        }
+    }
+
+    mod fnt_builder {
+        use super::*;
+
+        #[test]
+        fn test_simple() -> TestResult {
+            let mut builder = FntBuilder::new();
+            assert_eq!(builder.add_file("/arc/anim/a.brlan", 0x30)?, 0);
+            assert_eq!(builder.add_file("arc/blyt/b.brlyt", 0x10)?, 0x40);
+            assert_eq!(builder.add_file("/ARC/anim/c.brlan", 0)?, 0x60);
+            assert_eq!(builder.add_file("/d", 4)?, 0x60);
+            assert_eq!(builder.fat_size(), 0x64);
+
+            assert_eq!(
+                builder.finish(),
+                U8Node::Folder(U8FolderNode::from([
+                    (
+                        "arc".to_owned(),
+                        U8Node::Folder(U8FolderNode::from([
+                            (
+                                "anim".to_owned(),
+                                U8Node::Folder(U8FolderNode::from([
+                                    (
+                                        "a.brlan".to_owned(),
+                                        U8Node::File(U8FileNode {
+                                            offset: 0,
+                                            size: 0x30
+                                        })
+                                    ),
+                                    (
+                                        "c.brlan".to_owned(),
+                                        U8Node::File(U8FileNode {
+                                            offset: 0x60,
+                                            size: 0
+                                        })
+                                    ),
+                                ]))
+                            ),
+                            (
+                                "blyt".to_owned(),
+                                U8Node::Folder(U8FolderNode::from([(
+                                    "b.brlyt".to_owned(),
+                                    U8Node::File(U8FileNode {
+                                        offset: 0x40,
+                                        size: 0x10
+                                    })
+                                )]))
+                            ),
+                        ]))
+                    ),
+                    (
+                        "d".to_owned(),
+                        U8Node::File(U8FileNode {
+                            offset: 0x60,
+                            size: 4
+                        })
+                    ),
+                ]))
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn test_errors() -> TestResult {
+            let mut builder = FntBuilder::new();
+            builder.add_file("/arc/a", 1)?;
+
+            assert_eq!(
+                builder.add_file("/arc/A", 1),
+                Err(FntBuilderError::AlreadyExists("/arc/A".to_owned()))
+            );
+            assert_eq!(
+                builder.add_file("/arc/a/b", 1),
+                Err(FntBuilderError::NotAFolder("arc/a".to_owned()))
+            );
+            assert_eq!(
+                builder.add_file("/", 1),
+                Err(FntBuilderError::InvalidPath("/".to_owned()))
+            );
+            assert_eq!(
+                builder.add_file("/arc/huge", u32::MAX),
+                Err(FntBuilderError::FatTooLarge)
+            );
+            Ok(())
+        }
+    }
+
+    mod read {
+        use super::*;
+
+        #[test]
+        fn test_empty_fnt() -> TestResult {
+            let mut cursor = Cursor::new(
+                concat_bytes!(
+                    b"U\xaa8-\0\0\0 \0\0\0\r\0\0\0@\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+                    b"\x01\0\0\0\0\0\0\0\0\0\0\x01\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+                )
+                .to_vec(),
+            );
+            let (root, data_table_offs) = read(&mut cursor)?;
+            assert_eq!(root, U8Node::Folder(U8FolderNode::new()));
+            assert_eq!(data_table_offs, 0x40);
+            Ok(())
+        }
 
         #[test]
         fn test_simple_fnt() -> TestResult {
@@ -685,6 +2081,363 @@ FILENAME                                              OFFSET      SIZE
             assert_eq!(data_table_offs, 0x80);
             Ok(())
         }
+
+        #[test]
+        fn test_data_table_offset_mismatch() -> TestResult {
+            let mut data = concat_bytes!(
+                b"U\xaa8-\0\0\0 \0\0\0]\0\0\0\x80\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+                b"\x01\0\0\0\0\0\0\0\0\0\0\x06\0\0\0\x01\0\0\0\x81\0\0\0\x03\x01\0\0\x03\0\0\0\0",
+                b"\0\0\0\x05\0\0\0\x06\0\0\0\x85\0\0\0\x07\0\0\0\n\0\0\0\x89\0\0\0\x0b\0\0\0\x0f",
+                b"\0\0\0\x8d\0\0\0\x0f\0a\0bb\0ccc\0dddd\0eeeee\0\0\0\0",
+            )
+            .to_vec();
+            check_data_table_offset(&mut Cursor::new(data.clone()))?;
+
+            // Claim that the data table starts earlier than it does
+            data[0xf] = 0x60;
+            assert!(matches!(
+                check_data_table_offset(&mut Cursor::new(data.clone())),
+                Err(ParseU8Error::DataTableOffsetMismatch {
+                    claimed: 0x60,
+                    computed: 0x80,
+                })
+            ));
+
+            // Regular reading still trusts the header
+            assert_eq!(read(&mut Cursor::new(data))?.1, 0x60);
+            Ok(())
+        }
+
+        #[test]
+        fn test_reserved_round_trip() -> TestResult {
+            let data = concat_bytes!(
+                b"U\xaa8-\0\0\0 \0\0\0\x1b\0\0\0@hidden\0data\xff\x01\x02\x03\x04",
+                b"\x01\0\0\0\0\0\0\0\0\0\0\x02\0\0\0\x01\0\0\0@\0\0\0\x10",
+                b"\0a\0\0\0\0\0\0",
+            );
+
+            let arc = read_parsed_arc(&mut Cursor::new(data.to_vec()))?;
+            assert_eq!(&arc.reserved, b"hidden\0data\xff\x01\x02\x03\x04");
+
+            let mut cursor = Cursor::new(Vec::new());
+            write_parsed_arc(&mut cursor, &arc)?;
+            assert_eq!(cursor.into_inner(), data);
+            Ok(())
+        }
+
+        #[test]
+        fn test_read_failed() -> TestResult {
+            // A header that ends partway through the data table offset
+            let data = b"U\xaa8-\0\0\0 \0\0\0\x19\0\0";
+            let err = read(&mut Cursor::new(data.to_vec())).unwrap_err();
+            assert!(matches!(
+                err,
+                ParseU8Error::ReadFailed {
+                    what: "data table offset",
+                    offset: 0xc,
+                    ..
+                }
+            ));
+            assert_eq!(err.to_string(), "failed to read data table offset at 0xc");
+            Ok(())
+        }
+
+        #[test]
+        fn test_truncated() -> TestResult {
+            let root = U8Node::Folder(U8FolderNode::from([(
+                "a".to_owned(),
+                U8Node::File(U8FileNode {
+                    offset: 0,
+                    size: 0x40,
+                }),
+            )]));
+            let mut cursor = Cursor::new(Vec::new());
+            let data_table_offs = write(&mut cursor, &root)?;
+            let implied = u64::from(data_table_offs) + 0x40;
+
+            // Missing a little data (like alignment padding) is fine
+            cursor.write_all(&[0; 0x30])?;
+            let arc = read_parsed_arc(&mut cursor)?;
+            assert_eq!(arc.implied_size(), implied);
+
+            // But not a whole lot
+            cursor.get_mut().truncate(data_table_offs as usize + 0x10);
+            assert!(matches!(
+                read(&mut cursor),
+                Err(ParseU8Error::Truncated { implied: i, actual })
+                    if i == implied && actual == implied - 0x30
+            ));
+
+            let options = ReadOptions {
+                allow_truncated: true,
+                ..Default::default()
+            };
+            let arc = read_parsed_arc_with_options(&mut cursor, &options)?;
+            assert_eq!(arc.implied_size(), implied);
+            Ok(())
+        }
+
+        #[test]
+        fn test_lenient_magic() -> TestResult {
+            let data = concat_bytes!(
+                b"U\xaa8\0\0\0\0 \0\0\0\x19\0\0\0@\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+                b"\x01\0\0\0\0\0\0\0\0\0\0\x01\0\0\0\0\0\0\0\0\0\0\0\0",
+            );
+
+            assert!(matches!(
+                read_parsed_arc(&mut Cursor::new(data.to_vec())),
+                Err(ParseU8Error::BadMagic(0x55aa3800))
+            ));
+
+            let options = ReadOptions {
+                lenient_magic: true,
+                ..Default::default()
+            };
+            let arc = read_parsed_arc_with_options(&mut Cursor::new(data.to_vec()), &options)?;
+            assert_eq!(arc.root, U8Node::Folder(U8FolderNode::new()));
+
+            // Magics that are more than slightly off are still rejected
+            let mut data = data.to_vec();
+            data[2] = b'9';
+            assert!(matches!(
+                read_parsed_arc_with_options(&mut Cursor::new(data), &options),
+                Err(ParseU8Error::BadMagic(0x55aa3900))
+            ));
+            Ok(())
+        }
+
+        #[test]
+        fn test_lenient_strings() -> TestResult {
+            // One file, "abc", whose name's terminator is missing (so
+            // the data table starts right after it)
+            let data = concat_bytes!(
+                b"U\xaa8-\0\0\0 \0\0\0\x1c\0\0\0<\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+                b"\x01\0\0\0\0\0\0\0\0\0\0\x02\0\0\0\x01\0\0\0<\0\0\0\x04",
+                b"\0abcDATA",
+            );
+
+            // Normally, the name runs into the file data
+            let arc = read_parsed_arc(&mut Cursor::new(data.to_vec()));
+            assert!(arc.map_or(true, |arc| arc.root.child("abc").is_none()));
+
+            let options = ReadOptions {
+                lenient_strings: true,
+                ..Default::default()
+            };
+            let arc = read_parsed_arc_with_options(&mut Cursor::new(data.to_vec()), &options)?;
+            assert_eq!(
+                arc.root,
+                U8Node::Folder(U8FolderNode::from([(
+                    "abc".to_owned(),
+                    U8Node::File(U8FileNode { offset: 0, size: 4 })
+                )]))
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn test_header() -> TestResult {
+            let data = concat_bytes!(
+                b"U\xaa8\0\0\0\0 \0\0\0\x19\0\0\0@\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\x07",
+                b"\x01\0\0\0\0\0\0\0\0\0\0\x01\0\0\0\0\0\0\0\0\0\0\0\0",
+            );
+
+            // Works even though the magic is wrong
+            let header = read_header(&mut Cursor::new(data.to_vec()))?;
+            assert_eq!(
+                header,
+                U8Header {
+                    magic: 0x55aa3800,
+                    root_node_offs: 0x20,
+                    header_size: 0x19,
+                    data_table_offs: 0x40,
+                    node_count: 1,
+                    reserved: [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 7],
+                }
+            );
+            assert_eq!(header.end_of_header(), 0x39);
+
+            // The same values should be available from a full read
+            let options = ReadOptions {
+                lenient_magic: true,
+                ..Default::default()
+            };
+            let arc = read_parsed_arc_with_options(&mut Cursor::new(data.to_vec()), &options)?;
+            assert_eq!(arc.header, header);
+            Ok(())
+        }
+
+        #[test]
+        fn test_root_name_round_trip() -> TestResult {
+            let data = concat_bytes!(
+                b"U\xaa8-\0\0\0 \0\0\0\x1c\0\0\0@\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+                b"\x01\0\0\0\0\0\0\0\0\0\0\x02\0\0\0\x02\0\0\0@\0\0\0\0",
+                b"r\0a\0\0\0\0\0",
+            );
+
+            let arc = read_parsed_arc(&mut Cursor::new(data.to_vec()))?;
+            assert_eq!(arc.root_name, "r");
+            assert_eq!(arc.serialized_fnt_len(), 0x40);
+
+            let mut cursor = Cursor::new(Vec::new());
+            write_parsed_arc(&mut cursor, &arc)?;
+            assert_eq!(cursor.into_inner(), data);
+            Ok(())
+        }
+
+        #[test]
+        fn test_name_with_slash() -> TestResult {
+            let root = U8Node::Folder(U8FolderNode::from([(
+                "a/b".to_owned(),
+                U8Node::File(U8FileNode { offset: 0, size: 1 }),
+            )]));
+            assert!(root.validate().is_empty());
+
+            let mut cursor = Cursor::new(Vec::new());
+            write(&mut cursor, &root)?;
+            let (read_root, _) = read(&mut cursor)?;
+            assert_eq!(read_root, root);
+
+            // Reachable as a child, but not by path
+            assert!(read_root.child("a/b").is_some());
+            assert!(read_root.get("a/b").is_none());
+            Ok(())
+        }
+
+        #[test]
+        fn test_from_reader() -> TestResult {
+            let data = concat_bytes!(
+                b"U\xaa8-\0\0\0 \0\0\0\x1b\0\0\0@\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+                b"\x01\0\0\0\0\0\0\0\0\0\0\x02\0\0\0\x01\0\0\0@\0\0\0\x04",
+                b"\0a\0\0\0\0\0\0data",
+            );
+
+            // `&[u8]` is `Read`, but not `Seek`
+            let (root, data_table_offs, mut cursor) = read_from_reader(&data[..])?;
+            assert_eq!(
+                root,
+                U8Node::Folder(U8FolderNode::from([(
+                    "a".to_owned(),
+                    U8Node::File(U8FileNode { offset: 0, size: 4 }),
+                )]))
+            );
+            assert_eq!(data_table_offs, 0x40);
+            assert_eq!(util::read_file_slice(&mut cursor, 0x40, 4)?, b"data");
+
+            let (arc, bytes) = read_parsed_arc_from_reader(&data[..])?;
+            assert_eq!(arc.root, root);
+            assert_eq!(arc.data_table_offs, 0x40);
+            assert_eq!(bytes, data);
+            Ok(())
+        }
+
+        #[test]
+        fn test_with_length() -> TestResult {
+            let data = concat_bytes!(
+                b"U\xaa8-\0\0\0 \0\0\0\x1b\0\0\0@\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+                b"\x01\0\0\0\0\0\0\0\0\0\0\x02\0\0\0\x01\0\0\0@\0\0\0\x04",
+                b"\0a\0\0\0\0\0\0data",
+            );
+
+            // The reader is left at the start of the data table
+            let mut reader = &data[..];
+            let (root, data_table_offs) = read_with_length(&mut reader, data.len() as u64)?;
+            assert_eq!((root, data_table_offs), read(&mut Cursor::new(data))?);
+            assert_eq!(reader, b"data");
+
+            // Checked against the given length, not what's actually
+            // there (missing just the file data's last few bytes is
+            // fine, as with `read()`)
+            read_with_length(&mut &data[..], 0x40)?;
+            assert!(matches!(
+                read_with_length(&mut &data[..], 0x3f),
+                Err(ParseU8Error::Malformed(_))
+            ));
+            assert!(matches!(
+                read_with_length(&mut &data[..0x30], data.len() as u64),
+                Err(ParseU8Error::ReadFailed { offset: 0x30, .. })
+            ));
+            assert!(matches!(
+                read_with_length(&mut &b"abcd"[..], data.len() as u64),
+                Err(ParseU8Error::ReadFailed { offset: 0, .. })
+            ));
+            Ok(())
+        }
+
+        #[test]
+        fn test_parts() -> TestResult {
+            let root = U8Node::Folder(U8FolderNode::from([(
+                "a".to_owned(),
+                U8Node::File(U8FileNode {
+                    offset: 0x10,
+                    size: 0x20,
+                }),
+            )]));
+
+            let arc = ParsedArc::from_parts(root.clone(), 0x40);
+            assert_eq!(arc.reserved, [0; 16]);
+            assert_eq!(arc.into_parts(), (root, 0x40));
+            Ok(())
+        }
+
+        #[test]
+        fn test_bad_node_type() -> TestResult {
+            let mut cursor = Cursor::new(
+                concat_bytes!(
+                    b"U\xaa8-\0\0\0 \0\0\0\x19\0\0\0@\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+                    b"\x01\0\0\0\0\0\0\0\0\0\0\x02\x03\0\0\x01\0\0\0\0\0\0\0\0\0a\0\0\0\0\0\0",
+                )
+                .to_vec(),
+            );
+            assert!(matches!(
+                read(&mut cursor),
+                Err(ParseU8Error::UnexpectedNodeType {
+                    node_type: 3,
+                    offset: 0x2c
+                })
+            ));
+            Ok(())
+        }
+
+        #[test]
+        fn test_malformed_node_count() -> TestResult {
+            // Same as `test_empty_fnt()`, but with the root node
+            // claiming more nodes than could fit in the file
+            let mut cursor = Cursor::new(
+                concat_bytes!(
+                    b"U\xaa8-\0\0\0 \0\0\0\r\0\0\0@\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+                    b"\x01\0\0\0\0\0\0\0\x10\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+                )
+                .to_vec(),
+            );
+            let err = read(&mut cursor).unwrap_err();
+            assert!(matches!(err, ParseU8Error::Malformed(_)));
+            assert!(err.to_string().contains("past the end of the file"));
+
+            // ... or none at all
+            cursor.get_mut()[0x28..0x2c].copy_from_slice(&[0; 4]);
+            assert!(matches!(read(&mut cursor), Err(ParseU8Error::Malformed(_))));
+            Ok(())
+        }
+
+        #[test]
+        fn test_malformed_folder_size() -> TestResult {
+            // Two nodes, but the subfolder claims to end at node 0x100
+            // (so its children would run into the string table and
+            // beyond). The root node's size is the node count itself, so
+            // it can't be wrong in the same way.
+            let mut cursor = Cursor::new(
+                concat_bytes!(
+                    b"U\xaa8-\0\0\0 \0\0\0\x19\0\0\0@\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+                    b"\x01\0\0\0\0\0\0\0\0\0\0\x02\x01\0\0\x01\0\0\0\0\0\0\x01\0\0a\0\0\0\0\0\0",
+                )
+                .to_vec(),
+            );
+            let err = read(&mut cursor).unwrap_err();
+            assert!(matches!(err, ParseU8Error::Malformed(_)));
+            assert!(err.to_string().contains("folder node 1 ends at node 256"));
+            Ok(())
+        }
     }
 
     mod write {
@@ -693,6 +2446,8 @@ FILENAME                                              OFFSET      SIZE
         /// Helper function to automatically test a bunch of variations
         /// on a FNT-writing scenario
         fn test_writing_a_fnt(root: &U8Node, expected_output: &[u8]) -> TestResult {
+            assert_eq!(root.serialized_fnt_len(), expected_output.len());
+
             // Test files with various amounts of data already written to them...
             for initial_offset in [0, 1, 2, 3, 4, 8, 12, 50] {
                 // ...and with various amounts of data already written past the cursor position
@@ -707,10 +2462,15 @@ FILENAME                                              OFFSET      SIZE
                     cursor.seek(SeekFrom::Start(initial_offset.try_into()?))?;
 
                     // Test the function
-                    write(&mut cursor, root)?;
+                    let data_table_offset = write(&mut cursor, root)?;
 
-                    // Check that it wrote the expected output
+                    // Check that it wrote the expected output, and
+                    // returned the FAT offset
                     let end_of_fnt = cursor.stream_position()?.try_into()?;
+                    assert_eq!(
+                        usize::try_from(data_table_offset)?,
+                        end_of_fnt - initial_offset
+                    );
                     assert_eq!(
                         &cursor.into_inner()[initial_offset..end_of_fnt],
                         expected_output
@@ -750,5 +2510,181 @@ FILENAME                                              OFFSET      SIZE
                 ),
             )
         }
+
+        #[test]
+        fn test_unsorted() -> TestResult {
+            let arc = ParsedArc::new(U8Node::Folder(U8FolderNode::from([
+                (
+                    "B".to_owned(),
+                    U8Node::File(U8FileNode { offset: 0, size: 1 }),
+                ),
+                (
+                    "a".to_owned(),
+                    U8Node::Folder(U8FolderNode::from([(
+                        "c".to_owned(),
+                        U8Node::File(U8FileNode {
+                            offset: 0x20,
+                            size: 2,
+                        }),
+                    )])),
+                ),
+                (
+                    "d".to_owned(),
+                    U8Node::File(U8FileNode {
+                        offset: 0x40,
+                        size: 3,
+                    }),
+                ),
+            ])));
+
+            // The order is arbitrary, but the tree should be the same,
+            // and the length shouldn't change
+            let mut cursor = Cursor::new(Vec::new());
+            let length = write_parsed_arc_with_order(&mut cursor, &arc, FntOrder::Unsorted)?;
+            assert_eq!(usize::try_from(length)?, arc.serialized_fnt_len());
+            // (plus some file data, so that the arc isn't truncated)
+            cursor.write_all(&[0; 0x60])?;
+            let (root, _) = read(&mut cursor)?;
+            assert_eq!(root, arc.root);
+            Ok(())
+        }
+
+        #[test]
+        fn test_insertion_order() -> TestResult {
+            let file = |offset| U8Node::File(U8FileNode { offset, size: 1 });
+            let mut root = U8Node::from([("c".to_owned(), file(0)), ("A".to_owned(), file(1))]);
+            root.as_mut_folder()
+                .unwrap()
+                .insert("b".to_owned(), file(2));
+            let arc = ParsedArc::new(root);
+
+            let mut cursor = Cursor::new(Vec::new());
+            write_parsed_arc_with_order(&mut cursor, &arc, FntOrder::Insertion)?;
+            cursor.write_all(&[0; 3])?;
+
+            // Reading it back keeps the same order
+            let (root, _) = read(&mut cursor)?;
+            assert_eq!(root, arc.root);
+            assert_eq!(
+                root.as_folder()
+                    .unwrap()
+                    .iter_insertion_order()
+                    .map(|(name, _)| name.as_str())
+                    .collect_vec(),
+                ["c", "A", "b"]
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn test_fat_len() -> TestResult {
+            let arc = ParsedArc::new(U8Node::Folder(U8FolderNode::from([(
+                "a".to_owned(),
+                U8Node::Folder(U8FolderNode::from([(
+                    "b".to_owned(),
+                    U8Node::File(U8FileNode {
+                        offset: 0x20,
+                        size: 0x10,
+                    }),
+                )])),
+            )])));
+
+            // Exactly fits
+            let mut cursor = Cursor::new(Vec::new());
+            write_parsed_arc_with_options(
+                &mut cursor,
+                &arc,
+                &WriteOptions {
+                    fat_len: Some(0x30),
+                    ..Default::default()
+                },
+            )?;
+
+            // One byte too short
+            let mut cursor = Cursor::new(Vec::new());
+            let error = write_parsed_arc_with_options(
+                &mut cursor,
+                &arc,
+                &WriteOptions {
+                    fat_len: Some(0x2f),
+                    ..Default::default()
+                },
+            )
+            .unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+            assert!(error.to_string().contains("\"/a/b\""));
+            assert!(cursor.into_inner().is_empty());
+            Ok(())
+        }
+
+        #[test]
+        fn test_folder_parent_indices() -> TestResult {
+            // {
+            //     "a": {
+            //         "b": {"c": File},
+            //         "d": {}},
+            //     "e": {
+            //         "f": {}}}
+            let root = U8Node::Folder(U8FolderNode::from([
+                (
+                    "a".to_owned(),
+                    U8Node::Folder(U8FolderNode::from([
+                        (
+                            "b".to_owned(),
+                            U8Node::Folder(U8FolderNode::from([(
+                                "c".to_owned(),
+                                U8Node::File(U8FileNode { offset: 0, size: 1 }),
+                            )])),
+                        ),
+                        ("d".to_owned(), U8Node::Folder(U8FolderNode::new())),
+                    ])),
+                ),
+                (
+                    "e".to_owned(),
+                    U8Node::Folder(U8FolderNode::from([(
+                        "f".to_owned(),
+                        U8Node::Folder(U8FolderNode::new()),
+                    )])),
+                ),
+            ]));
+
+            let mut cursor = Cursor::new(Vec::new());
+            write(&mut cursor, &root)?;
+            let data = cursor.into_inner();
+
+            // Read the raw (type, data offset, size) of each node
+            let node = |idx: usize| {
+                let start = 0x20 + 12 * idx;
+                let value = |offs: usize| {
+                    u32::from_be_bytes(data[start + offs..start + offs + 4].try_into().unwrap())
+                };
+                (data[start], value(4), value(8))
+            };
+            let node_count = node(0).2 as usize;
+            assert_eq!(node_count, 7);
+
+            // Check it the way a strict parser would: every folder's
+            // data offset must be the index of the innermost folder
+            // that contains it
+            let mut enclosing_folders = vec![(0, node_count)];
+            let mut parents = vec![node(0).1];
+            for idx in 1..node_count {
+                while idx >= enclosing_folders.last().unwrap().1 {
+                    enclosing_folders.pop();
+                }
+                let (node_type, data_offs, size) = node(idx);
+                if node_type == U8Node::FOLDER_TYPE {
+                    assert_eq!(data_offs as usize, enclosing_folders.last().unwrap().0);
+                    enclosing_folders.push((idx, size as usize));
+                    parents.push(data_offs);
+                }
+            }
+
+            // root, a, b, d, e, f
+            assert_eq!(parents, [0, 0, 1, 1, 0, 5]);
+
+            assert_eq!(read(&mut Cursor::new(data))?.0, root);
+            Ok(())
+        }
     }
 }
@@ -5,6 +5,14 @@ use std::fmt;
 use std::hash::{Hash, Hasher};
 use std::io::{self, Read, Seek, SeekFrom, Write};
 
+use log::warn;
+use unicode_width::UnicodeWidthStr;
+
+/// How far short of the end of a file's data the arc can end before
+/// `calc_hash_from_file_slice()` gives up, even when lenient: this is
+/// the alignment of file data in U8 archives.
+pub const EOF_LENIENCY_LIMIT: usize = 0x20;
+
 /// Write nulls to the provided `Write`, to pad its position to the next
 /// multiple of `alignment`.
 pub fn write_zeros_to_align_to<SW: Seek + Write>(
@@ -35,23 +43,44 @@ pub fn write_zeros_to_align_to<SW: Seek + Write>(
     Ok(())
 }
 
-/// Read data from one file and write it to another.
-pub fn read_from_into<R: Read, W: Write>(
-    read_from: &mut R,
-    write_to: &mut W,
-    amount: usize,
+/// Copy exactly `n` bytes from one file to another, in chunks no
+/// larger than `buf` (which can be reused across calls, to avoid
+/// allocating a new buffer every time). Returns an `UnexpectedEof`
+/// error if `src` ends early, in which case some of the data may have
+/// already been written to `dst`.
+pub fn copy_n<R: Read, W: Write>(
+    src: &mut R,
+    dst: &mut W,
+    n: u64,
+    buf: &mut [u8],
 ) -> Result<(), io::Error> {
-    // It'd be good to write this with more of a streaming approach, but
-    // I don't know how to determine the optimal buffer size...
-    let mut tmp = vec![0; amount];
-    let actual_amount = read_from.read(&mut tmp)?;
-    if actual_amount < amount {
+    if buf.is_empty() && n > 0 {
         return Err(io::Error::new(
-            io::ErrorKind::UnexpectedEof,
-            format!("tried to read {amount} bytes, but could only read {actual_amount}"),
+            io::ErrorKind::InvalidInput,
+            "can't copy data with an empty buffer",
         ));
     }
-    write_to.write_all(&tmp)?;
+
+    let mut remaining = n;
+    while remaining > 0 {
+        let chunk_size = usize::try_from(remaining).map_or(buf.len(), |r| r.min(buf.len()));
+        let chunk = &mut buf[..chunk_size];
+        src.read_exact(chunk).map_err(|err| {
+            if err.kind() == io::ErrorKind::UnexpectedEof {
+                io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    format!(
+                        "tried to copy {n} bytes, but could only read {}",
+                        n - remaining
+                    ),
+                )
+            } else {
+                err
+            }
+        })?;
+        dst.write_all(chunk)?;
+        remaining -= chunk_size as u64;
+    }
     Ok(())
 }
 
@@ -74,32 +103,81 @@ pub fn write_right_aligned_str(
 ) -> fmt::Result {
     let spaces_to_write = align_to
         .saturating_sub(*amount_written)
-        .saturating_sub(s.len());
+        .saturating_sub(s.width());
     if spaces_to_write > 0 {
         write!(writer, "{}", " ".repeat(spaces_to_write))?;
         *amount_written += spaces_to_write;
     }
     write!(writer, "{s}")?;
-    *amount_written += s.len();
+    *amount_written += s.width();
     Ok(())
 }
 
+/// Read the data from some part of a seekable and readable file.
+pub fn read_file_slice<SR: Seek + Read>(
+    reader: &mut SR,
+    offset: u64,
+    size: usize,
+) -> Result<Vec<u8>, io::Error> {
+    reader.seek(SeekFrom::Start(offset))?;
+
+    // (Not preallocated, since `size` may come from a corrupted file,
+    // and allocating gigabytes up front can abort the process before
+    // we find out that the data isn't there)
+    let mut data = Vec::new();
+    reader.take(size as u64).read_to_end(&mut data)?;
+    if data.len() < size {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!(
+                "tried to read {size} bytes, but could only read {}",
+                data.len()
+            ),
+        ));
+    }
+    Ok(data)
+}
+
 /// Calculate a hash of the data from some part of a seekable and
 /// readable file.
+///
+/// If `lenient_eof` is set and the file ends less than
+/// `EOF_LENIENCY_LIMIT` bytes short of the end of the slice, the
+/// available data is hashed instead (with a warning). Some arcs
+/// over-report the size of their last file this way, by counting
+/// trailing padding that isn't actually there.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "trace", skip(reader))
+)]
 pub fn calc_hash_from_file_slice<SR: Seek + Read>(
     reader: &mut SR,
     offset: u64,
     size: usize,
+    lenient_eof: bool,
 ) -> Result<u64, io::Error> {
     reader.seek(SeekFrom::Start(offset))?;
 
-    let mut tmp = vec![0; size];
-    let actual_amount = reader.read(&mut tmp)?;
+    // `read_to_end()` keeps reading until it hits EOF, so a short read
+    // here really does mean that the file ended early
+    let mut tmp = Vec::new();
+    reader.take(size as u64).read_to_end(&mut tmp)?;
+    let actual_amount = tmp.len();
     if actual_amount < size {
-        return Err(io::Error::new(
-            io::ErrorKind::UnexpectedEof,
-            format!("tried to read {size} bytes for hashing, but could only read {actual_amount}"),
-        ));
+        let shortfall = size - actual_amount;
+        if lenient_eof && shortfall < EOF_LENIENCY_LIMIT {
+            warn!(
+                "File data at {offset:#x} runs {shortfall} bytes past the end of the file; \
+                 hashing the {actual_amount} bytes that are there"
+            );
+        } else {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!(
+                    "tried to read {size} bytes for hashing, but could only read {actual_amount}"
+                ),
+            ));
+        }
     }
 
     let mut hasher = DefaultHasher::new();
@@ -107,6 +185,164 @@ pub fn calc_hash_from_file_slice<SR: Seek + Read>(
     Ok(hasher.finish())
 }
 
+/// Check whether `text` matches a simple glob `pattern`, in which `*`
+/// matches any run of characters (including none, and including
+/// forward-slashes) and `?` matches exactly one character. ASCII
+/// letters are compared case-insensitively, like U8 filenames.
+pub fn glob_matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    // Standard greedy matching, backtracking to the most recent `*`
+    // on a mismatch
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p].eq_ignore_ascii_case(&text[t])) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = backtrack {
+            // Let the `*` swallow one more character, and try again
+            backtrack = Some((star_p, star_t + 1));
+            p = star_p + 1;
+            t = star_t + 1;
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Lookup table for `crc32()`, generated at compile time.
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut value = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            value = if value & 1 == 0 {
+                value >> 1
+            } else {
+                (value >> 1) ^ 0xedb88320
+            };
+            bit += 1;
+        }
+        table[i] = value;
+        i += 1;
+    }
+    table
+};
+
+/// Calculate the standard (zlib / ISO-HDLC) CRC-32 of some data. Unlike
+/// `calc_hash_from_file_slice()`, the result is stable across Rust
+/// versions and platforms, and matches what common external tools
+/// report.
+pub fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0, |crc, &byte| {
+        CRC32_TABLE[usize::from((crc as u8) ^ byte)] ^ (crc >> 8)
+    })
+}
+
+/// SHA-256 round constants.
+const SHA256_K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// Run the SHA-256 compression function on one 64-byte block.
+fn sha256_block(state: &mut [u32; 8], block: &[u8; 64]) {
+    let mut w = [0_u32; 64];
+    for (i, word) in block.chunks_exact(4).enumerate() {
+        w[i] = u32::from_be_bytes(word.try_into().unwrap());
+    }
+    for i in 16..64 {
+        let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+        let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+        w[i] = w[i - 16]
+            .wrapping_add(s0)
+            .wrapping_add(w[i - 7])
+            .wrapping_add(s1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+    for (k, w) in SHA256_K.iter().zip(w) {
+        let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+        let ch = (e & f) ^ (!e & g);
+        let t1 = h
+            .wrapping_add(s1)
+            .wrapping_add(ch)
+            .wrapping_add(*k)
+            .wrapping_add(w);
+        let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+        let maj = (a & b) ^ (a & c) ^ (b & c);
+        let t2 = s0.wrapping_add(maj);
+        (h, g, f, e, d, c, b, a) = (g, f, e, d.wrapping_add(t1), c, b, a, t1.wrapping_add(t2));
+    }
+
+    for (value, new) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+        *value = value.wrapping_add(new);
+    }
+}
+
+/// Calculate the SHA-256 of everything left in a reader. The data is
+/// hashed as it's read, so it doesn't need to fit in memory.
+///
+/// This is for checking whole files against hashes published
+/// elsewhere; for comparing file data within smallworld, the cheaper
+/// `calc_hash_from_file_slice()` is used instead.
+pub fn sha256<R: Read>(reader: &mut R) -> Result<[u8; 32], io::Error> {
+    let mut state: [u32; 8] = [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+        0x5be0cd19,
+    ];
+    let mut block = [0; 64];
+    let mut block_len = 0;
+    let mut total_len: u64 = 0;
+
+    loop {
+        let amount = match reader.read(&mut block[block_len..]) {
+            Ok(0) => break,
+            Ok(amount) => amount,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        block_len += amount;
+        total_len += amount as u64;
+        if block_len == block.len() {
+            sha256_block(&mut state, &block);
+            block_len = 0;
+        }
+    }
+
+    // Padding: a 1 bit, zeros, and the message length in bits, which
+    // may spill over into one more block
+    block[block_len] = 0x80;
+    block[block_len + 1..].fill(0);
+    if block_len >= 56 {
+        sha256_block(&mut state, &block);
+        block.fill(0);
+    }
+    block[56..].copy_from_slice(&(total_len * 8).to_be_bytes());
+    sha256_block(&mut state, &block);
+
+    let mut digest = [0; 32];
+    for (bytes, value) in digest.chunks_exact_mut(4).zip(state) {
+        bytes.copy_from_slice(&value.to_be_bytes());
+    }
+    Ok(digest)
+}
+
 #[cfg(test)]
 #[allow(clippy::unnecessary_wraps)]
 mod tests {
@@ -148,7 +384,7 @@ mod tests {
         Ok(())
     }
 
-    mod read_from_into {
+    mod copy_n {
         use super::*;
 
         #[test]
@@ -156,8 +392,10 @@ mod tests {
             let mut vec_1 = Cursor::new(vec![0, 1, 2, 3, 4, 5, 6, 7]);
             let mut vec_2 = Cursor::new(vec![8, 9, 10, 11, 12, 13, 14, 15]);
 
+            // A buffer smaller than the amount to copy needs more than
+            // one chunk
             vec_2.seek(SeekFrom::Start(6))?;
-            read_from_into(&mut vec_1, &mut vec_2, 5)?;
+            copy_n(&mut vec_1, &mut vec_2, 5, &mut [0; 2])?;
 
             assert_eq!(vec_1.into_inner(), vec![0, 1, 2, 3, 4, 5, 6, 7]);
             assert_eq!(
@@ -172,7 +410,8 @@ mod tests {
         fn test_oob() -> TestResult {
             let mut vec_1 = Cursor::new(vec![0, 1, 2, 3]);
             let mut vec_2 = Cursor::new(vec![8, 9, 10, 11]);
-            assert!(read_from_into(&mut vec_1, &mut vec_2, 5).is_err());
+            let err = copy_n(&mut vec_1, &mut vec_2, 5, &mut [0; 2]).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
             Ok(())
         }
     }
@@ -212,18 +451,102 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_read_file_slice() -> TestResult {
+        let mut cursor = Cursor::new(b"AAAABBBBCCCC".to_vec());
+
+        assert_eq!(read_file_slice(&mut cursor, 4, 4)?, b"BBBB");
+        assert_eq!(read_file_slice(&mut cursor, 12, 0)?, b"");
+        assert!(read_file_slice(&mut cursor, 8, 5).is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_calc_hash_from_file_slice() -> TestResult {
         let data = b"AAAABBBBAAAABBBBCCCCDDDD";
         let mut cursor = Cursor::new(data.to_vec());
 
         assert_eq!(
-            calc_hash_from_file_slice(&mut cursor, 0, 8)?,
-            calc_hash_from_file_slice(&mut cursor, 8, 8)?
+            calc_hash_from_file_slice(&mut cursor, 0, 8, false)?,
+            calc_hash_from_file_slice(&mut cursor, 8, 8, false)?
         );
         assert_ne!(
-            calc_hash_from_file_slice(&mut cursor, 0, 8)?,
-            calc_hash_from_file_slice(&mut cursor, 16, 8)?
+            calc_hash_from_file_slice(&mut cursor, 0, 8, false)?,
+            calc_hash_from_file_slice(&mut cursor, 16, 8, false)?
+        );
+
+        // Zero-size slices all hash the same, wherever they are
+        assert_eq!(
+            calc_hash_from_file_slice(&mut cursor, 0, 0, false)?,
+            calc_hash_from_file_slice(&mut cursor, 100, 0, false)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_calc_hash_from_file_slice_lenient_eof() -> TestResult {
+        let data = b"AAAABBBBAAAA";
+        let mut cursor = Cursor::new(data.to_vec());
+
+        // Running a little past EOF is only OK if lenient
+        assert!(calc_hash_from_file_slice(&mut cursor, 8, 0x10, false).is_err());
+        assert_eq!(
+            calc_hash_from_file_slice(&mut cursor, 8, 0x10, true)?,
+            calc_hash_from_file_slice(&mut cursor, 0, 4, false)?
+        );
+
+        // ...but not too far
+        assert!(calc_hash_from_file_slice(&mut cursor, 8, 4 + EOF_LENIENCY_LIMIT, true).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_glob_matches() -> TestResult {
+        assert!(glob_matches("*.bak", "/arc/anim/file.bak"));
+        assert!(glob_matches("*.BAK", "/arc/anim/file.bak"));
+        assert!(!glob_matches("*.bak", "/arc/anim/file.bak2"));
+        assert!(glob_matches("/arc/*/file.?", "/arc/anim/file.x"));
+        assert!(!glob_matches("/arc/*/file.?", "/arc/anim/file.xy"));
+        assert!(glob_matches("*~*", "/arc/blyt/~tmp"));
+        assert!(glob_matches("*", ""));
+        assert!(!glob_matches("?", ""));
+        assert!(glob_matches("/arc/anim/x", "/arc/anim/x"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_crc32() -> TestResult {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf43926);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sha256() -> TestResult {
+        fn sha256_hex(data: &[u8]) -> Result<String, io::Error> {
+            Ok(sha256(&mut Cursor::new(data))?
+                .iter()
+                .map(|b| format!("{b:02x}"))
+                .collect())
+        }
+
+        assert_eq!(
+            sha256_hex(b"")?,
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            sha256_hex(b"abc")?,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        // Long enough that the padding needs an extra block
+        assert_eq!(
+            sha256_hex(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq")?,
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+        );
+        // Several full blocks
+        assert_eq!(
+            sha256_hex(&[b'a'; 1000])?,
+            "41edece42d63e8d9bf515a9ba6932e1c20cbc9f5a5d134645adb5db1b9737ea3"
         );
         Ok(())
     }
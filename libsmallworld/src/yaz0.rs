@@ -0,0 +1,238 @@
+//! Yaz0 compression, as used for compressed arcs (.szs files).
+//!
+//! Yaz0 data is a 0x10-byte header ("Yaz0", the decompressed size, and
+//! 8 reserved bytes), followed by groups of up to eight chunks. Each
+//! group starts with a code byte, whose bits (MSB first) say whether
+//! each chunk is a literal byte (1) or a back-reference to earlier
+//! output (0).
+
+use std::collections::HashMap;
+
+use thiserror::Error;
+
+/// How far back a back-reference can point.
+const MAX_DISTANCE: usize = 0x1000;
+
+/// The shortest back-reference that can be encoded.
+const MIN_MATCH_LEN: usize = 3;
+
+/// The longest back-reference that can be encoded.
+const MAX_MATCH_LEN: usize = 0xff + 0x12;
+
+/// How many earlier positions to try per byte when looking for a
+/// back-reference. Higher values compress slightly better, but more
+/// slowly.
+const MAX_CHAIN_STEPS: usize = 0x100;
+
+/// All errors that can be encountered when decompressing Yaz0 data.
+#[non_exhaustive]
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum Yaz0Error {
+    /// The data doesn't start with a Yaz0 header.
+    #[error("not Yaz0-compressed data")]
+    InvalidHeader,
+
+    /// The data ended before the full decompressed size was reached.
+    #[error("Yaz0 data ends unexpectedly")]
+    UnexpectedEof,
+
+    /// A back-reference (at the given offset in the decompressed
+    /// output) points before the start of the output.
+    #[error("invalid back-reference at {0:#x}")]
+    InvalidBackReference(usize),
+}
+
+/// Find the longest match for the data at `pos`, among earlier
+/// positions with the same first three bytes. Returns (length,
+/// distance), with a length of 0 if there's no usable match.
+fn find_match(
+    data: &[u8],
+    pos: usize,
+    head: &HashMap<[u8; 3], usize>,
+    prev: &[usize],
+) -> (usize, usize) {
+    let Some(key) = data.get(pos..pos + MIN_MATCH_LEN) else {
+        return (0, 0);
+    };
+    let max_len = (data.len() - pos).min(MAX_MATCH_LEN);
+
+    let mut best = (0, 0);
+    let mut candidate = head.get(key).copied();
+    for _ in 0..MAX_CHAIN_STEPS {
+        let Some(start) = candidate.filter(|&start| pos - start <= MAX_DISTANCE) else {
+            break;
+        };
+        let len = (0..max_len)
+            .take_while(|&i| data[start + i] == data[pos + i])
+            .count();
+        if len > best.0 {
+            best = (len, pos - start);
+            if len == max_len {
+                break;
+            }
+        }
+        candidate = Some(prev[start]).filter(|&p| p != usize::MAX);
+    }
+    best
+}
+
+/// Compress data with Yaz0. This is a greedy compressor, so the output
+/// is usually a little larger than Nintendo's, but it's close enough
+/// for estimating sizes.
+///
+/// Panics if the data is 4 GiB or larger, since the header can't
+/// represent its size.
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    let size: u32 = data
+        .len()
+        .try_into()
+        .expect("Yaz0 data must be smaller than 4 GiB");
+
+    let mut out = Vec::with_capacity(0x10 + data.len() + data.len() / 8 + 1);
+    out.extend_from_slice(b"Yaz0");
+    out.extend_from_slice(&size.to_be_bytes());
+    out.extend_from_slice(&[0; 8]);
+
+    // The most recent position of each three-byte sequence, and for
+    // each position, the previous position with the same three bytes
+    let mut head = HashMap::new();
+    let mut prev = vec![usize::MAX; data.len()];
+
+    let mut pos = 0;
+    while pos < data.len() {
+        let code_byte_pos = out.len();
+        out.push(0);
+
+        for bit in (0..8).rev() {
+            if pos >= data.len() {
+                break;
+            }
+
+            let (len, distance) = find_match(data, pos, &head, &prev);
+            let len = if len >= MIN_MATCH_LEN {
+                let distance = distance - 1;
+                if len < 0x12 {
+                    out.push(((len - 2) << 4 | distance >> 8) as u8);
+                    out.push(distance as u8);
+                } else {
+                    out.push((distance >> 8) as u8);
+                    out.push(distance as u8);
+                    out.push((len - 0x12) as u8);
+                }
+                len
+            } else {
+                out[code_byte_pos] |= 1 << bit;
+                out.push(data[pos]);
+                1
+            };
+
+            for (i, prev_pos) in prev.iter_mut().enumerate().skip(pos).take(len) {
+                if let Some(key) = data.get(i..i + MIN_MATCH_LEN) {
+                    let key: [u8; 3] = key.try_into().unwrap();
+                    *prev_pos = head.insert(key, i).unwrap_or(usize::MAX);
+                }
+            }
+            pos += len;
+        }
+    }
+
+    out
+}
+
+/// Decompress Yaz0 data.
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, Yaz0Error> {
+    if data.len() < 0x10 || &data[..4] != b"Yaz0" {
+        return Err(Yaz0Error::InvalidHeader);
+    }
+    let size = u32::from_be_bytes(data[4..8].try_into().unwrap()) as usize;
+
+    let mut out = Vec::with_capacity(size);
+    let mut src = 0x10;
+    let mut next_byte = || {
+        let byte = data.get(src).copied().ok_or(Yaz0Error::UnexpectedEof);
+        src += 1;
+        byte
+    };
+
+    while out.len() < size {
+        let code_byte = next_byte()?;
+        for bit in (0..8).rev() {
+            if out.len() >= size {
+                break;
+            }
+
+            if code_byte & (1 << bit) != 0 {
+                out.push(next_byte()?);
+                continue;
+            }
+
+            let (b1, b2) = (next_byte()?, next_byte()?);
+            let distance = (usize::from(b1 & 0xf) << 8 | usize::from(b2)) + 1;
+            let len = match b1 >> 4 {
+                0 => usize::from(next_byte()?) + 0x12,
+                n => usize::from(n) + 2,
+            };
+            let Some(start) = out.len().checked_sub(distance) else {
+                return Err(Yaz0Error::InvalidBackReference(out.len()));
+            };
+            // (byte by byte, since the source and destination can
+            // overlap)
+            for i in start..start + len {
+                if out.len() >= size {
+                    break;
+                }
+                out.push(out[i]);
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+#[allow(clippy::unnecessary_wraps)]
+mod tests {
+    use super::*;
+
+    type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+    #[test]
+    fn test_compress_simple() -> TestResult {
+        assert_eq!(
+            compress(b"abcabcabc"),
+            b"Yaz0\0\0\0\x09\0\0\0\0\0\0\0\0\xe0abc\x40\x02"
+        );
+        assert_eq!(compress(b""), b"Yaz0\0\0\0\0\0\0\0\0\0\0\0\0");
+        Ok(())
+    }
+
+    #[test]
+    fn test_round_trip() -> TestResult {
+        // A long run (which needs the three-byte back-reference form),
+        // some text, and some bytes that don't repeat at all
+        let mut data = vec![0; 0x500];
+        data.extend_from_slice(
+            b"openingTitle_EU_00_inPress.brlan openingTitle_US_00_inPress.brlan",
+        );
+        data.extend((0..=255_u8).map(|i| i.wrapping_mul(167)));
+
+        let compressed = compress(&data);
+        assert!(compressed.len() < data.len());
+        assert_eq!(decompress(&compressed)?, data);
+        Ok(())
+    }
+
+    #[test]
+    fn test_decompress_errors() -> TestResult {
+        assert_eq!(decompress(b"Yaz1"), Err(Yaz0Error::InvalidHeader));
+        assert_eq!(
+            decompress(b"Yaz0\0\0\0\x04\0\0\0\0\0\0\0\0\xf0ab"),
+            Err(Yaz0Error::UnexpectedEof)
+        );
+        assert_eq!(
+            decompress(b"Yaz0\0\0\0\x04\0\0\0\0\0\0\0\0\x80a\x20\x05"),
+            Err(Yaz0Error::InvalidBackReference(1))
+        );
+        Ok(())
+    }
+}
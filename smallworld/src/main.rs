@@ -1,12 +1,16 @@
 #![doc = include_str!("../../README.md")]
 
+use std::cell::RefCell;
 use std::fs::File;
-use std::io::{Cursor, Read, Seek, Write};
+use std::io::{IsTerminal, Read, Seek, Write};
+use std::num::NonZeroUsize;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
 
 use anyhow::{bail, Context, Result};
-use clap::Parser;
-use log::{debug, trace};
+use clap::{CommandFactory, FromArgMatches, Parser};
+use log::{debug, error, info, trace, warn};
 
 use libsmallworld as lib;
 
@@ -22,12 +26,31 @@ struct Args {
     // auto-generated `--help` output, which is why they're worded a bit
     // oddly
     /// Input filename
-    input_file: PathBuf,
+    ///
+    /// With `--template`, this is the output filename instead. If this
+    /// is a directory, every ".arc" file directly inside it is
+    /// converted (see `--jobs`).
+    #[clap(required_unless_present_any = ["print-filenames", "self-test"])]
+    input_file: Option<PathBuf>,
 
     /// Output filename [default: overwrite the input file]
+    ///
+    /// With `--split-regions`, this is the output directory instead
+    /// [default: the input file's directory]. If the input is a
+    /// directory, this is the output directory too [default: overwrite
+    /// the input files].
     #[clap(short, long)]
     output_file: Option<PathBuf>,
 
+    /// The number of arcs to convert at once, if the input is a
+    /// directory [default: the number of CPUs]
+    ///
+    /// Each arc is converted independently. Results are printed in
+    /// order of filename once every arc has been converted, and
+    /// smallworld fails if any of them couldn't be.
+    #[clap(short, long, value_parser, value_name = "N")]
+    jobs: Option<NonZeroUsize>,
+
     /// The regions to convert from, in order of priority
     ///
     /// Files from regions not listed here will be entirely ignored.
@@ -79,10 +102,407 @@ struct Args {
     #[clap(long, action)]
     ignore_conflicts: bool,
 
+    /// Ask what to do about each conflict, instead of failing
+    ///
+    /// For each conflict (see `--ignore-conflicts`), the conflicting
+    /// files are printed, and you're asked which one to keep, or
+    /// whether to stop. If standard input isn't a terminal, this has no
+    /// effect, and conflicts are treated as errors as usual.
+    #[clap(long, action, conflicts_with = "ignore-conflicts")]
+    interactive: bool,
+
+    /// Whether `interactive` was only set because of the config file's
+    /// `conflict_strategy`, rather than on the command line
+    #[clap(skip)]
+    interactive_from_config: bool,
+
+    /// Fail if the output would fully support fewer than this many
+    /// regions
+    ///
+    /// This is a safety check for distribution builds: for example,
+    /// `--min-regions 6` ensures that a region-free arc really does
+    /// cover all six regions. Regions whose files were left untouched
+    /// (i.e. not listed in `--from`) count too, if they're complete.
+    #[clap(long, value_parser, default_value_t = 0)]
+    min_regions: usize,
+
+    /// Always take regional files' data from this region, if it has
+    /// them
+    ///
+    /// Normally, each file's data comes from whichever region is listed
+    /// first in `--from`. With this, the given region's copy is used
+    /// instead whenever it exists, while `--from` still decides how
+    /// conflicts are resolved and reported. The region must also be
+    /// listed in `--from`.
+    #[clap(long, value_parser, value_name = "REGION")]
+    canonical_region: Option<String>,
+
+    /// Only keep the files the `--to` regions need
+    ///
+    /// The output will contain just the regional files for the
+    /// `--to` regions, plus the TPLs. Any other files in the input
+    /// (including other regions' files) are left out.
+    #[clap(long, action)]
+    minimal: bool,
+
+    /// Also remove regional files from regions not listed in `--from`
+    ///
+    /// Normally, other regions' files are left as-is. With this, the
+    /// output only contains data from the `--from` regions. Combined
+    /// with `--to all`, this makes the smallest possible region-free
+    /// arc, while still keeping every non-regional file (unlike
+    /// `--minimal`).
+    #[clap(long, action)]
+    strip_unselected: bool,
+
+    /// Rename regional files with slightly-off names to the standard
+    /// names before converting
+    ///
+    /// This can rescue arcs whose filenames were mangled by other
+    /// tools: for example, "openingTitle_EU_01_inPress.brlan" would be
+    /// treated as "openingTitle_EU_00_inPress.brlan". Each rename is
+    /// printed. Files are only renamed if the match is unambiguous.
+    #[clap(long, action)]
+    normalize_names: bool,
+
+    /// The order to write the files and folders in
+    ///
+    /// "nintendo" sorts each folder's contents case-insensitively, like
+    /// Nintendo's own arcs. "insertion" keeps the input file's order,
+    /// with any new files after the original ones. "none" skips
+    /// sorting, which is slightly faster, but the resulting order is
+    /// arbitrary. Only use the last two for files that won't be loaded
+    /// by the game.
+    #[clap(long, value_enum, default_value = "nintendo")]
+    sort: SortOrder,
+
+    /// Leave out files whose paths match this glob pattern (can be
+    /// given more than once)
+    ///
+    /// Patterns are matched against each file's full path within the
+    /// arc, such as "/arc/anim/file.bak". "*" matches any run of
+    /// characters (including "/"), and "?" matches any one character,
+    /// so `--exclude "*.bak"` drops every ".bak" file. Excluded
+    /// regional files are treated as missing.
+    #[clap(long, value_parser, value_name = "GLOB")]
+    exclude: Vec<String>,
+
+    /// Keep all file data at the same absolute offsets as in the input
+    ///
+    /// The new FNT is padded so that the data table starts where it did
+    /// in the input, and the input's data table is copied over as-is.
+    /// This keeps binary diffs tiny, e.g. if files were only renamed or
+    /// removed. If the new FNT is too large for this, a warning is
+    /// printed and the data is laid out normally.
+    #[clap(long, action)]
+    pin_data_offset: bool,
+
+    /// Fail if the output file would be larger than this many bytes
+    ///
+    /// The output size is predicted before anything is written, so an
+    /// existing output file is left untouched if it's too large. Also
+    /// available as `--max-output-size`.
+    #[clap(long, value_parser, alias = "max-output-size", value_name = "BYTES")]
+    max_size: Option<u64>,
+
+    /// Fail before converting if the input file's SHA-256 isn't this
+    /// (given as 64 hexadecimal digits)
+    ///
+    /// This guards against converting a modified or wrong arc by
+    /// mistake. The whole input file is hashed exactly as it is on
+    /// disk, and the actual hash is printed if it doesn't match.
+    #[clap(long, value_parser, value_name = "HEX")]
+    expect_sha: Option<String>,
+
+    /// Check the input file for structural problems instead of
+    /// converting it
+    ///
+    /// Every problem found is printed, and smallworld exits with an
+    /// error if there were any. No output file is written.
+    #[clap(long, action)]
+    validate: bool,
+
+    /// Print the input file's U8 header fields instead of converting it
+    ///
+    /// The values are printed as-is, so this also works on files that
+    /// are too broken to convert. If the whole FNT can be read, the
+    /// file size it implies (from the data table offset and the last
+    /// file's data) is printed too, next to the actual size. No output
+    /// file is written.
+    #[clap(long, action)]
+    dump_header: bool,
+
+    /// Check the input file for signs of corruption instead of
+    /// converting it
+    ///
+    /// These checks are heuristics, so anything they find is only
+    /// printed as a warning, and smallworld still exits successfully.
+    /// No output file is written. If smallworld was built with the
+    /// "brlyt" feature, brlan and brlyt files are also checked for the
+    /// correct magic.
+    #[clap(long, action)]
+    check_health: bool,
+
+    /// Check whether the input file already has filenames for all six
+    /// regions, instead of converting it
+    ///
+    /// For scripting: smallworld prints "yes" and exits successfully if
+    /// it does, or prints "no" and exits with status 1 if it doesn't.
+    /// No output file is written.
+    #[clap(long, action)]
+    is_region_free: bool,
+
+    /// Print whether the input file is already region-free, can be
+    /// converted to region-free, or is missing some kinds of regional
+    /// files entirely, instead of converting it
+    ///
+    /// Prints "region-free", "convertible", or "incomplete", followed
+    /// in the last case by the missing kinds of files. No output file
+    /// is written.
+    #[clap(long, action)]
+    readiness: bool,
+
+    /// Extract one region's regional files and title logo TPL to the
+    /// output directory, instead of converting the input file
+    ///
+    /// The files are given names without the region in them (e.g.
+    /// "openingTitle.brlyt"), so edits don't depend on which region's
+    /// copy they were made to. The output directory defaults to the
+    /// input file's directory. Putting edited files back into the arc
+    /// isn't supported yet.
+    #[clap(long, value_parser, value_name = "REGION")]
+    extract_region: Option<String>,
+
+    /// After converting, print the output file's size both as-is and
+    /// Yaz0-compressed
+    ///
+    /// Only the sizes are printed; the output file itself is still
+    /// written uncompressed.
+    #[clap(long, action)]
+    compare_compression: bool,
+
+    /// Look for regional files throughout the whole arc if they aren't
+    /// in the anim or blyt folder
+    ///
+    /// This is for edited arcs that keep regional files in other
+    /// folders (e.g. a subfolder per region). Only files with exactly
+    /// the expected names are found. Each file found this way is
+    /// printed, and stays in the same folder in the output.
+    #[clap(long, action)]
+    deep_search: bool,
+
+    /// Leave the timg folder exactly as it is
+    ///
+    /// For arcs with a custom logo. Normally, when converting to C, the
+    /// shared P/E logo is also added as China's logo if China doesn't
+    /// have one. The brlans and brlyts are still converted as usual.
+    #[clap(long, action)]
+    keep_logo: bool,
+
+    /// Print how long each step of the conversion took
+    ///
+    /// Each step is printed with its wall-clock duration as soon as it
+    /// finishes, to help find which one dominates for a large arc.
+    #[clap(long, action)]
+    timings: bool,
+
+    /// Also write the output to this path, and check that both copies
+    /// read back identically
+    ///
+    /// The output is converted in memory once, and then written to
+    /// both paths. smallworld fails if the two files differ afterward,
+    /// which would point to a problem while writing them.
+    #[clap(
+        long,
+        value_parser,
+        value_name = "PATH",
+        conflicts_with = "split-regions"
+    )]
+    also_verify_to: Option<PathBuf>,
+
+    /// Write a separate single-region arc for each region in `--to`,
+    /// instead of one arc containing all of them
+    ///
+    /// The output files are named after the input file, with the region
+    /// letter appended (e.g. "openingTitle_J.arc"), and are written to
+    /// the directory given by `-o`.
+    #[clap(long, action)]
+    split_regions: bool,
+
+    /// Print where each file's data would be placed in the output, and
+    /// exit without writing anything
+    ///
+    /// Each file is listed with its offset in the new data table and
+    /// its size, in order of offset, followed by the total data size.
+    /// Files that share data are listed at the same offset. All other
+    /// conversion options are applied as usual.
+    #[clap(long, action)]
+    print_layout: bool,
+
+    /// Print the predicted output size for each region in `--to` by
+    /// itself, each pair of them, and all of them together, and exit
+    /// without writing anything
+    ///
+    /// Each size is also given as a percentage of the size with all of
+    /// the `--to` regions, to show how much leaving regions out would
+    /// save. All other conversion options (except `--max-size`) are
+    /// applied as usual.
+    #[clap(long, action)]
+    size_matrix: bool,
+
+    /// Build a new openingTitle.arc for the `--to` regions from loose
+    /// files in `--data-dir`, instead of converting an existing one
+    ///
+    /// The regional files are looked up by name, either directly in the
+    /// data directory or in "anim" and "blyt" subfolders. Every ".tpl"
+    /// file found in the data directory (or a "timg" subfolder) is
+    /// included too. The positional filename is the output file.
+    #[clap(long, action, requires = "data-dir")]
+    template: bool,
+
+    /// The directory to read files from, for `--template`
+    #[clap(long, value_parser, requires = "template")]
+    data_dir: Option<PathBuf>,
+
+    /// After converting, print a summary table of the conversion
+    ///
+    /// This lists which region each regional file's data came from,
+    /// any filenames that were overwritten, the sizes of the new FNT
+    /// and FAT (and how much of the FAT is padding), and the CRC32 of
+    /// the output file.
+    #[clap(long, action)]
+    report: bool,
+
+    /// Perform the conversion, including checking for conflicts, but
+    /// don't write the output file
+    ///
+    /// Any conflicts are printed (or fail the conversion) as usual.
+    /// With `--report`, the full report is printed too; since that
+    /// includes the output's CRC32, the output is still built, but only
+    /// in memory, so this needs enough memory to hold all of it.
+    #[clap(
+        long,
+        action,
+        conflicts_with_all = &["split-regions", "also-verify-to", "compare-compression"]
+    )]
+    dry_run: bool,
+
+    /// Print the filenames that each of the given regions expects in
+    /// openingTitle.arc, and exit
+    ///
+    /// Regions are given in the same format as `--from` and `--to`, and
+    /// default to "all". No input file is needed.
+    #[clap(long, value_parser, value_name = "REGIONS")]
+    print_filenames: Option<Option<String>>,
+
+    /// Read default values for `--from`, `--to` and how to handle
+    /// conflicts from this TOML file [default: "smallworld.toml" in the
+    /// current directory, if it exists]
+    ///
+    /// Options given on the command line always take precedence over
+    /// the config file, which takes precedence over the built-in
+    /// defaults. The file can contain `from` and `to` (lists of
+    /// regions, e.g. `from = ["E", "J"]`), and `conflict_strategy`,
+    /// which can be "Fail" (the default), "Overwrite" (like
+    /// `--ignore-conflicts`) or "Callback" (like `--interactive`).
+    /// "Callback" acts like "Fail" when the input is a directory.
+    #[clap(long, value_parser, value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    // Hidden because it's mostly useful for smallworld's own
+    // development: checks the built-in filename table for internal
+    // consistency, and then (if given) that the input file (which
+    // should be a known-good, complete region-free arc) contains every
+    // filename smallworld expects, to catch typos in the table
+    #[clap(long, action, hide = true)]
+    self_test: bool,
+
     #[clap(flatten)]
     verbose: clap_verbosity_flag::Verbosity,
 }
 
+/// The config file that's read if `--config` isn't given.
+const DEFAULT_CONFIG_FILENAME: &str = "smallworld.toml";
+
+/// Default values for some arguments, read from a config file. See
+/// `--config`.
+#[derive(serde::Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    from: Option<Vec<lib::Region>>,
+    to: Option<Vec<lib::Region>>,
+    conflict_strategy: Option<lib::ConflictStrategy>,
+}
+
+impl Config {
+    /// Read the config file given by `--config`, or the default one if
+    /// that isn't given and it exists. Returns an empty `Config` if
+    /// there's no config file to read.
+    fn load(filepath: Option<&Path>) -> Result<Self> {
+        let filepath = match filepath {
+            Some(filepath) => filepath,
+            None if Path::new(DEFAULT_CONFIG_FILENAME).is_file() => {
+                Path::new(DEFAULT_CONFIG_FILENAME)
+            }
+            None => return Ok(Self::default()),
+        };
+        debug!("Reading config file {filepath:?}");
+
+        let text = std::fs::read_to_string(filepath)
+            .with_context(|| format!("couldn't read config file \"{}\"", filepath.display()))?;
+        toml::from_str(&text)
+            .with_context(|| format!("couldn't parse config file \"{}\"", filepath.display()))
+    }
+
+    /// Fill in the arguments that weren't given on the command line.
+    fn apply(self, args: &mut Args, matches: &clap::ArgMatches) {
+        let on_command_line =
+            |id| matches.value_source(id) == Some(clap::parser::ValueSource::CommandLine);
+        let region_list = |regions: Vec<lib::Region>| {
+            regions
+                .into_iter()
+                .map(<&str>::from)
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+
+        if let Some(regions) = self.from.filter(|_| !on_command_line("from")) {
+            args.from = region_list(regions);
+        }
+        if let Some(regions) = self.to.filter(|_| !on_command_line("to")) {
+            args.to = region_list(regions);
+        }
+        if !on_command_line("ignore-conflicts") && !on_command_line("interactive") {
+            match self.conflict_strategy {
+                Some(lib::ConflictStrategy::Overwrite) => args.ignore_conflicts = true,
+                Some(lib::ConflictStrategy::Callback) => {
+                    args.interactive = true;
+                    args.interactive_from_config = true;
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Values for the `--sort` argument.
+#[derive(clap::ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
+enum SortOrder {
+    Nintendo,
+    Insertion,
+    None,
+}
+
+impl From<SortOrder> for lib::u8_fnt::FntOrder {
+    fn from(order: SortOrder) -> Self {
+        match order {
+            SortOrder::Nintendo => Self::Nintendo,
+            SortOrder::Insertion => Self::Insertion,
+            SortOrder::None => Self::Unsorted,
+        }
+    }
+}
+
 /// Read a string containing a list of regions (e.g. `"P,J,C"`) or
 /// `"all"` (meaning all regions, in the default order), and return a
 /// `Vec` with the `Region`s.
@@ -108,110 +528,943 @@ impl<T: Seek + Read> SeekRead for T {}
 pub trait SeekWrite: Seek + Write {}
 impl<T: Seek + Write> SeekWrite for T {}
 
+/// Build the path of the temporary file that
+/// `write_output_via_temporary_file()` writes to before replacing the
+/// output file: the output path with a suffix appended.
+fn temporary_output_filepath(output_filepath: &Path) -> PathBuf {
+    let mut filepath = output_filepath.as_os_str().to_owned();
+    filepath.push(".smallworld-tmp");
+    PathBuf::from(filepath)
+}
+
+/// Run a function that writes an output file. The output is written to
+/// a temporary file next to the output file, which is then moved over
+/// the output file, so an existing output file is left as-is if the
+/// function fails.
+fn write_output_via_temporary_file(
+    output_filepath: &Path,
+    write_function: impl FnOnce(&mut File) -> Result<()>,
+) -> Result<()> {
+    // Create a temporary file next to the output file (so that they're
+    // on the same filesystem, and the final rename is cheap)
+    let tmp_filepath = temporary_output_filepath(output_filepath);
+    trace!("Writing output to temporary file {tmp_filepath:?}");
+    let mut tmp_file = File::create(&tmp_filepath).with_context(|| {
+        format!(
+            "couldn't create temporary output file \"{}\"",
+            tmp_filepath.display()
+        )
+    })?;
+
+    // Write to it
+    let result = write_function(&mut tmp_file);
+
+    // Close it before touching the output path (Windows won't replace
+    // a file that's still open)
+    drop(tmp_file);
+
+    // Move it over the output file, or clean up if writing failed
+    if let Err(error) = result {
+        let _ = std::fs::remove_file(&tmp_filepath);
+        return Err(error);
+    }
+    std::fs::rename(&tmp_filepath, output_filepath).with_context(|| {
+        let _ = std::fs::remove_file(&tmp_filepath);
+        format!(
+            "couldn't replace output file \"{}\"",
+            output_filepath.display()
+        )
+    })?;
+
+    Ok(())
+}
+
 /// Run a function that reads from one file-path and writes to another,
-/// efficiently. Will write directly to the output file if the two paths
-/// are distinct; otherwise it will buffer the output data in memory and
-/// write it into the file afterward.
+/// using `write_output_via_temporary_file()`, so this works even if the
+/// two paths are the same file.
+///
+/// Neither the input nor the output is ever held in memory in full, so
+/// peak memory usage depends only on what the conversion function
+/// itself needs (for region conversion, that's roughly the size of the
+/// largest single file in the arc).
 pub fn run_file_conversion_function(
     input_filepath: &Path,
     output_filepath: &Path,
     conversion_function: impl Fn(&mut dyn SeekRead, &mut dyn SeekWrite) -> Result<()>,
 ) -> Result<()> {
     // Open input file
-    let mut in_file = File::open(&input_filepath)
+    let mut in_file = File::open(input_filepath)
         .with_context(|| format!("couldn't open input file \"{}\"", input_filepath.display()))?;
 
-    // If the output file can be proven distinct from the input file,
-    // we can optimize by writing our output to it directly
-    let paths_definitely_distinct = if let Ok(input_canon) = input_filepath.canonicalize() {
-        if let Ok(output_canon) = output_filepath.canonicalize() {
-            input_canon != output_canon
-        } else {
-            false
+    // (The input file is moved into the closure, so that it's closed
+    // before the output file is replaced, too)
+    write_output_via_temporary_file(output_filepath, move |tmp_file| {
+        conversion_function(&mut in_file, tmp_file)
+    })
+}
+
+/// Build the filepath for one region's output file in `--split-regions`
+/// mode: the input filename, with `_` and the region letter appended to
+/// its stem, in `output_dir`.
+fn split_region_output_filepath(
+    input_filepath: &Path,
+    output_dir: &Path,
+    region: lib::Region,
+) -> PathBuf {
+    let mut filename = input_filepath.file_stem().unwrap_or_default().to_owned();
+    filename.push("_");
+    filename.push(<&str>::from(region));
+    if let Some(extension) = input_filepath.extension() {
+        filename.push(".");
+        filename.push(extension);
+    }
+    output_dir.join(filename)
+}
+
+/// Read a U8 file's FNT, print every structural problem found in it,
+/// and return `Err` if there were any.
+fn validate_file(input_filepath: &Path) -> Result<()> {
+    let mut in_file = File::open(input_filepath)
+        .with_context(|| format!("couldn't open input file \"{}\"", input_filepath.display()))?;
+
+    let (fnt, _) = lib::u8_fnt::read(&mut in_file).context("invalid U8 file")?;
+
+    let issues = fnt.validate();
+    for issue in &issues {
+        println!("{issue}");
+    }
+    let mut num_problems = issues.len();
+
+    match lib::u8_fnt::check_data_table_offset(&mut in_file) {
+        Ok(()) => (),
+        Err(err @ lib::ParseU8Error::DataTableOffsetMismatch { .. }) => {
+            println!("/: {err}");
+            num_problems += 1;
+        }
+        Err(err) => return Err(err).context("invalid U8 file"),
+    }
+
+    if num_problems > 0 {
+        bail!("found {num_problems} structural problem(s)");
+    }
+
+    println!("No problems found");
+    Ok(())
+}
+
+/// Read a U8 file's header, and print its fields.
+fn dump_header(input_filepath: &Path) -> Result<()> {
+    let mut in_file = File::open(input_filepath)
+        .with_context(|| format!("couldn't open input file \"{}\"", input_filepath.display()))?;
+
+    let header = lib::u8_fnt::read_header(&mut in_file).context("couldn't read U8 header")?;
+
+    println!("Magic: {:#010x}", header.magic);
+    if header.magic != lib::u8_fnt::U8_MAGIC {
+        println!("    (expected {:#010x})", lib::u8_fnt::U8_MAGIC);
+    }
+    println!("Root node offset: {:#x}", header.root_node_offs);
+    println!(
+        "Header size: {:#x} (ends at {:#x})",
+        header.header_size,
+        header.end_of_header()
+    );
+    println!("Data table offset: {:#x}", header.data_table_offs);
+    println!("Node count: {}", header.node_count);
+    println!("Reserved: {:02x?}", header.reserved);
+
+    // The implied size needs the whole FNT, so it's only printed if
+    // that can be read
+    let options = lib::u8_fnt::ReadOptions {
+        lenient_magic: true,
+        lenient_strings: true,
+        allow_truncated: true,
+    };
+    match lib::u8_fnt::read_parsed_arc_with_options(&mut in_file, &options) {
+        Ok(arc) => {
+            let actual_size = in_file
+                .seek(std::io::SeekFrom::End(0))
+                .context("couldn't get input file size")?;
+            println!(
+                "Implied size: {:#x} (actual: {actual_size:#x})",
+                arc.implied_size()
+            );
         }
+        Err(error) => debug!("Couldn't read the FNT: {error}"),
+    }
+    Ok(())
+}
+
+/// Run heuristic health checks on an openingTitle.arc, and print any
+/// warnings. Warnings alone never cause `Err` to be returned.
+fn check_health(input_filepath: &Path) -> Result<()> {
+    let in_file = File::open(input_filepath)
+        .with_context(|| format!("couldn't open input file \"{}\"", input_filepath.display()))?;
+
+    let warnings =
+        lib::check_openingtitle_health(in_file).context("couldn't check the input file")?;
+
+    for warning in &warnings {
+        println!("warning: {warning}");
+    }
+    if warnings.is_empty() {
+        println!("No health warnings");
+    }
+    Ok(())
+}
+
+/// Print whether openingTitle.arc is already region-free, and exit with
+/// status 1 if it isn't.
+fn is_region_free(input_filepath: &Path) -> Result<()> {
+    let mut in_file = File::open(input_filepath)
+        .with_context(|| format!("couldn't open input file \"{}\"", input_filepath.display()))?;
+
+    let region_free = lib::is_region_free(&mut in_file).context("couldn't check the input file")?;
+
+    if region_free {
+        println!("yes");
+        Ok(())
     } else {
-        false
+        println!("no");
+        std::process::exit(1);
+    }
+}
+
+/// Ask the user (on stderr) how to resolve a conflict, for
+/// `--interactive`. Returns `None` if they choose to abort, or if
+/// standard input is closed.
+fn prompt_for_conflict_resolution(conflict: &lib::ConflictInfo) -> Option<lib::ConflictResolution> {
+    let (keep_a, replace_a) = match conflict.kind {
+        lib::ConflictKind::FileContents => {
+            eprintln!(
+                "{:?} from region {} and {:?} from region {} have different data.",
+                conflict.file_a, conflict.region_a, conflict.file_b, conflict.region_b
+            );
+            (
+                format!("keep {:?}", conflict.file_a),
+                format!("use {:?} instead", conflict.file_b),
+            )
+        }
+        lib::ConflictKind::Filename => {
+            eprintln!(
+                "{:?} already exists (needed for region {}).",
+                conflict.file_a, conflict.region_a
+            );
+            (
+                "keep the existing file".to_owned(),
+                "overwrite it".to_owned(),
+            )
+        }
     };
-    trace!(
-        "Paths {} definitely distinct",
-        if paths_definitely_distinct {
-            "are"
-        } else {
-            "are NOT"
+
+    loop {
+        eprint!("[1] {keep_a}, [2] {replace_a}, or [a]bort? ");
+        let mut answer = String::new();
+        if std::io::stdin().read_line(&mut answer).ok()? == 0 {
+            return None;
         }
+        match answer.trim().to_lowercase().as_str() {
+            "1" => return Some(lib::ConflictResolution::KeptA),
+            "2" => return Some(lib::ConflictResolution::ReplacedA),
+            "a" | "abort" => return None,
+            _ => eprintln!("Please enter 1, 2, or a."),
+        }
+    }
+}
+
+/// Print the size of a file, both as-is and Yaz0-compressed.
+fn print_compression_comparison(filepath: &Path) -> Result<()> {
+    let data = std::fs::read(filepath)
+        .with_context(|| format!("couldn't read output file \"{}\"", filepath.display()))?;
+    let compressed_len = lib::yaz0::compress(&data).len();
+
+    println!("Uncompressed: {:#x} bytes", data.len());
+    println!(
+        "Yaz0:         {compressed_len:#x} bytes ({:.1}%)",
+        compressed_len as f64 * 100.0 / data.len().max(1) as f64
     );
+    Ok(())
+}
 
-    if paths_definitely_distinct {
-        // We believe that the input and output files are different, so
-        // it should be safe to open the output file before we've read
-        // the input file.
+/// Print whether openingTitle.arc is region-free, convertible, or
+/// incomplete.
+fn readiness(input_filepath: &Path) -> Result<()> {
+    let mut in_file = File::open(input_filepath)
+        .with_context(|| format!("couldn't open input file \"{}\"", input_filepath.display()))?;
 
-        // Open the output file
-        let mut out_file = File::create(&output_filepath).with_context(|| {
-            format!(
-                "couldn't open output file \"{}\"",
-                output_filepath.display()
-            )
+    let (fnt, _) = lib::u8_fnt::read(&mut in_file).context("couldn't read the input file")?;
+    match lib::region_free_readiness(&fnt).context("couldn't check the input file")? {
+        lib::RegionFreeStatus::RegionFree => println!("region-free"),
+        lib::RegionFreeStatus::Convertible => println!("convertible"),
+        lib::RegionFreeStatus::Incomplete(missing) => {
+            println!("incomplete (missing: {})", missing.join(", "));
+        }
+    }
+    Ok(())
+}
+
+/// Write one region's regional files and title logo TPL from a U8
+/// file to a directory, under their generic names.
+fn extract_region(
+    input_filepath: &Path,
+    output_dirpath: Option<&Path>,
+    region: &str,
+) -> Result<()> {
+    let region = region
+        .parse::<lib::Region>()
+        .context("couldn't read `--extract-region`")?;
+
+    let mut in_file = File::open(input_filepath)
+        .with_context(|| format!("couldn't open input file \"{}\"", input_filepath.display()))?;
+    let files = lib::extract_region_files(&mut in_file, region)
+        .with_context(|| format!("couldn't extract region {region}'s files"))?;
+
+    let output_dirpath = match output_dirpath {
+        Some(dirpath) => dirpath,
+        None => input_filepath.parent().unwrap_or_else(|| Path::new("")),
+    };
+    std::fs::create_dir_all(output_dirpath).with_context(|| {
+        format!(
+            "couldn't create output directory \"{}\"",
+            output_dirpath.display()
+        )
+    })?;
+
+    for (filename, data) in files {
+        let filepath = output_dirpath.join(filename);
+        std::fs::write(&filepath, data)
+            .with_context(|| format!("couldn't write \"{}\"", filepath.display()))?;
+        println!("Extracted {}", filepath.display());
+    }
+    Ok(())
+}
+
+/// Check that the SHA-256 of an entire file matches an expected value,
+/// given as a hexadecimal string.
+fn verify_input_sha(input_filepath: &Path, expected: &str) -> Result<()> {
+    let expected = expected.trim().to_lowercase();
+    if expected.len() != 64 || !expected.bytes().all(|b| b.is_ascii_hexdigit()) {
+        bail!("`--expect-sha` must be a SHA-256 hash (64 hexadecimal digits)");
+    }
+
+    let mut in_file = File::open(input_filepath)
+        .with_context(|| format!("couldn't open input file \"{}\"", input_filepath.display()))?;
+    let actual: String = lib::sha256(&mut std::io::BufReader::new(&mut in_file))
+        .context("couldn't hash the input file")?
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    debug!("Input SHA-256: {actual}");
+
+    if actual != expected {
+        bail!("input file's SHA-256 is {actual}, but `--expect-sha` expected {expected}");
+    }
+    Ok(())
+}
+
+/// Check the built-in filename table for internal consistency, and, if
+/// an input file is given, check that it (a known-good region-free
+/// openingTitle.arc) contains every regional filename smallworld
+/// expects. Print any problems, and return `Err` if there were any.
+fn self_test(input_filepath: Option<&Path>) -> Result<()> {
+    let problems = lib::check_filename_table();
+    for problem in &problems {
+        println!("{problem}");
+    }
+    if !problems.is_empty() {
+        bail!(
+            "self-test failed: {} problem(s) found in the filename table",
+            problems.len()
+        );
+    }
+
+    let Some(input_filepath) = input_filepath else {
+        println!("Self-test passed");
+        return Ok(());
+    };
+
+    let in_file = File::open(input_filepath)
+        .with_context(|| format!("couldn't open input file \"{}\"", input_filepath.display()))?;
+
+    let missing = lib::find_missing_regional_filenames(in_file, lib::RegionBitFlags::ALL)
+        .context("couldn't check the input file")?;
+
+    for (region, filename) in &missing {
+        println!("{region}: {filename}");
+    }
+    if !missing.is_empty() {
+        bail!(
+            "self-test failed: {} expected filename(s) not found",
+            missing.len()
+        );
+    }
+
+    println!("Self-test passed");
+    Ok(())
+}
+
+/// Find the file for a path within a template arc (e.g.
+/// "/arc/anim/openingTitle_US_00_inPress.brlan") in the data
+/// directory, either at the same relative path (without the "arc"
+/// folder) or directly in the data directory.
+fn find_template_file(data_dir: &Path, arc_path: &str) -> Option<PathBuf> {
+    let relative_path = arc_path.trim_start_matches("/arc/");
+    let filename = relative_path.rsplit('/').next().unwrap_or(relative_path);
+    [data_dir.join(relative_path), data_dir.join(filename)]
+        .into_iter()
+        .find(|filepath| filepath.is_file())
+}
+
+/// List the filenames of the TPLs in the data directory and its "timg"
+/// subfolder, for `--template`.
+fn find_template_tpls(data_dir: &Path) -> Result<Vec<String>> {
+    let mut filenames = Vec::new();
+    for dir in [data_dir.to_owned(), data_dir.join("timg")] {
+        if !dir.is_dir() {
+            continue;
+        }
+        for entry in std::fs::read_dir(&dir)
+            .with_context(|| format!("couldn't read directory \"{}\"", dir.display()))?
+        {
+            let filename = entry?.file_name().to_string_lossy().into_owned();
+            if filename.to_lowercase().ends_with(".tpl") && !filenames.contains(&filename) {
+                filenames.push(filename);
+            }
+        }
+    }
+    filenames.sort();
+    Ok(filenames)
+}
+
+/// Build a new openingTitle.arc from the files in a data directory.
+fn build_template(
+    data_dir: &Path,
+    output_filepath: &Path,
+    to_regions: lib::RegionBitFlags,
+) -> Result<()> {
+    let tpl_filenames = find_template_tpls(data_dir)?;
+    let tpl_filenames: Vec<&str> = tpl_filenames.iter().map(String::as_str).collect();
+
+    let open_file = |arc_path: &str| {
+        let Some(filepath) = find_template_file(data_dir, arc_path) else {
+            return Ok(None);
+        };
+        debug!("Using {filepath:?} for {arc_path}");
+        let file = File::open(filepath)?;
+        let size = file.metadata()?.len().try_into().map_err(|_| {
+            std::io::Error::new(std::io::ErrorKind::InvalidData, "file is too large")
         })?;
+        Ok(Some((file, size)))
+    };
+
+    write_output_via_temporary_file(output_filepath, |out_file| {
+        lib::build_openingtitle_template(out_file, to_regions, &tpl_filenames, open_file)
+            .context("failed to build openingTitle.arc")
+    })
+}
+
+/// Print the renames that the `normalize_names` conversion option will
+/// perform on an openingTitle.arc.
+fn print_normalizations(input_filepath: &Path) -> Result<()> {
+    let in_file = File::open(input_filepath)
+        .with_context(|| format!("couldn't open input file \"{}\"", input_filepath.display()))?;
+
+    let normalizations = lib::find_nonstandard_regional_filenames(in_file)
+        .context("couldn't check the input file for nonstandard filenames")?;
+
+    for normalization in &normalizations {
+        println!("Renaming {normalization}");
+    }
+    Ok(())
+}
+
+/// Log how many regional filenames converting the input file will add
+/// and remove.
+fn log_filename_delta(
+    input_filepath: &Path,
+    from_regions: lib::RegionBitFlags,
+    to_regions: lib::RegionBitFlags,
+) -> Result<()> {
+    let mut in_file = File::open(input_filepath)
+        .with_context(|| format!("couldn't open input file \"{}\"", input_filepath.display()))?;
+    let (fnt, _) = lib::u8_fnt::read(&mut in_file)?;
+    let (added, removed) = lib::filename_delta(&fnt, from_regions, to_regions)?;
+    info!("This will add {added} filename(s) and remove {removed}");
+    Ok(())
+}
 
-        // Write directly to it
-        conversion_function(&mut in_file, &mut out_file)?;
+/// Write a second copy of the output to `verify_filepath`, then read
+/// both files back and check that they match each other and `data`.
+fn write_verification_copy(
+    output_filepath: &Path,
+    verify_filepath: &Path,
+    data: &[u8],
+) -> Result<()> {
+    std::fs::write(verify_filepath, data).with_context(|| {
+        format!(
+            "couldn't write verification copy \"{}\"",
+            verify_filepath.display()
+        )
+    })?;
+
+    let output = std::fs::read(output_filepath).with_context(|| {
+        format!(
+            "couldn't read output file \"{}\"",
+            output_filepath.display()
+        )
+    })?;
+    let copy = std::fs::read(verify_filepath).with_context(|| {
+        format!(
+            "couldn't read verification copy \"{}\"",
+            verify_filepath.display()
+        )
+    })?;
+    if output != copy || output != data {
+        bail!(
+            "output file \"{}\" and verification copy \"{}\" differ",
+            output_filepath.display(),
+            verify_filepath.display()
+        );
+    }
+    info!("Verification copy matches the output file");
+    Ok(())
+}
+
+/// Print how long a conversion step took, for `--timings`.
+fn print_step_timing(name: &'static str, duration: Duration) {
+    println!("{name:<24}{:>10.3} ms", duration.as_secs_f64() * 1000.0);
+}
+
+/// Print every file in a FNT as a path, offset and size, in order of
+/// offset, followed by the total size of the file data.
+fn print_fnt_layout(fnt: &lib::u8_fnt::U8Node) {
+    /// Helper function to collect the files in a node, recursively
+    fn collect_files<'a>(
+        node: &'a lib::u8_fnt::U8Node,
+        path: &str,
+        files: &mut Vec<(String, &'a lib::u8_fnt::U8FileNode)>,
+    ) {
+        for (name, child) in node.iter() {
+            let child_path = format!("{path}/{name}");
+            match child {
+                lib::u8_fnt::U8Node::File(file) => files.push((child_path, file)),
+                lib::u8_fnt::U8Node::Folder(_) => collect_files(child, &child_path, files),
+            }
+        }
+    }
+
+    let mut files = Vec::new();
+    collect_files(fnt, "", &mut files);
+    files.sort_by_key(|(_, file)| file.offset);
+
+    for (path, file) in &files {
+        println!("{:#010x} {:#010x} {path}", file.offset, file.size);
+    }
+    let total_size = files
+        .iter()
+        .map(|(_, file)| file.offset + file.size)
+        .max()
+        .unwrap_or(0);
+    println!("Total data size: {total_size:#x}");
+}
+
+/// Print a summary of the conflicts that were resolved during a
+/// conversion, after any regional files found by `--deep-search`. The
+/// individual conflicts are logged at the warning level, so they're
+/// shown with `-v`.
+fn print_conversion_report(report: &lib::ConversionReport) {
+    for path in &report.found_elsewhere {
+        println!("Found {path}");
+    }
+    if report.conflicts.is_empty() {
+        return;
+    }
+    for conflict in &report.conflicts {
+        warn!("{conflict}");
+    }
+    println!("Resolved {} conflict(s)", report.conflicts.len());
+}
+
+/// Print a conversion report as a table, for `--report`. The output
+/// file is read back in to calculate its CRC32.
+fn print_report_table(report: &lib::ConversionReport, output_filepath: &Path) -> Result<()> {
+    let output = std::fs::read(output_filepath).with_context(|| {
+        format!(
+            "couldn't read output file \"{}\"",
+            output_filepath.display()
+        )
+    })?;
+    print_report_table_for_output(report, &output);
+    Ok(())
+}
+
+/// Print a conversion report as a table, given the complete output
+/// file's data.
+fn print_report_table_for_output(report: &lib::ConversionReport, output: &[u8]) {
+    println!("{:<16} Source region", "Regional file");
+    for (kind, region) in &report.sources {
+        println!("{kind:<16} {region}");
+    }
+
+    let overwritten: Vec<_> = report
+        .conflicts
+        .iter()
+        .filter(|conflict| conflict.kind == lib::ConflictKind::Filename)
+        .collect();
+    if overwritten.is_empty() {
+        println!("Overwritten filenames: none");
     } else {
-        // The input and output files may be the same.
+        println!("Overwritten filenames:");
+        for conflict in overwritten {
+            println!("    {} ({})", conflict.file_a, conflict.region_a);
+        }
+    }
+
+    println!("FNT size:     {:#x}", report.fnt_size);
+    println!("FAT size:     {:#x}", report.fat_size);
+    println!("FAT padding:  {:#x}", report.fat_padding);
+    println!("Output CRC32: {:#010x}", lib::crc32(output));
+}
+
+/// Print a table of the predicted output size for each single region in
+/// `to_regions`, each pair of them, and all of them together, for
+/// `--size-matrix`. Each prediction is a dry run of the conversion.
+fn print_size_matrix(
+    input_filepath: &Path,
+    from_regions: Option<&[lib::Region]>,
+    to_regions: lib::RegionBitFlags,
+    conflict_strategies: &lib::ConvertOpeningTitleBetweenRegionsConflictStrategies,
+    options: &lib::ConvertOpeningTitleBetweenRegionsOptions,
+) -> Result<()> {
+    let regions: Vec<lib::Region> = to_regions.iter().collect();
+    let mut subsets: Vec<lib::RegionBitFlags> = regions.iter().map(|&r| r.into()).collect();
+    for (i, &a) in regions.iter().enumerate() {
+        for &b in &regions[i + 1..] {
+            subsets.push(a | b);
+        }
+    }
+    if regions.len() > 2 {
+        subsets.push(to_regions);
+    }
 
-        // Create a buffer in memory
-        let mut buf: Cursor<Vec<u8>> = Cursor::new(Vec::new());
+    let predict_size = |subset: lib::RegionBitFlags| -> Result<u64> {
+        let in_file = File::open(input_filepath).with_context(|| {
+            format!("couldn't open input file \"{}\"", input_filepath.display())
+        })?;
+        let report = lib::convert_openingtitle_between_regions_with_options(
+            in_file,
+            std::io::Cursor::new(Vec::new()),
+            from_regions,
+            subset,
+            conflict_strategies,
+            &lib::ConvertOpeningTitleBetweenRegionsOptions {
+                max_size: None,
+                dry_run: true,
+                ..*options
+            },
+            &lib::HashFileComparator,
+        )?;
+        Ok(report.fnt_size + report.fat_size)
+    };
+    let format_subset = |subset: lib::RegionBitFlags| {
+        subset
+            .iter()
+            .map(|region| region.to_string())
+            .collect::<Vec<_>>()
+            .join(",")
+    };
 
-        // Write to it
-        conversion_function(&mut in_file, &mut buf)?;
-        trace!(
-            "Buffered {:#x} bytes of output file data in memory",
-            buf.get_ref().len()
+    let full_size = predict_size(to_regions).context("failed to predict output size")?;
+    println!("{:<12} {:>12} {:>8}", "Regions", "Size", "Of all");
+    for subset in subsets {
+        let size = predict_size(subset).with_context(|| {
+            format!(
+                "failed to predict output size for {}",
+                format_subset(subset)
+            )
+        })?;
+        let percentage = size as f64 / full_size as f64 * 100.0;
+        println!(
+            "{:<12} {:>#12x} {:>7.1}%",
+            format_subset(subset),
+            size,
+            percentage
         );
+    }
+    Ok(())
+}
 
-        // Open the output file and write the buffer data to it
-        File::create(&output_filepath)
-            .with_context(|| {
-                format!(
-                    "couldn't open output file \"{}\"",
-                    output_filepath.display()
-                )
-            })?
-            .write_all(&buf.into_inner())
-            .context("couldn't write data to output file")?;
+/// Print the regional filenames for each of the given regions.
+fn print_filenames(regions: &[lib::Region]) {
+    for region in regions {
+        let filenames = region.filenames();
+        println!("{region}:");
+        println!("    anim/{}", filenames.in_press_brlan);
+        println!("    anim/{}", filenames.in_title_brlan);
+        println!("    anim/{}", filenames.loop_press_brlan);
+        println!("    anim/{}", filenames.out_press_brlan);
+        println!("    blyt/{}", filenames.brlyt);
     }
+}
+
+/// Build the conversion options selected by the command-line arguments.
+fn conversion_options<'a>(
+    args: &Args,
+    exclude: &'a [&'a str],
+    canonical_region: Option<lib::Region>,
+) -> lib::ConvertOpeningTitleBetweenRegionsOptions<'a> {
+    lib::ConvertOpeningTitleBetweenRegionsOptions {
+        min_regions: args.min_regions,
+        minimal: args.minimal,
+        strip_unselected: args.strip_unselected,
+        max_size: args.max_size,
+        normalize_names: args.normalize_names,
+        fnt_order: args.sort.into(),
+        canonical_region,
+        exclude,
+        pin_data_offset: args.pin_data_offset,
+        resolve_conflict: Some(&prompt_for_conflict_resolution),
+        deep_search: args.deep_search,
+        keep_logo: args.keep_logo,
+        step_timings: args
+            .timings
+            .then_some(&print_step_timing as lib::StepTimingsHook),
+        ..Default::default()
+    }
+}
+
+/// List the ".arc" files directly inside a directory, sorted by name.
+fn list_arc_files(dirpath: &Path) -> Result<Vec<PathBuf>> {
+    let entries = std::fs::read_dir(dirpath)
+        .with_context(|| format!("couldn't read input directory \"{}\"", dirpath.display()))?;
+    let mut filepaths = Vec::new();
+    for entry in entries {
+        let filepath = entry
+            .with_context(|| format!("couldn't read input directory \"{}\"", dirpath.display()))?
+            .path();
+        let is_arc = filepath
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("arc"));
+        if is_arc && filepath.is_file() {
+            filepaths.push(filepath);
+        }
+    }
+    filepaths.sort();
+    Ok(filepaths)
+}
+
+/// Convert every ".arc" file in a directory, using up to `--jobs`
+/// threads at once. The results are printed in the same order as the
+/// files, after all of them have been converted.
+fn convert_directory(
+    args: &Args,
+    input_dirpath: &Path,
+    from_regions: Option<&[lib::Region]>,
+    to_regions: lib::RegionBitFlags,
+    canonical_region: Option<lib::Region>,
+) -> Result<()> {
+    // A callback conflict strategy from the config file isn't an
+    // explicit request, so it just falls back to failing on conflicts
+    if args.interactive && args.interactive_from_config {
+        info!("Ignoring the config file's \"Callback\" conflict strategy for directory input");
+    }
+    let interactive = args.interactive && !args.interactive_from_config;
+
+    // These either only make sense for a single file, or would print
+    // output from several conversions interleaved
+    let unsupported = [
+        (args.split_regions, "--split-regions"),
+        (args.print_layout, "--print-layout"),
+        (args.size_matrix, "--size-matrix"),
+        (args.also_verify_to.is_some(), "--also-verify-to"),
+        (args.expect_sha.is_some(), "--expect-sha"),
+        (interactive, "--interactive"),
+        (args.timings, "--timings"),
+        (args.report, "--report"),
+        (args.dry_run, "--dry-run"),
+        (args.compare_compression, "--compare-compression"),
+    ];
+    if let Some((_, name)) = unsupported.iter().find(|(present, _)| *present) {
+        bail!("`{name}` can't be used when the input is a directory");
+    }
+
+    let input_filepaths = list_arc_files(input_dirpath)?;
+    if input_filepaths.is_empty() {
+        bail!(
+            "no \".arc\" files found in input directory \"{}\"",
+            input_dirpath.display()
+        );
+    }
+
+    let output_dirpath = args.output_file.as_deref().unwrap_or(input_dirpath);
+    debug!("Output directory: {output_dirpath:?}");
+    std::fs::create_dir_all(output_dirpath).with_context(|| {
+        format!(
+            "couldn't create output directory \"{}\"",
+            output_dirpath.display()
+        )
+    })?;
+
+    let conflict_strategy = if args.ignore_conflicts {
+        lib::ConflictStrategy::Overwrite
+    } else {
+        lib::ConflictStrategy::Fail
+    };
+    let conflict_strategies = lib::ConvertOpeningTitleBetweenRegionsConflictStrategies {
+        file_contents: conflict_strategy,
+        filenames: conflict_strategy,
+    };
+    let exclude: Vec<&str> = args.exclude.iter().map(String::as_str).collect();
+
+    let convert_file = |input_filepath: &Path| -> Result<lib::ConversionReport> {
+        // (The options hold references to hooks that can't be shared
+        // between threads, so each conversion builds its own)
+        let options = conversion_options(args, &exclude, canonical_region);
+        let output_filepath = output_dirpath.join(input_filepath.file_name().unwrap_or_default());
+        let last_report = RefCell::new(None);
+        run_file_conversion_function(input_filepath, &output_filepath, |in_file, out_file| {
+            let report = lib::convert_openingtitle_between_regions_with_options(
+                in_file,
+                out_file,
+                from_regions,
+                to_regions,
+                &conflict_strategies,
+                &options,
+                &lib::HashFileComparator,
+            )
+            .context("failed to perform region conversion")?;
+            last_report.replace(Some(report));
+            Ok(())
+        })?;
+        Ok(last_report
+            .into_inner()
+            .expect("conversion succeeded without a report"))
+    };
+
+    let jobs = args
+        .jobs
+        .or_else(|| std::thread::available_parallelism().ok())
+        .map_or(1, NonZeroUsize::get)
+        .min(input_filepaths.len());
+    debug!(
+        "Converting {} arc(s) with {jobs} job(s)",
+        input_filepaths.len()
+    );
 
+    // Each thread takes the next unclaimed file until there are none
+    // left, and the results are put back in order afterward
+    let next_index = AtomicUsize::new(0);
+    let mut results: Vec<_> = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..jobs)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut results = Vec::new();
+                    loop {
+                        let index = next_index.fetch_add(1, Ordering::Relaxed);
+                        let Some(input_filepath) = input_filepaths.get(index) else {
+                            break;
+                        };
+                        results.push((index, convert_file(input_filepath)));
+                    }
+                    results
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("conversion thread panicked"))
+            .collect()
+    });
+    results.sort_by_key(|(index, _)| *index);
+
+    let mut num_failed = 0;
+    for (input_filepath, (_, result)) in input_filepaths.iter().zip(results) {
+        match result {
+            Ok(report) => {
+                println!("Converted {}", input_filepath.display());
+                print_conversion_report(&report);
+            }
+            Err(error) => {
+                error!("{}: {error:#}", input_filepath.display());
+                num_failed += 1;
+            }
+        }
+    }
+    if num_failed > 0 {
+        bail!(
+            "failed to convert {num_failed} of {} arc(s)",
+            input_filepaths.len()
+        );
+    }
     Ok(())
 }
 
 /// Entry-point function (mainly deals with CLI-related logic)
 fn main() -> Result<()> {
-    let args = Args::parse();
+    let matches = Args::command().get_matches();
+    let mut args = Args::from_arg_matches(&matches)?;
 
     env_logger::Builder::new()
         .filter_level(args.verbose.log_level_filter())
         .init();
 
-    let input_filepath = &args.input_file;
+    Config::load(args.config.as_deref())?.apply(&mut args, &matches);
+
+    if let Some(regions) = &args.print_filenames {
+        let regions = read_region_list_str(regions.as_deref().unwrap_or("all"))
+            .context("couldn't read `--print-filenames` region list")?;
+        print_filenames(&regions);
+        return Ok(());
+    }
+    if args.self_test {
+        return self_test(args.input_file.as_deref());
+    }
+
+    // Clap ensures this is present if neither `--print-filenames` nor
+    // `--self-test` is
+    let input_filepath = match &args.input_file {
+        Some(filepath) => filepath,
+        None => bail!("no input file specified"),
+    };
     debug!(
         "Input filepath: {:?} ({:?})",
         input_filepath,
         input_filepath.canonicalize()
     );
 
-    // If not specified, default output path is the input path (i.e. overwrite)
-    let output_filepath = match &args.output_file {
-        Some(filename) => filename,
-        None => &args.input_file,
-    };
-    debug!(
-        "Output filepath: {:?} ({:?})",
-        output_filepath,
-        output_filepath.canonicalize()
-    );
+    if args.validate {
+        return validate_file(input_filepath);
+    }
+    if args.dump_header {
+        return dump_header(input_filepath);
+    }
+    if args.check_health {
+        return check_health(input_filepath);
+    }
+    if args.is_region_free {
+        return is_region_free(input_filepath);
+    }
+    if args.readiness {
+        return readiness(input_filepath);
+    }
+    if let Some(region) = &args.extract_region {
+        return extract_region(input_filepath, args.output_file.as_deref(), region);
+    }
 
     let from_regions =
         read_region_list_str(&args.from).context("couldn't read `--from` region list")?;
+    let canonical_region = args
+        .canonical_region
+        .as_deref()
+        .map(str::parse::<lib::Region>)
+        .transpose()
+        .context("couldn't read `--canonical-region`")?;
+    if let Some(region) = canonical_region {
+        if !from_regions.contains(&region) {
+            bail!("`--canonical-region` must be one of the `--from` regions");
+        }
+    }
+    let from_region_flags = lib::RegionBitFlags::from_iter(from_regions.iter().copied());
     let from_regions = Some(&from_regions as &[lib::Region]);
 
     let to_regions = lib::RegionBitFlags::from_iter(
@@ -223,8 +1476,34 @@ fn main() -> Result<()> {
         bail!("must select at least one output region");
     }
 
+    // Clap ensures that `--data-dir` is present if and only if
+    // `--template` is
+    if let Some(data_dir) = &args.data_dir {
+        return build_template(data_dir, input_filepath, to_regions);
+    }
+
+    if input_filepath.is_dir() {
+        return convert_directory(
+            &args,
+            input_filepath,
+            from_regions,
+            to_regions,
+            canonical_region,
+        );
+    }
+
+    if let Some(expected) = &args.expect_sha {
+        verify_input_sha(input_filepath, expected)?;
+    }
+
+    let interactive = args.interactive && std::io::stdin().is_terminal();
+    if args.interactive && !interactive {
+        warn!("Standard input isn't a terminal, so `--interactive` has no effect");
+    }
     let conflict_strategy = if args.ignore_conflicts {
         lib::ConflictStrategy::Overwrite
+    } else if interactive {
+        lib::ConflictStrategy::Callback
     } else {
         lib::ConflictStrategy::Fail
     };
@@ -232,17 +1511,195 @@ fn main() -> Result<()> {
         file_contents: conflict_strategy,
         filenames: conflict_strategy,
     };
+    let exclude: Vec<&str> = args.exclude.iter().map(String::as_str).collect();
+    let options = conversion_options(&args, &exclude, canonical_region);
 
-    run_file_conversion_function(input_filepath, output_filepath, |in_file, out_file| {
-        lib::convert_openingtitle_between_regions(
+    if args.normalize_names {
+        print_normalizations(input_filepath)?;
+    }
+
+    if args.print_layout {
+        let in_file = File::open(input_filepath).with_context(|| {
+            format!("couldn't open input file \"{}\"", input_filepath.display())
+        })?;
+        let print_layout = |stage, fnt: &lib::u8_fnt::U8Node| {
+            if stage == lib::ConversionStage::LaidOut {
+                print_fnt_layout(fnt);
+            }
+        };
+        let report = lib::convert_openingtitle_between_regions_with_options(
             in_file,
-            out_file,
+            std::io::Cursor::new(Vec::new()),
             from_regions,
             to_regions,
             &conflict_strategies,
+            &lib::ConvertOpeningTitleBetweenRegionsOptions {
+                inspect: Some(&print_layout),
+                dry_run: true,
+                ..options
+            },
+            &lib::HashFileComparator,
         )
-        .context("failed to perform region conversion")
-    })
+        .context("failed to lay out region conversion")?;
+        print_conversion_report(&report);
+        return Ok(());
+    }
+
+    if args.size_matrix {
+        return print_size_matrix(
+            input_filepath,
+            from_regions,
+            to_regions,
+            &conflict_strategies,
+            &options,
+        );
+    }
+
+    if args.dry_run {
+        let in_file = File::open(input_filepath).with_context(|| {
+            format!("couldn't open input file \"{}\"", input_filepath.display())
+        })?;
+        // `--report` needs the output's CRC32, so in that case the
+        // output is built in memory rather than stopping after layout
+        let mut output = std::io::Cursor::new(Vec::new());
+        let report = lib::convert_openingtitle_between_regions_with_options(
+            in_file,
+            &mut output,
+            from_regions,
+            to_regions,
+            &conflict_strategies,
+            &lib::ConvertOpeningTitleBetweenRegionsOptions {
+                dry_run: !args.report,
+                ..options
+            },
+            &lib::HashFileComparator,
+        )
+        .context("failed to perform region conversion")?;
+        print_conversion_report(&report);
+        if args.report {
+            print_report_table_for_output(&report, output.get_ref());
+        }
+        info!("Dry run, so no output file was written");
+        return Ok(());
+    }
+
+    if args.split_regions {
+        // If not specified, default output directory is the input
+        // file's directory
+        let output_dir = match &args.output_file {
+            Some(dirname) => dirname.clone(),
+            None => input_filepath
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_default(),
+        };
+        debug!("Output directory: {output_dir:?}");
+        std::fs::create_dir_all(&output_dir).with_context(|| {
+            format!(
+                "couldn't create output directory \"{}\"",
+                output_dir.display()
+            )
+        })?;
+
+        for region in to_regions {
+            let output_filepath = split_region_output_filepath(input_filepath, &output_dir, region);
+            debug!("Output filepath for {region}: {output_filepath:?}");
+
+            let last_report = RefCell::new(None);
+            run_file_conversion_function(input_filepath, &output_filepath, |in_file, out_file| {
+                let report = lib::convert_openingtitle_between_regions_with_options(
+                    in_file,
+                    out_file,
+                    from_regions,
+                    lib::RegionBitFlags::from(region),
+                    &conflict_strategies,
+                    &options,
+                    &lib::HashFileComparator,
+                )
+                .with_context(|| format!("failed to perform region conversion to {region}"))?;
+                print_conversion_report(&report);
+                last_report.replace(Some(report));
+                Ok(())
+            })?;
+
+            if let Some(report) = last_report.into_inner().filter(|_| args.report) {
+                println!("{}:", output_filepath.display());
+                print_report_table(&report, &output_filepath)?;
+            }
+        }
+
+        return Ok(());
+    }
+
+    // If not specified, default output path is the input path (i.e. overwrite)
+    let output_filepath = match &args.output_file {
+        Some(filename) => filename,
+        None => input_filepath,
+    };
+    debug!(
+        "Output filepath: {:?} ({:?})",
+        output_filepath,
+        output_filepath.canonicalize()
+    );
+
+    // (Any errors here will come up again, with more context, in the
+    // conversion itself)
+    if log::log_enabled!(log::Level::Info) {
+        if let Err(error) = log_filename_delta(input_filepath, from_region_flags, to_regions) {
+            debug!("Couldn't count filename changes: {error:#}");
+        }
+    }
+
+    // (The report is kept around for `--report`, which needs to read
+    // the output file after it's been finalized)
+    let last_report = RefCell::new(None);
+    let verification_data = RefCell::new(Vec::new());
+    run_file_conversion_function(input_filepath, output_filepath, |in_file, out_file| {
+        let convert = |in_file: &mut dyn SeekRead, out_file: &mut dyn SeekWrite| {
+            lib::convert_openingtitle_between_regions_with_options(
+                in_file,
+                out_file,
+                from_regions,
+                to_regions,
+                &conflict_strategies,
+                &options,
+                &lib::HashFileComparator,
+            )
+            .context("failed to perform region conversion")
+        };
+        // With `--also-verify-to`, the same bytes are written to both
+        // paths, so any difference afterward comes from writing them
+        let report = if args.also_verify_to.is_some() {
+            let mut buffer = std::io::Cursor::new(Vec::new());
+            let report = convert(in_file, &mut buffer)?;
+            out_file
+                .write_all(buffer.get_ref())
+                .context("couldn't write output file")?;
+            verification_data.replace(buffer.into_inner());
+            report
+        } else {
+            convert(in_file, out_file)?
+        };
+        print_conversion_report(&report);
+        last_report.replace(Some(report));
+        Ok(())
+    })?;
+
+    if let Some(verify_filepath) = &args.also_verify_to {
+        write_verification_copy(
+            output_filepath,
+            verify_filepath,
+            &verification_data.into_inner(),
+        )?;
+    }
+
+    if let Some(report) = last_report.into_inner().filter(|_| args.report) {
+        print_report_table(&report, output_filepath)?;
+    }
+    if args.compare_compression {
+        print_compression_comparison(output_filepath)?;
+    }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -269,6 +1726,23 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_split_region_output_filepath() -> Result<()> {
+        assert_eq!(
+            split_region_output_filepath(
+                Path::new("in/openingTitle.arc"),
+                Path::new("out"),
+                lib::Region::J
+            ),
+            Path::new("out/openingTitle_J.arc")
+        );
+        assert_eq!(
+            split_region_output_filepath(Path::new("title"), Path::new(""), lib::Region::C),
+            Path::new("title_C")
+        );
+        Ok(())
+    }
+
     mod run_file_conversion_function {
         use super::*;
 
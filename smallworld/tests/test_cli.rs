@@ -7,10 +7,12 @@ use anyhow::Result;
 use assert_cmd::{assert::OutputAssertExt, cargo::CommandCargoExt};
 use assert_fs::{
     assert::PathAssert,
-    fixture::{FileTouch, FileWriteBin},
-    NamedTempFile,
+    fixture::{FileTouch, FileWriteBin, PathChild},
+    NamedTempFile, TempDir,
 };
-use predicates::prelude::predicate;
+use libsmallworld::u8_fnt::{self, U8FileNode, U8Node};
+use libsmallworld::Region;
+use predicates::prelude::{predicate, PredicateBooleanExt};
 
 const BIN_NAME: &str = "smallworld";
 
@@ -417,13 +419,30 @@ fn test_arg_ignore_conflicts_data() -> Result<()> {
         .failure()
         .stderr(predicate::str::contains("conflicting"));
 
+    // --interactive can't prompt without a terminal, so it fails the
+    // same way
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.args(["--to", "e", "--interactive", "-v"])
+        .arg(filepath.path())
+        .stdin(std::process::Stdio::null());
+    cmd.assert().failure().stderr(
+        predicate::str::contains("has no effect").and(predicate::str::contains("conflicting")),
+    );
+
     // With --ignore-conflicts, it succeeds and chooses the EU one over
     // the CN one
     let mut cmd = Command::cargo_bin(BIN_NAME)?;
     cmd.args(["--to", "e"])
         .arg("--ignore-conflicts")
+        .arg("-v")
         .arg(filepath.path());
-    cmd.assert().success();
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Resolved 1 conflict(s)"))
+        .stderr(predicate::str::contains(
+            "\"openingTitle_EU_00_inPress.brlan\" from region P (international, uses EU_00 \
+             filenames) and \"openingTitle_CN_00_inPress.brlan\" from region C have different data",
+        ));
 
     filepath.assert(concat_bytes!(
         b"U\xaa8-\0\0\0 \0\0\x01w\0\0\x01\xa0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
@@ -511,7 +530,9 @@ fn test_arg_ignore_conflicts_filenames() -> Result<()> {
         .args(["--to", "e"])
         .arg("--ignore-conflicts")
         .arg(filepath.path());
-    cmd.assert().success();
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Resolved 1 conflict(s)"));
 
     filepath.assert(concat_bytes!(
         b"U\xaa8-\0\0\0 \0\0\x01w\0\0\x01\xa0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
@@ -538,3 +559,1393 @@ fn test_arg_ignore_conflicts_filenames() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_arg_validate() -> Result<()> {
+    let filepath = NamedTempFile::new("test.arc")?;
+    let data = concat_bytes!(
+        b"U\xaa8-\0\0\0 \0\0\0\r\0\0\0@\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"\x01\0\0\0\0\0\0\0\0\0\0\x01\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+    );
+    filepath.write_binary(data)?;
+
+    // An empty U8 file isn't a valid openingTitle.arc, but it's
+    // structurally fine, and --validate shouldn't touch it
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.arg("--validate").arg(filepath.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("No problems found"));
+
+    filepath.assert(data as &[u8]);
+
+    // Two names the game would treat as the same are both read, and
+    // reported
+    let file = U8Node::File(U8FileNode { offset: 0, size: 0 });
+    let root = U8Node::from([("a".to_owned(), file.clone()), ("A".to_owned(), file)]);
+    let mut data = std::io::Cursor::new(Vec::new());
+    u8_fnt::write(&mut data, &root)?;
+    filepath.write_binary(data.get_ref())?;
+
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.arg("--validate").arg(filepath.path());
+    cmd.assert().failure().stdout(predicate::str::contains(
+        "\"A\" and \"a\" have the same name (case-insensitively)",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_arg_dump_header() -> Result<()> {
+    let filepath = NamedTempFile::new("test.arc")?;
+    filepath.write_binary(concat_bytes!(
+        b"U\xaa8-\0\0\0 \0\0\0\r\0\0\0@\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"\x01\0\0\0\0\0\0\0\0\0\0\x01\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+    ))?;
+
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.arg("--dump-header").arg(filepath.path());
+    cmd.assert().success().stdout(predicate::str::diff(
+        "Magic: 0x55aa382d\n\
+        Root node offset: 0x20\n\
+        Header size: 0xd (ends at 0x2d)\n\
+        Data table offset: 0x40\n\
+        Node count: 1\n\
+        Reserved: [00, 00, 00, 00, 00, 00, 00, 00, 00, 00, 00, 00, 00, 00, 00, 00]\n\
+        Implied size: 0x40 (actual: 0x40)\n",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_arg_check_health() -> Result<()> {
+    let filepath = NamedTempFile::new("test.arc")?;
+
+    // {
+    //     "arc": {
+    //         "anim": {
+    //             "openingTitle_TW_00_inPress.brlan": b"in_press_brlan",
+    //             "openingTitle_TW_00_inTitle.brlan": b"in_title_brlan",
+    //             "openingTitle_TW_00_loopPress.brlan": b"loop_press_brlan",
+    //             "openingTitle_TW_00_outPress.brlan": b"out_press_brlan",
+    //             "some other random thing": b"whatever"},
+    //         "blyt": {
+    //             "openingTitle_TW_00.brlyt": b"brlyt"},
+    //         "timg": {
+    //             "wiiMario_Title_logo_TW.tpl": b"tpl"}}}
+    filepath.write_binary(concat_bytes!(
+        b"U\xaa8-\0\0\0 \0\0\x01w\0\0\x01\xa0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"\x01\0\0\0\0\0\0\0\0\0\0\x0c\x01\0\0\x01\0\0\0\0\0\0\0\x0c\x01\0\0\x05\0\0\0\x01",
+        b"\0\0\0\x08\0\0\0\n\0\0\x01\xa0\0\0\0\x0e\0\0\0+\0\0\x01\xc0\0\0\0\x0e\0\0\0L",
+        b"\0\0\x01\xe0\0\0\0\x10\0\0\0o\0\0\x02\0\0\0\0\x0f\0\0\0\x91\0\0\x02 \0\0\0\x08",
+        b"\x01\0\0\xa9\0\0\0\x01\0\0\0\n\0\0\0\xae\0\0\x02@\0\0\0\x05\x01\0\0\xc7\0\0\0\x01",
+        b"\0\0\0\x0c\0\0\0\xcc\0\0\x02`\0\0\0\x03\0arc\0anim\0openin",
+        b"gTitle_TW_00_inPress.brlan\0openi",
+        b"ngTitle_TW_00_inTitle.brlan\0open",
+        b"ingTitle_TW_00_loopPress.brlan\0o",
+        b"peningTitle_TW_00_outPress.brlan",
+        b"\0some other random thing\0blyt\0op",
+        b"eningTitle_TW_00.brlyt\0timg\0wiiM",
+        b"ario_Title_logo_TW.tpl\0\0\0\0\0\0\0\0\0\0",
+        b"in_press_brlan\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"in_title_brlan\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"loop_press_brlan\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"out_press_brlan\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"whatever\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"brlyt\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"tpl",
+    ))?;
+
+    // The file data is all placeholders, so with magic checks enabled,
+    // they're the only things that should be flagged
+    #[cfg(not(feature = "brlyt"))]
+    let expected_output = predicate::str::contains("No health warnings");
+    #[cfg(feature = "brlyt")]
+    let expected_output = predicate::str::contains("named like a BRLAN")
+        .and(predicate::str::contains("named like a BRLYT"));
+
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.arg("--check-health").arg(filepath.path());
+    cmd.assert().success().stdout(expected_output);
+
+    Ok(())
+}
+
+#[test]
+fn test_arg_print_filenames() -> Result<()> {
+    // No input file needed
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.args(["--print-filenames", "j,c"]);
+    cmd.assert().success().stdout(
+        predicate::str::contains("J:\n")
+            .and(predicate::str::contains(
+                "anim/openingTitle_13_inPress.brlan",
+            ))
+            .and(predicate::str::contains("blyt/openingTitle_CN_00.brlyt"))
+            .and(predicate::str::contains("openingTitle_EU_00").not()),
+    );
+
+    // Defaults to all regions
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.arg("--print-filenames");
+    cmd.assert().success().stdout(
+        predicate::str::contains("P:\n")
+            .and(predicate::str::contains("blyt/openingTitle_EU_00.brlyt"))
+            .and(predicate::str::contains("blyt/openingTitle_TW_00.brlyt")),
+    );
+
+    // But an input file is still required otherwise
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.assert().failure();
+
+    Ok(())
+}
+
+#[test]
+fn test_arg_self_test() -> Result<()> {
+    let filepath = NamedTempFile::new("test.arc")?;
+
+    // {
+    //     "arc": {
+    //         "anim": {
+    //             "openingTitle_EU_00_inPress.brlan": b"in_press_brlan",
+    //             "openingTitle_EU_00_inTitle.brlan": b"in_title_brlan",
+    //             "openingTitle_EU_00_loopPress.brlan": b"loop_press_brlan",
+    //             "openingTitle_EU_00_outPress.brlan": b"out_press_brlan",
+    //             "some other random thing": b"whatever"},
+    //         "blyt": {
+    //             "openingTitle_EU_00.brlyt": b"brlyt"},
+    //         "timg": {
+    //             "wiiMario_Title_logo_local_00.tpl": b"tpl"}}}
+    filepath.write_binary(concat_bytes!(
+        b"U\xaa8-\0\0\0 \0\0\x01}\0\0\x01\xa0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"\x01\0\0\0\0\0\0\0\0\0\0\x0c\x01\0\0\x01\0\0\0\0\0\0\0\x0c\x01\0\0\x05\0\0\0\x01",
+        b"\0\0\0\x08\0\0\0\n\0\0\x01\xa0\0\0\0\x0e\0\0\0+\0\0\x01\xc0\0\0\0\x0e\0\0\0L",
+        b"\0\0\x01\xe0\0\0\0\x10\0\0\0o\0\0\x02\0\0\0\0\x0f\0\0\0\x91\0\0\x02 \0\0\0\x08",
+        b"\x01\0\0\xa9\0\0\0\x01\0\0\0\n\0\0\0\xae\0\0\x02@\0\0\0\x05\x01\0\0\xc7\0\0\0\x01",
+        b"\0\0\0\x0c\0\0\0\xcc\0\0\x02`\0\0\0\x03\0arc\0anim\0openin",
+        b"gTitle_EU_00_inPress.brlan\0openi",
+        b"ngTitle_EU_00_inTitle.brlan\0open",
+        b"ingTitle_EU_00_loopPress.brlan\0o",
+        b"peningTitle_EU_00_outPress.brlan",
+        b"\0some other random thing\0blyt\0op",
+        b"eningTitle_EU_00.brlyt\0timg\0wiiM",
+        b"ario_Title_logo_local_00.tpl\0\0\0\0",
+        b"in_press_brlan\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"in_title_brlan\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"loop_press_brlan\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"out_press_brlan\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"whatever\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"brlyt\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"tpl",
+    ))?;
+
+    // Single-region arc: the self-test should fail and list the
+    // filenames for the other regions
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.arg("--self-test").arg(filepath.path());
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("J: openingTitle_13.brlyt"))
+        .stdout(predicate::str::contains("openingTitle_EU_00").not());
+
+    // After making it region-free, it should pass
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.arg(filepath.path());
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.arg("--self-test").arg(filepath.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Self-test passed"));
+
+    // Without an input file, only the filename table is checked
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.arg("--self-test");
+    cmd.assert().success().stdout("Self-test passed\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_arg_is_region_free() -> Result<()> {
+    let filepath = NamedTempFile::new("test.arc")?;
+
+    // {
+    //     "arc": {
+    //         "anim": {
+    //             "openingTitle_EU_00_inPress.brlan": b"in_press_brlan",
+    //             "openingTitle_EU_00_inTitle.brlan": b"in_title_brlan",
+    //             "openingTitle_EU_00_loopPress.brlan": b"loop_press_brlan",
+    //             "openingTitle_EU_00_outPress.brlan": b"out_press_brlan",
+    //             "some other random thing": b"whatever"},
+    //         "blyt": {
+    //             "openingTitle_EU_00.brlyt": b"brlyt"},
+    //         "timg": {
+    //             "wiiMario_Title_logo_local_00.tpl": b"tpl"}}}
+    filepath.write_binary(concat_bytes!(
+        b"U\xaa8-\0\0\0 \0\0\x01}\0\0\x01\xa0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"\x01\0\0\0\0\0\0\0\0\0\0\x0c\x01\0\0\x01\0\0\0\0\0\0\0\x0c\x01\0\0\x05\0\0\0\x01",
+        b"\0\0\0\x08\0\0\0\n\0\0\x01\xa0\0\0\0\x0e\0\0\0+\0\0\x01\xc0\0\0\0\x0e\0\0\0L",
+        b"\0\0\x01\xe0\0\0\0\x10\0\0\0o\0\0\x02\0\0\0\0\x0f\0\0\0\x91\0\0\x02 \0\0\0\x08",
+        b"\x01\0\0\xa9\0\0\0\x01\0\0\0\n\0\0\0\xae\0\0\x02@\0\0\0\x05\x01\0\0\xc7\0\0\0\x01",
+        b"\0\0\0\x0c\0\0\0\xcc\0\0\x02`\0\0\0\x03\0arc\0anim\0openin",
+        b"gTitle_EU_00_inPress.brlan\0openi",
+        b"ngTitle_EU_00_inTitle.brlan\0open",
+        b"ingTitle_EU_00_loopPress.brlan\0o",
+        b"peningTitle_EU_00_outPress.brlan",
+        b"\0some other random thing\0blyt\0op",
+        b"eningTitle_EU_00.brlyt\0timg\0wiiM",
+        b"ario_Title_logo_local_00.tpl\0\0\0\0",
+        b"in_press_brlan\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"in_title_brlan\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"loop_press_brlan\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"out_press_brlan\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"whatever\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"brlyt\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"tpl",
+    ))?;
+
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.arg("--is-region-free").arg(filepath.path());
+    cmd.assert().code(1).stdout("no\n");
+
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.arg("--readiness").arg(filepath.path());
+    cmd.assert().success().stdout("convertible\n");
+
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.arg(filepath.path());
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.arg("--is-region-free").arg(filepath.path());
+    cmd.assert().success().stdout("yes\n");
+
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.arg("--readiness").arg(filepath.path());
+    cmd.assert().success().stdout("region-free\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_arg_split_regions() -> Result<()> {
+    let in_filepath = NamedTempFile::new("openingTitle.arc")?;
+    let out_dir = TempDir::new()?;
+
+    // {
+    //     "arc": {
+    //         "anim": {
+    //             "openingTitle_TW_00_inPress.brlan": b"in_press_brlan",
+    //             "openingTitle_TW_00_inTitle.brlan": b"in_title_brlan",
+    //             "openingTitle_TW_00_loopPress.brlan": b"loop_press_brlan",
+    //             "openingTitle_TW_00_outPress.brlan": b"out_press_brlan",
+    //             "some other random thing": b"whatever"},
+    //         "blyt": {
+    //             "openingTitle_TW_00.brlyt": b"brlyt"},
+    //         "timg": {
+    //             "wiiMario_Title_logo_TW.tpl": b"tpl"}}}
+    in_filepath.write_binary(concat_bytes!(
+        b"U\xaa8-\0\0\0 \0\0\x01w\0\0\x01\xa0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"\x01\0\0\0\0\0\0\0\0\0\0\x0c\x01\0\0\x01\0\0\0\0\0\0\0\x0c\x01\0\0\x05\0\0\0\x01",
+        b"\0\0\0\x08\0\0\0\n\0\0\x01\xa0\0\0\0\x0e\0\0\0+\0\0\x01\xc0\0\0\0\x0e\0\0\0L",
+        b"\0\0\x01\xe0\0\0\0\x10\0\0\0o\0\0\x02\0\0\0\0\x0f\0\0\0\x91\0\0\x02 \0\0\0\x08",
+        b"\x01\0\0\xa9\0\0\0\x01\0\0\0\n\0\0\0\xae\0\0\x02@\0\0\0\x05\x01\0\0\xc7\0\0\0\x01",
+        b"\0\0\0\x0c\0\0\0\xcc\0\0\x02`\0\0\0\x03\0arc\0anim\0openin",
+        b"gTitle_TW_00_inPress.brlan\0openi",
+        b"ngTitle_TW_00_inTitle.brlan\0open",
+        b"ingTitle_TW_00_loopPress.brlan\0o",
+        b"peningTitle_TW_00_outPress.brlan",
+        b"\0some other random thing\0blyt\0op",
+        b"eningTitle_TW_00.brlyt\0timg\0wiiM",
+        b"ario_Title_logo_TW.tpl\0\0\0\0\0\0\0\0\0\0",
+        b"in_press_brlan\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"in_title_brlan\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"loop_press_brlan\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"out_press_brlan\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"whatever\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"brlyt\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"tpl",
+    ))?;
+
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.arg("--split-regions")
+        .args(["--to", "j,k"])
+        .arg("-o")
+        .arg(out_dir.path())
+        .arg(in_filepath.path());
+    cmd.assert().success();
+
+    let contains = |data: &[u8], needle: &[u8]| data.windows(needle.len()).any(|w| w == needle);
+
+    let j_data = std::fs::read(out_dir.child("openingTitle_J.arc").path())?;
+    assert!(contains(&j_data, b"openingTitle_13.brlyt"));
+    assert!(!contains(&j_data, b"openingTitle_KR_00"));
+
+    let k_data = std::fs::read(out_dir.child("openingTitle_K.arc").path())?;
+    assert!(contains(&k_data, b"openingTitle_KR_00.brlyt"));
+    assert!(!contains(&k_data, b"openingTitle_13"));
+
+    out_dir
+        .child("openingTitle_P.arc")
+        .assert(predicate::path::missing());
+
+    Ok(())
+}
+
+#[test]
+fn test_arg_extract_region() -> Result<()> {
+    let in_filepath = NamedTempFile::new("openingTitle.arc")?;
+    let out_dir = TempDir::new()?;
+
+    // Same as in test_arg_split_regions()
+    in_filepath.write_binary(concat_bytes!(
+        b"U\xaa8-\0\0\0 \0\0\x01w\0\0\x01\xa0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"\x01\0\0\0\0\0\0\0\0\0\0\x0c\x01\0\0\x01\0\0\0\0\0\0\0\x0c\x01\0\0\x05\0\0\0\x01",
+        b"\0\0\0\x08\0\0\0\n\0\0\x01\xa0\0\0\0\x0e\0\0\0+\0\0\x01\xc0\0\0\0\x0e\0\0\0L",
+        b"\0\0\x01\xe0\0\0\0\x10\0\0\0o\0\0\x02\0\0\0\0\x0f\0\0\0\x91\0\0\x02 \0\0\0\x08",
+        b"\x01\0\0\xa9\0\0\0\x01\0\0\0\n\0\0\0\xae\0\0\x02@\0\0\0\x05\x01\0\0\xc7\0\0\0\x01",
+        b"\0\0\0\x0c\0\0\0\xcc\0\0\x02`\0\0\0\x03\0arc\0anim\0openin",
+        b"gTitle_TW_00_inPress.brlan\0openi",
+        b"ngTitle_TW_00_inTitle.brlan\0open",
+        b"ingTitle_TW_00_loopPress.brlan\0o",
+        b"peningTitle_TW_00_outPress.brlan",
+        b"\0some other random thing\0blyt\0op",
+        b"eningTitle_TW_00.brlyt\0timg\0wiiM",
+        b"ario_Title_logo_TW.tpl\0\0\0\0\0\0\0\0\0\0",
+        b"in_press_brlan\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"in_title_brlan\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"loop_press_brlan\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"out_press_brlan\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"whatever\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"brlyt\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"tpl",
+    ))?;
+
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.args(["--extract-region", "w"])
+        .arg("-o")
+        .arg(out_dir.path())
+        .arg(in_filepath.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("openingTitle.brlyt"));
+
+    for (filename, data) in [
+        ("openingTitle_inPress.brlan", "in_press_brlan"),
+        ("openingTitle_inTitle.brlan", "in_title_brlan"),
+        ("openingTitle_loopPress.brlan", "loop_press_brlan"),
+        ("openingTitle_outPress.brlan", "out_press_brlan"),
+        ("openingTitle.brlyt", "brlyt"),
+        ("wiiMario_Title_logo.tpl", "tpl"),
+    ] {
+        out_dir.child(filename).assert(data);
+    }
+
+    // The input file doesn't have any other region's files
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.args(["--extract-region", "j"])
+        .arg("-o")
+        .arg(out_dir.path())
+        .arg(in_filepath.path());
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("openingTitle_13.brlyt"));
+
+    Ok(())
+}
+
+#[test]
+fn test_arg_minimal() -> Result<()> {
+    let filepath = NamedTempFile::new("test.arc")?;
+
+    // {
+    //     "arc": {
+    //         "anim": {
+    //             "openingTitle_TW_00_inPress.brlan": b"in_press_brlan",
+    //             "openingTitle_TW_00_inTitle.brlan": b"in_title_brlan",
+    //             "openingTitle_TW_00_loopPress.brlan": b"loop_press_brlan",
+    //             "openingTitle_TW_00_outPress.brlan": b"out_press_brlan",
+    //             "some other random thing": b"whatever"},
+    //         "blyt": {
+    //             "openingTitle_TW_00.brlyt": b"brlyt"},
+    //         "timg": {
+    //             "wiiMario_Title_logo_TW.tpl": b"tpl"}}}
+    filepath.write_binary(concat_bytes!(
+        b"U\xaa8-\0\0\0 \0\0\x01w\0\0\x01\xa0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"\x01\0\0\0\0\0\0\0\0\0\0\x0c\x01\0\0\x01\0\0\0\0\0\0\0\x0c\x01\0\0\x05\0\0\0\x01",
+        b"\0\0\0\x08\0\0\0\n\0\0\x01\xa0\0\0\0\x0e\0\0\0+\0\0\x01\xc0\0\0\0\x0e\0\0\0L",
+        b"\0\0\x01\xe0\0\0\0\x10\0\0\0o\0\0\x02\0\0\0\0\x0f\0\0\0\x91\0\0\x02 \0\0\0\x08",
+        b"\x01\0\0\xa9\0\0\0\x01\0\0\0\n\0\0\0\xae\0\0\x02@\0\0\0\x05\x01\0\0\xc7\0\0\0\x01",
+        b"\0\0\0\x0c\0\0\0\xcc\0\0\x02`\0\0\0\x03\0arc\0anim\0openin",
+        b"gTitle_TW_00_inPress.brlan\0openi",
+        b"ngTitle_TW_00_inTitle.brlan\0open",
+        b"ingTitle_TW_00_loopPress.brlan\0o",
+        b"peningTitle_TW_00_outPress.brlan",
+        b"\0some other random thing\0blyt\0op",
+        b"eningTitle_TW_00.brlyt\0timg\0wiiM",
+        b"ario_Title_logo_TW.tpl\0\0\0\0\0\0\0\0\0\0",
+        b"in_press_brlan\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"in_title_brlan\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"loop_press_brlan\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"out_press_brlan\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"whatever\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"brlyt\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"tpl",
+    ))?;
+
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.arg("--minimal")
+        .args(["--to", "j"])
+        .arg(filepath.path());
+    cmd.assert().success();
+
+    let contains = |data: &[u8], needle: &[u8]| data.windows(needle.len()).any(|w| w == needle);
+
+    let data = std::fs::read(filepath.path())?;
+    assert!(contains(&data, b"openingTitle_13_inPress.brlan"));
+    assert!(contains(&data, b"openingTitle_13.brlyt"));
+    assert!(contains(&data, b"wiiMario_Title_logo_TW.tpl"));
+    assert!(!contains(&data, b"openingTitle_TW_00"));
+    assert!(!contains(&data, b"some other random thing"));
+    assert!(!contains(&data, b"whatever"));
+
+    Ok(())
+}
+
+#[test]
+fn test_arg_keep_logo() -> Result<()> {
+    let filepath = NamedTempFile::new("test.arc")?;
+    let out_filepath = NamedTempFile::new("out.arc")?;
+
+    // {
+    //     "arc": {
+    //         "anim": {
+    //             "openingTitle_EU_00_inPress.brlan": b"in_press_brlan",
+    //             "openingTitle_EU_00_inTitle.brlan": b"in_title_brlan",
+    //             "openingTitle_EU_00_loopPress.brlan": b"loop_press_brlan",
+    //             "openingTitle_EU_00_outPress.brlan": b"out_press_brlan",
+    //             "some other random thing": b"whatever"},
+    //         "blyt": {
+    //             "openingTitle_EU_00.brlyt": b"brlyt"},
+    //         "timg": {
+    //             "wiiMario_Title_logo_local_00.tpl": b"tpl"}}}
+    filepath.write_binary(concat_bytes!(
+        b"U\xaa8-\0\0\0 \0\0\x01}\0\0\x01\xa0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"\x01\0\0\0\0\0\0\0\0\0\0\x0c\x01\0\0\x01\0\0\0\0\0\0\0\x0c\x01\0\0\x05\0\0\0\x01",
+        b"\0\0\0\x08\0\0\0\n\0\0\x01\xa0\0\0\0\x0e\0\0\0+\0\0\x01\xc0\0\0\0\x0e\0\0\0L",
+        b"\0\0\x01\xe0\0\0\0\x10\0\0\0o\0\0\x02\0\0\0\0\x0f\0\0\0\x91\0\0\x02 \0\0\0\x08",
+        b"\x01\0\0\xa9\0\0\0\x01\0\0\0\n\0\0\0\xae\0\0\x02@\0\0\0\x05\x01\0\0\xc7\0\0\0\x01",
+        b"\0\0\0\x0c\0\0\0\xcc\0\0\x02`\0\0\0\x03\0arc\0anim\0openin",
+        b"gTitle_EU_00_inPress.brlan\0openi",
+        b"ngTitle_EU_00_inTitle.brlan\0open",
+        b"ingTitle_EU_00_loopPress.brlan\0o",
+        b"peningTitle_EU_00_outPress.brlan",
+        b"\0some other random thing\0blyt\0op",
+        b"eningTitle_EU_00.brlyt\0timg\0wiiM",
+        b"ario_Title_logo_local_00.tpl\0\0\0\0",
+        b"in_press_brlan\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"in_title_brlan\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"loop_press_brlan\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"out_press_brlan\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"whatever\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"brlyt\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"tpl",
+    ))?;
+
+    let timg_filenames = |path: &std::path::Path| -> Result<Vec<String>> {
+        let (root, _) = u8_fnt::read(&mut std::fs::File::open(path)?)?;
+        let timg = root.get("/arc/timg").and_then(U8Node::as_folder).unwrap();
+        let mut filenames: Vec<String> = timg.keys().cloned().collect();
+        filenames.sort();
+        Ok(filenames)
+    };
+
+    // Converting to China adds a fallback logo by default...
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.args(["--to", "e,c"])
+        .arg("-o")
+        .arg(out_filepath.path())
+        .arg(filepath.path());
+    cmd.assert().success();
+    assert_eq!(
+        timg_filenames(out_filepath.path())?,
+        [
+            "wiiMario_Title_logo_CN.tpl",
+            "wiiMario_Title_logo_local_00.tpl"
+        ]
+    );
+
+    // ...but not with --keep-logo
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.args(["--to", "e,c"])
+        .arg("--keep-logo")
+        .arg("-o")
+        .arg(out_filepath.path())
+        .arg(filepath.path());
+    cmd.assert().success();
+    assert_eq!(
+        timg_filenames(out_filepath.path())?,
+        ["wiiMario_Title_logo_local_00.tpl"]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_arg_report() -> Result<()> {
+    let filepath = NamedTempFile::new("test.arc")?;
+
+    // {
+    //     "arc": {
+    //         "anim": {
+    //             "openingTitle_TW_00_inPress.brlan": b"in_press_brlan",
+    //             "openingTitle_TW_00_inTitle.brlan": b"in_title_brlan",
+    //             "openingTitle_TW_00_loopPress.brlan": b"loop_press_brlan",
+    //             "openingTitle_TW_00_outPress.brlan": b"out_press_brlan",
+    //             "some other random thing": b"whatever"},
+    //         "blyt": {
+    //             "openingTitle_TW_00.brlyt": b"brlyt"},
+    //         "timg": {
+    //             "wiiMario_Title_logo_TW.tpl": b"tpl"}}}
+    filepath.write_binary(concat_bytes!(
+        b"U\xaa8-\0\0\0 \0\0\x01w\0\0\x01\xa0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"\x01\0\0\0\0\0\0\0\0\0\0\x0c\x01\0\0\x01\0\0\0\0\0\0\0\x0c\x01\0\0\x05\0\0\0\x01",
+        b"\0\0\0\x08\0\0\0\n\0\0\x01\xa0\0\0\0\x0e\0\0\0+\0\0\x01\xc0\0\0\0\x0e\0\0\0L",
+        b"\0\0\x01\xe0\0\0\0\x10\0\0\0o\0\0\x02\0\0\0\0\x0f\0\0\0\x91\0\0\x02 \0\0\0\x08",
+        b"\x01\0\0\xa9\0\0\0\x01\0\0\0\n\0\0\0\xae\0\0\x02@\0\0\0\x05\x01\0\0\xc7\0\0\0\x01",
+        b"\0\0\0\x0c\0\0\0\xcc\0\0\x02`\0\0\0\x03\0arc\0anim\0openin",
+        b"gTitle_TW_00_inPress.brlan\0openi",
+        b"ngTitle_TW_00_inTitle.brlan\0open",
+        b"ingTitle_TW_00_loopPress.brlan\0o",
+        b"peningTitle_TW_00_outPress.brlan",
+        b"\0some other random thing\0blyt\0op",
+        b"eningTitle_TW_00.brlyt\0timg\0wiiM",
+        b"ario_Title_logo_TW.tpl\0\0\0\0\0\0\0\0\0\0",
+        b"in_press_brlan\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"in_title_brlan\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"loop_press_brlan\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"out_press_brlan\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"whatever\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"brlyt\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"tpl",
+    ))?;
+
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.arg("--report").args(["--to", "j"]).arg(filepath.path());
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let output = String::from_utf8(output)?;
+
+    let crc = libsmallworld::crc32(&std::fs::read(filepath.path())?);
+    assert!(output.contains("inPress brlan    W\n"));
+    assert!(output.contains("brlyt            W\n"));
+    assert!(output.contains("Overwritten filenames: none\n"));
+    assert!(output.contains(&format!("Output CRC32: {crc:#010x}\n")));
+
+    Ok(())
+}
+
+#[test]
+fn test_arg_dry_run() -> Result<()> {
+    let dir = TempDir::new()?;
+    let input = dir.child("in.arc");
+
+    // W-only arc, with every regional file sharing the same data
+    let filenames = Region::W.filenames();
+    let file = || U8Node::File(U8FileNode { offset: 0, size: 4 });
+    let root = U8Node::from([(
+        "arc".to_owned(),
+        U8Node::from([
+            (
+                "anim".to_owned(),
+                U8Node::from([
+                    (filenames.in_press_brlan.to_owned(), file()),
+                    (filenames.in_title_brlan.to_owned(), file()),
+                    (filenames.loop_press_brlan.to_owned(), file()),
+                    (filenames.out_press_brlan.to_owned(), file()),
+                ]),
+            ),
+            (
+                "blyt".to_owned(),
+                U8Node::from([(filenames.brlyt.to_owned(), file())]),
+            ),
+        ]),
+    )]);
+    let mut data = std::io::Cursor::new(Vec::new());
+    u8_fnt::write(&mut data, &root)?;
+    let mut data = data.into_inner();
+    data.extend_from_slice(b"data");
+    input.write_binary(&data)?;
+
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.arg("--dry-run").arg("--report").arg(input.path());
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let output = String::from_utf8(output)?;
+    assert_eq!(std::fs::read(input.path())?, data);
+
+    // The report should match a real conversion's
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.arg("--report")
+        .arg(input.path())
+        .arg("-o")
+        .arg(dir.child("out.arc").path());
+    let expected = cmd.assert().success().get_output().stdout.clone();
+    assert_eq!(output, String::from_utf8(expected)?);
+    assert!(output.contains("brlyt            W\n"));
+
+    // Conflicts are still detected
+    let mut root = root;
+    root.get_mut("/arc/blyt")
+        .and_then(U8Node::as_mut_folder)
+        .unwrap()
+        .insert(
+            Region::K.filenames().brlyt.to_owned(),
+            U8Node::File(U8FileNode { offset: 4, size: 4 }),
+        );
+    let mut data = std::io::Cursor::new(Vec::new());
+    u8_fnt::write(&mut data, &root)?;
+    let mut data = data.into_inner();
+    data.extend_from_slice(b"datadiff");
+    input.write_binary(&data)?;
+
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.arg("--dry-run").arg(input.path());
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("conflicting files"));
+
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.args(["--dry-run", "-v", "--ignore-conflicts"])
+        .arg(input.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Resolved 1 conflict(s)"));
+    assert_eq!(std::fs::read(input.path())?, data);
+
+    Ok(())
+}
+
+#[test]
+fn test_arg_compare_compression() -> Result<()> {
+    let filepath = NamedTempFile::new("test.arc")?;
+
+    // {
+    //     "arc": {
+    //         "anim": {
+    //             "openingTitle_TW_00_inPress.brlan": b"in_press_brlan",
+    //             "openingTitle_TW_00_inTitle.brlan": b"in_title_brlan",
+    //             "openingTitle_TW_00_loopPress.brlan": b"loop_press_brlan",
+    //             "openingTitle_TW_00_outPress.brlan": b"out_press_brlan",
+    //             "some other random thing": b"whatever"},
+    //         "blyt": {
+    //             "openingTitle_TW_00.brlyt": b"brlyt"},
+    //         "timg": {
+    //             "wiiMario_Title_logo_TW.tpl": b"tpl"}}}
+    filepath.write_binary(concat_bytes!(
+        b"U\xaa8-\0\0\0 \0\0\x01w\0\0\x01\xa0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"\x01\0\0\0\0\0\0\0\0\0\0\x0c\x01\0\0\x01\0\0\0\0\0\0\0\x0c\x01\0\0\x05\0\0\0\x01",
+        b"\0\0\0\x08\0\0\0\n\0\0\x01\xa0\0\0\0\x0e\0\0\0+\0\0\x01\xc0\0\0\0\x0e\0\0\0L",
+        b"\0\0\x01\xe0\0\0\0\x10\0\0\0o\0\0\x02\0\0\0\0\x0f\0\0\0\x91\0\0\x02 \0\0\0\x08",
+        b"\x01\0\0\xa9\0\0\0\x01\0\0\0\n\0\0\0\xae\0\0\x02@\0\0\0\x05\x01\0\0\xc7\0\0\0\x01",
+        b"\0\0\0\x0c\0\0\0\xcc\0\0\x02`\0\0\0\x03\0arc\0anim\0openin",
+        b"gTitle_TW_00_inPress.brlan\0openi",
+        b"ngTitle_TW_00_inTitle.brlan\0open",
+        b"ingTitle_TW_00_loopPress.brlan\0o",
+        b"peningTitle_TW_00_outPress.brlan",
+        b"\0some other random thing\0blyt\0op",
+        b"eningTitle_TW_00.brlyt\0timg\0wiiM",
+        b"ario_Title_logo_TW.tpl\0\0\0\0\0\0\0\0\0\0",
+        b"in_press_brlan\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"in_title_brlan\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"loop_press_brlan\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"out_press_brlan\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"whatever\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"brlyt\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        b"tpl",
+    ))?;
+
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.arg("--compare-compression").arg(filepath.path());
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let output = String::from_utf8(output)?;
+
+    // The output file itself isn't compressed
+    let data = std::fs::read(filepath.path())?;
+    assert!(data.starts_with(b"U\xaa8-"));
+
+    let compressed_len = libsmallworld::yaz0::compress(&data).len();
+    assert!(output.contains(&format!("Uncompressed: {:#x} bytes\n", data.len())));
+    assert!(output.contains(&format!("Yaz0:         {compressed_len:#x} bytes (")));
+
+    Ok(())
+}
+
+#[test]
+fn test_arg_max_size() -> Result<()> {
+    let dir = TempDir::new()?;
+    let input = dir.child("in.arc");
+    let output = dir.child("out.arc");
+
+    // K-only arc, with every regional file sharing the same data
+    let filenames = Region::K.filenames();
+    let file = || U8Node::File(U8FileNode { offset: 0, size: 4 });
+    let root = U8Node::from([(
+        "arc".to_owned(),
+        U8Node::from([
+            (
+                "anim".to_owned(),
+                U8Node::from([
+                    (filenames.in_press_brlan.to_owned(), file()),
+                    (filenames.in_title_brlan.to_owned(), file()),
+                    (filenames.loop_press_brlan.to_owned(), file()),
+                    (filenames.out_press_brlan.to_owned(), file()),
+                ]),
+            ),
+            (
+                "blyt".to_owned(),
+                U8Node::from([(filenames.brlyt.to_owned(), file())]),
+            ),
+        ]),
+    )]);
+    let mut data = std::io::Cursor::new(Vec::new());
+    u8_fnt::write(&mut data, &root)?;
+    let mut data = data.into_inner();
+    data.extend_from_slice(b"data");
+    input.write_binary(&data)?;
+    output.write_binary(b"old output")?;
+
+    // The region-free output is bigger than the input, so this is too small
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.arg(input.path())
+        .arg("-o")
+        .arg(output.path())
+        .arg("--max-output-size")
+        .arg(data.len().to_string());
+    cmd.assert()
+        .failure()
+        .stderr(
+            predicate::str::contains("output would be").and(predicate::str::contains(format!(
+                "the maximum is {:#x}",
+                data.len()
+            ))),
+        );
+    output.assert(&b"old output"[..]);
+
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.arg(input.path())
+        .arg("-o")
+        .arg(output.path())
+        .args(["--max-size", "1048576"]);
+    cmd.assert().success();
+    assert!(std::fs::read(output.path())?.starts_with(b"U\xaa8-"));
+
+    Ok(())
+}
+
+#[test]
+fn test_arg_canonical_region_not_in_from() -> Result<()> {
+    let filepath = NamedTempFile::new("test.arc")?;
+    filepath.touch()?;
+
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.args(["--from", "p,j", "--canonical-region", "e"])
+        .arg(filepath.path());
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "`--canonical-region` must be one of the `--from` regions",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_arg_expect_sha() -> Result<()> {
+    let filepath = NamedTempFile::new("test.arc")?;
+    filepath.touch()?;
+
+    // Matching (case-insensitively): conversion goes ahead, and fails
+    // only because the file is empty
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.args([
+        "--expect-sha",
+        "E3B0C44298FC1C149AFBF4C8996FB92427AE41E4649B934CA495991B7852B855",
+    ])
+    .arg(filepath.path());
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid U8 file"));
+
+    // Not matching: the actual hash is reported
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.args(["--expect-sha", &"0".repeat(64)])
+        .arg(filepath.path());
+    cmd.assert().failure().stderr(
+        predicate::str::contains(
+            "SHA-256 is e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+        )
+        .and(predicate::str::contains("invalid U8 file").not()),
+    );
+
+    // Not a hash at all
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.args(["--expect-sha", "abc"]).arg(filepath.path());
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("64 hexadecimal digits"));
+
+    Ok(())
+}
+
+#[test]
+fn test_arg_timings() -> Result<()> {
+    let filepath = NamedTempFile::new("test.arc")?;
+
+    // P-only arc, with every regional file sharing the same data
+    let filenames = Region::P.filenames();
+    let file = || U8Node::File(U8FileNode { offset: 0, size: 4 });
+    let root = U8Node::from([(
+        "arc".to_owned(),
+        U8Node::from([
+            (
+                "anim".to_owned(),
+                U8Node::from([
+                    (filenames.in_press_brlan.to_owned(), file()),
+                    (filenames.in_title_brlan.to_owned(), file()),
+                    (filenames.loop_press_brlan.to_owned(), file()),
+                    (filenames.out_press_brlan.to_owned(), file()),
+                ]),
+            ),
+            (
+                "blyt".to_owned(),
+                U8Node::from([(filenames.brlyt.to_owned(), file())]),
+            ),
+        ]),
+    )]);
+    let mut data = std::io::Cursor::new(Vec::new());
+    u8_fnt::write(&mut data, &root)?;
+    let mut data = data.into_inner();
+    data.extend_from_slice(b"data");
+    filepath.write_binary(&data)?;
+
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.arg("--timings").arg(filepath.path());
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let output = String::from_utf8(output)?;
+
+    let steps: Vec<&str> = output
+        .lines()
+        .filter(|line| line.ends_with(" ms"))
+        .filter_map(|line| line.split_whitespace().next())
+        .collect();
+    assert_eq!(
+        steps,
+        [
+            "read_fnt",
+            "remove_regional_files",
+            "check_conflicts",
+            "select_regional_files",
+            "add_filenames",
+            "lay_out_fat",
+            "write_fnt",
+            "write_fat",
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_arg_also_verify_to() -> Result<()> {
+    let dir = TempDir::new()?;
+    let input = dir.child("in.arc");
+
+    // E-only arc, with every regional file sharing the same data
+    let filenames = Region::E.filenames();
+    let file = || U8Node::File(U8FileNode { offset: 0, size: 4 });
+    let root = U8Node::from([(
+        "arc".to_owned(),
+        U8Node::from([
+            (
+                "anim".to_owned(),
+                U8Node::from([
+                    (filenames.in_press_brlan.to_owned(), file()),
+                    (filenames.in_title_brlan.to_owned(), file()),
+                    (filenames.loop_press_brlan.to_owned(), file()),
+                    (filenames.out_press_brlan.to_owned(), file()),
+                ]),
+            ),
+            (
+                "blyt".to_owned(),
+                U8Node::from([(filenames.brlyt.to_owned(), file())]),
+            ),
+        ]),
+    )]);
+    let mut data = std::io::Cursor::new(Vec::new());
+    u8_fnt::write(&mut data, &root)?;
+    let mut data = data.into_inner();
+    data.extend_from_slice(b"data");
+    input.write_binary(&data)?;
+
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.arg(input.path())
+        .arg("-o")
+        .arg(dir.child("out.arc").path())
+        .arg("--also-verify-to")
+        .arg(dir.child("copy.arc").path());
+    cmd.assert().success();
+
+    let output = std::fs::read(dir.child("out.arc").path())?;
+    assert!(output.starts_with(b"U\xaa8-"));
+    assert_ne!(output, data);
+    assert_eq!(std::fs::read(dir.child("copy.arc").path())?, output);
+
+    // Doesn't make sense with more than one output file
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.arg(input.path())
+        .arg("--split-regions")
+        .arg("--also-verify-to")
+        .arg(dir.child("copy.arc").path());
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+
+    Ok(())
+}
+
+#[test]
+fn test_directory_input() -> Result<()> {
+    let in_dir = TempDir::new()?;
+    let out_dir = TempDir::new()?;
+
+    // Single-region arcs, with every regional file sharing the same data
+    let make_arc = |region: Region| -> Result<Vec<u8>> {
+        let filenames = region.filenames();
+        let file = || U8Node::File(U8FileNode { offset: 0, size: 4 });
+        let root = U8Node::from([(
+            "arc".to_owned(),
+            U8Node::from([
+                (
+                    "anim".to_owned(),
+                    U8Node::from([
+                        (filenames.in_press_brlan.to_owned(), file()),
+                        (filenames.in_title_brlan.to_owned(), file()),
+                        (filenames.loop_press_brlan.to_owned(), file()),
+                        (filenames.out_press_brlan.to_owned(), file()),
+                    ]),
+                ),
+                (
+                    "blyt".to_owned(),
+                    U8Node::from([(filenames.brlyt.to_owned(), file())]),
+                ),
+            ]),
+        )]);
+        let mut data = std::io::Cursor::new(Vec::new());
+        u8_fnt::write(&mut data, &root)?;
+        let mut data = data.into_inner();
+        data.extend_from_slice(b"data");
+        Ok(data)
+    };
+    let regions = [Region::P, Region::E, Region::J, Region::K];
+    for (i, region) in regions.into_iter().enumerate() {
+        in_dir
+            .child(format!("{i}.arc"))
+            .write_binary(&make_arc(region)?)?;
+    }
+    in_dir.child("notes.txt").write_binary(b"not an arc")?;
+
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.arg(in_dir.path())
+        .arg("-o")
+        .arg(out_dir.path())
+        .args(["--jobs", "3"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let output = String::from_utf8(output)?;
+
+    // Reported in order of filename, regardless of which finished first
+    let converted: Vec<_> = output
+        .lines()
+        .filter_map(|line| line.strip_prefix("Converted "))
+        .collect();
+    assert_eq!(converted.len(), regions.len());
+    for (i, line) in converted.iter().enumerate() {
+        assert!(line.ends_with(&format!("{i}.arc")));
+        let data = std::fs::read(out_dir.child(format!("{i}.arc")).path())?;
+        let needle = Region::C.filenames().brlyt.as_bytes();
+        assert!(data.windows(needle.len()).any(|w| w == needle));
+    }
+    out_dir
+        .child("notes.txt")
+        .assert(predicate::path::missing());
+
+    // One bad arc fails the whole run, but the others are still converted
+    in_dir.child("1.arc").write_binary(b"broken")?;
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.arg(in_dir.path()).arg("-o").arg(out_dir.path());
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("0.arc").and(predicate::str::contains("3.arc")))
+        .stderr(
+            predicate::str::contains("1.arc")
+                .and(predicate::str::contains("failed to convert 1 of 4 arc(s)")),
+        );
+
+    // Options that only make sense for a single file are rejected
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.arg(in_dir.path()).arg("--report");
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "`--report` can't be used when the input is a directory",
+    ));
+
+    // ...but a "Callback" conflict strategy from the config file just
+    // falls back to failing on conflicts
+    in_dir.child("1.arc").write_binary(&make_arc(Region::E)?)?;
+    let config = NamedTempFile::new("smallworld.toml")?;
+    config.write_binary(b"conflict_strategy = \"Callback\"\n")?;
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.arg(in_dir.path())
+        .arg("-o")
+        .arg(out_dir.path())
+        .arg("--config")
+        .arg(config.path());
+    cmd.assert().success();
+
+    Ok(())
+}
+
+#[test]
+fn test_large_arc_in_place() -> Result<()> {
+    const BRLYT_SIZE: u32 = 0x2000000;
+
+    let filepath = NamedTempFile::new("test.arc")?;
+
+    // P-only arc, with tiny brlans and a 32 MiB brlyt
+    let filenames = Region::P.filenames();
+    let file = |offset, size| U8Node::File(U8FileNode { offset, size });
+    let root = U8Node::from([(
+        "arc".to_owned(),
+        U8Node::from([
+            (
+                "anim".to_owned(),
+                U8Node::from([
+                    (filenames.in_press_brlan.to_owned(), file(0x00, 0x10)),
+                    (filenames.in_title_brlan.to_owned(), file(0x20, 0x10)),
+                    (filenames.loop_press_brlan.to_owned(), file(0x40, 0x10)),
+                    (filenames.out_press_brlan.to_owned(), file(0x60, 0x10)),
+                ]),
+            ),
+            (
+                "blyt".to_owned(),
+                U8Node::from([(filenames.brlyt.to_owned(), file(0x80, BRLYT_SIZE))]),
+            ),
+        ]),
+    )]);
+
+    let mut data = std::io::Cursor::new(Vec::new());
+    u8_fnt::write(&mut data, &root)?;
+    let mut data = data.into_inner();
+    data.resize(data.len() + 0x80, 0);
+    let brlyt_data: Vec<u8> = (0..BRLYT_SIZE).map(|i| (i % 251) as u8).collect();
+    data.extend_from_slice(&brlyt_data);
+    filepath.write_binary(&data)?;
+    drop(data);
+
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.arg(filepath.path());
+    cmd.assert().success();
+
+    // The temporary file should've been moved over the input file
+    let mut tmp_filepath = filepath.path().as_os_str().to_owned();
+    tmp_filepath.push(".smallworld-tmp");
+    assert!(!std::path::Path::new(&tmp_filepath).exists());
+
+    let data = std::fs::read(filepath.path())?;
+    let (out_root, out_data_offset) = u8_fnt::read(&mut std::io::Cursor::new(&data))?;
+    for (_, filenames) in Region::all_filenames() {
+        let path = format!("/arc/blyt/{}", filenames.brlyt);
+        let Some(U8Node::File(node)) = out_root.get(&path) else {
+            panic!("{path} not found");
+        };
+        assert_eq!(node.size, BRLYT_SIZE);
+        let start = (out_data_offset + node.offset) as usize;
+        assert!(data[start..start + BRLYT_SIZE as usize] == brlyt_data);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_arg_print_layout() -> Result<()> {
+    let filepath = NamedTempFile::new("test.arc")?;
+
+    // P-only arc, where two of the brlans share data, and with a gap
+    // before the brlyt
+    let filenames = Region::P.filenames();
+    let file = |offset, size| U8Node::File(U8FileNode { offset, size });
+    let root = U8Node::from([(
+        "arc".to_owned(),
+        U8Node::from([
+            (
+                "anim".to_owned(),
+                U8Node::from([
+                    (filenames.in_press_brlan.to_owned(), file(0x00, 0x10)),
+                    (filenames.in_title_brlan.to_owned(), file(0x20, 0x10)),
+                    (filenames.loop_press_brlan.to_owned(), file(0x40, 0x10)),
+                    (filenames.out_press_brlan.to_owned(), file(0x40, 0x10)),
+                ]),
+            ),
+            (
+                "blyt".to_owned(),
+                U8Node::from([(filenames.brlyt.to_owned(), file(0x80, 0x10))]),
+            ),
+        ]),
+    )]);
+
+    let mut data = std::io::Cursor::new(Vec::new());
+    u8_fnt::write(&mut data, &root)?;
+    let mut data = data.into_inner();
+    data.resize(data.len() + 0x90, 0);
+    filepath.write_binary(&data)?;
+
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.args(["--to", "p,j"])
+        .arg("--print-layout")
+        .arg(filepath.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "0x00000040 0x00000010 /arc/anim/openingTitle_13_outPress.brlan\n",
+        ))
+        .stdout(predicate::str::contains(
+            "0x00000060 0x00000010 /arc/blyt/openingTitle_13.brlyt\n",
+        ))
+        .stdout(predicate::str::ends_with("Total data size: 0x70\n"));
+
+    // Nothing should've been written
+    assert_eq!(std::fs::read(filepath.path())?, data);
+
+    Ok(())
+}
+
+#[test]
+fn test_arg_config() -> Result<()> {
+    let dir = TempDir::new()?;
+    let input = dir.child("in.arc");
+    let output = dir.child("out.arc");
+
+    // P-only arc, with every regional file sharing the same data
+    let filenames = Region::P.filenames();
+    let file = || U8Node::File(U8FileNode { offset: 0, size: 4 });
+    let root = U8Node::from([(
+        "arc".to_owned(),
+        U8Node::from([
+            (
+                "anim".to_owned(),
+                U8Node::from([
+                    (filenames.in_press_brlan.to_owned(), file()),
+                    (filenames.in_title_brlan.to_owned(), file()),
+                    (filenames.loop_press_brlan.to_owned(), file()),
+                    (filenames.out_press_brlan.to_owned(), file()),
+                ]),
+            ),
+            (
+                "blyt".to_owned(),
+                U8Node::from([(filenames.brlyt.to_owned(), file())]),
+            ),
+        ]),
+    )]);
+    let mut data = std::io::Cursor::new(Vec::new());
+    u8_fnt::write(&mut data, &root)?;
+    let mut data = data.into_inner();
+    data.extend_from_slice(b"data");
+    input.write_binary(&data)?;
+
+    let has_brlyt = |region: Region| -> Result<bool> {
+        let (root, _) = u8_fnt::read(&mut std::fs::File::open(output.path())?)?;
+        Ok(root
+            .get(&format!("/arc/blyt/{}", region.filenames().brlyt))
+            .is_some())
+    };
+
+    // Read from the current directory by default
+    dir.child("smallworld.toml")
+        .write_binary(b"from = [\"P\"]\nto = [\"J\"]\n")?;
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.current_dir(dir.path())
+        .arg(input.path())
+        .arg("-o")
+        .arg(output.path());
+    cmd.assert().success();
+    assert!(has_brlyt(Region::J)?);
+    assert!(!has_brlyt(Region::K)?);
+
+    // The command line takes precedence
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.current_dir(dir.path())
+        .arg(input.path())
+        .args(["--to", "k"])
+        .arg("-o")
+        .arg(output.path());
+    cmd.assert().success();
+    assert!(!has_brlyt(Region::J)?);
+    assert!(has_brlyt(Region::K)?);
+
+    // An explicit path
+    let config = dir.child("other.toml");
+    config.write_binary(b"to = [\"W\", \"C\"]\nconflict_strategy = \"Overwrite\"\n")?;
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.arg(input.path())
+        .arg("--config")
+        .arg(config.path())
+        .arg("-o")
+        .arg(output.path());
+    cmd.assert().success();
+    assert!(has_brlyt(Region::W)?);
+    assert!(!has_brlyt(Region::J)?);
+
+    // Typos are caught rather than ignored
+    config.write_binary(b"too = [\"W\"]\n")?;
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.arg(input.path()).arg("--config").arg(config.path());
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("couldn't parse config file"));
+
+    Ok(())
+}
+
+#[test]
+fn test_arg_size_matrix() -> Result<()> {
+    let filepath = NamedTempFile::new("test.arc")?;
+
+    // E-only arc, with every regional file sharing the same data
+    let filenames = Region::E.filenames();
+    let file = || U8Node::File(U8FileNode { offset: 0, size: 4 });
+    let root = U8Node::from([(
+        "arc".to_owned(),
+        U8Node::from([
+            (
+                "anim".to_owned(),
+                U8Node::from([
+                    (filenames.in_press_brlan.to_owned(), file()),
+                    (filenames.in_title_brlan.to_owned(), file()),
+                    (filenames.loop_press_brlan.to_owned(), file()),
+                    (filenames.out_press_brlan.to_owned(), file()),
+                ]),
+            ),
+            (
+                "blyt".to_owned(),
+                U8Node::from([(filenames.brlyt.to_owned(), file())]),
+            ),
+        ]),
+    )]);
+    let mut data = std::io::Cursor::new(Vec::new());
+    u8_fnt::write(&mut data, &root)?;
+    let mut data = data.into_inner();
+    data.extend_from_slice(b"data");
+    filepath.write_binary(&data)?;
+
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.arg("--size-matrix")
+        .args(["--to", "e,j,k"])
+        .arg(filepath.path());
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let output = String::from_utf8(output)?;
+
+    let rows: Vec<Vec<&str>> = output
+        .lines()
+        .skip(1)
+        .map(|line| line.split_whitespace().collect())
+        .collect();
+    let subsets: Vec<&str> = rows.iter().map(|row| row[0]).collect();
+    assert_eq!(subsets, ["E", "J", "K", "E,J", "E,K", "J,K", "E,J,K"]);
+    assert_eq!(rows.last().unwrap()[2], "100.0%");
+
+    // More regions means more filenames
+    let size = |row: &Vec<&str>| u64::from_str_radix(&row[1][2..], 16).unwrap();
+    assert!(size(&rows[0]) < size(&rows[3]));
+    assert!(size(&rows[3]) < size(&rows[6]));
+
+    // Nothing is written
+    assert_eq!(std::fs::read(filepath.path())?, data);
+
+    Ok(())
+}
+
+#[test]
+fn test_arg_template() -> Result<()> {
+    let data_dir = TempDir::new()?;
+    let filenames = Region::J.filenames();
+    for filename in [
+        filenames.in_press_brlan,
+        filenames.in_title_brlan,
+        filenames.loop_press_brlan,
+        filenames.out_press_brlan,
+    ] {
+        data_dir.child(filename).write_binary(filename.as_bytes())?;
+    }
+    data_dir
+        .child("blyt")
+        .child(filenames.brlyt)
+        .write_binary(b"brlyt")?;
+    data_dir
+        .child("timg")
+        .child("wiiMario_Title_logo_JP.tpl")
+        .write_binary(b"tpl")?;
+    let out_file = NamedTempFile::new("out.arc")?;
+
+    // Missing files for --to regions should fail, without touching an
+    // existing output file
+    out_file.write_binary(b"existing")?;
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.arg("--template")
+        .arg("--data-dir")
+        .arg(data_dir.path())
+        .arg("--to")
+        .arg("J,K")
+        .arg(out_file.path());
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains(Region::K.filenames().brlyt));
+    out_file.assert(&b"existing"[..]);
+
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.arg("--template")
+        .arg("--data-dir")
+        .arg(data_dir.path())
+        .arg("--to")
+        .arg("J")
+        .arg(out_file.path());
+    cmd.assert().success();
+
+    let mut in_file = std::fs::File::open(out_file.path())?;
+    let (root, _) = u8_fnt::read(&mut in_file)?;
+    let timg = root.get("/arc/timg").and_then(U8Node::as_folder).unwrap();
+    assert_eq!(
+        timg.keys().collect::<Vec<_>>(),
+        ["wiiMario_Title_logo_JP.tpl"]
+    );
+    assert_eq!(
+        root.get("/arc/anim")
+            .and_then(U8Node::as_folder)
+            .unwrap()
+            .len(),
+        4
+    );
+    assert!(root
+        .get(&format!("/arc/blyt/{}", filenames.brlyt))
+        .is_some());
+
+    // The output should be a valid openingTitle.arc that can be converted
+    let mut cmd = Command::cargo_bin(BIN_NAME)?;
+    cmd.arg(out_file.path())
+        .arg("--from")
+        .arg("J")
+        .arg("--to")
+        .arg("all");
+    cmd.assert().success();
+
+    Ok(())
+}